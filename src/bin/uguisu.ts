@@ -3,10 +3,12 @@ import { command as root } from './uguisu/root.js';
 import { command as _new } from './uguisu/new.js';
 import { command as run } from './uguisu/run.js';
 import { command as check } from './uguisu/check.js';
+import { command as builtins } from './uguisu/builtins.js';
+import { command as fmt } from './uguisu/fmt.js';
 
 const args = process.argv.slice(2);
 
-let sub: 'new' | 'run' | 'check' | null;
+let sub: 'new' | 'run' | 'check' | 'builtins' | 'fmt' | null;
 if (args.length > 0) {
     switch (args[0]) {
         case 'new': {
@@ -21,6 +23,14 @@ if (args.length > 0) {
             sub = 'check';
             break;
         }
+        case 'builtins': {
+            sub = 'builtins';
+            break;
+        }
+        case 'fmt': {
+            sub = 'fmt';
+            break;
+        }
         default: {
             sub = null;
         }
@@ -42,6 +52,14 @@ switch (sub) {
         check(args.slice(1));
         break;
     }
+    case 'builtins': {
+        builtins(args.slice(1));
+        break;
+    }
+    case 'fmt': {
+        fmt(args.slice(1));
+        break;
+    }
     default: {
         root(args);
         break;
@@ -0,0 +1,59 @@
+import { listBuiltins } from '../../lib/index.js';
+
+type Match = {
+    help: boolean,
+    free: string[],
+};
+
+function getopts(args: string[]): Match {
+    const match: Match = {
+        help: false,
+        free: [],
+    };
+
+    for (const arg of args) {
+        if (arg === '-h' || arg === '--help') {
+            match.help = true;
+        }
+        else if (arg.startsWith('-')) {
+            throw `unknown option: ${arg}`;
+        }
+        else {
+            match.free.push(arg);
+        }
+    }
+
+    return match;
+}
+
+function showHelp() {
+    const lines = [
+        'Usage: uguisu builtins [options]',
+        '',
+        'Examples:',
+        '    uguisu builtins',
+        '',
+        'Options:',
+        '    -h, --help          Print help message.',
+    ];
+    console.log(lines.join('\n'));
+}
+
+export function command(args: string[]) {
+    let match;
+    try {
+        match = getopts(args);
+    } catch (err) {
+        console.log(err);
+        return;
+    }
+
+    if (match.help) {
+        showHelp();
+        return;
+    }
+
+    for (const info of listBuiltins()) {
+        console.log(`${info.name}(${info.params.join(', ')}): ${info.returnType}`);
+    }
+}
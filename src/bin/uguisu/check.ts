@@ -2,12 +2,14 @@ import { Uguisu } from '../../lib/index.js';
 
 type Match = {
     help: boolean,
+    warnEndlessLoop: boolean,
     free: string[],
 };
 
 function getopts(args: string[]): Match {
     const match: Match = {
         help: false,
+        warnEndlessLoop: false,
         free: [],
     };
 
@@ -15,6 +17,9 @@ function getopts(args: string[]): Match {
         if (arg === '-h' || arg === '--help') {
             match.help = true;
         }
+        else if (arg === '--warn-endless-loop') {
+            match.warnEndlessLoop = true;
+        }
         else if (arg.startsWith('-')) {
             throw `unknown option: ${arg}`;
         }
@@ -32,8 +37,11 @@ function showHelp() {
         '',
         'Examples:',
         '    uguisu check <projectDir>',
+        '    uguisu check --warn-endless-loop <projectDir>',
         '',
         'Options:',
+        '        --warn-endless-loop',
+        '                        Report the loop that has no `break` or `return`.',
         '    -h, --help          Print help message.',
     ];
     console.log(lines.join('\n'));
@@ -63,7 +71,8 @@ export function command(args: string[]) {
     // lint
     try {
         const uguisu = new Uguisu();
-        uguisu.check(dirPath);
+        uguisu.check(dirPath, { warnEndlessLoop: match.warnEndlessLoop });
+        console.log('OK');
     }
     catch (e) {
         console.log(e);
@@ -0,0 +1,86 @@
+import fs from 'fs';
+import { formatCode } from '../../lib/index.js';
+
+type Match = {
+    help: boolean,
+    write: boolean,
+    free: string[],
+};
+
+function getopts(args: string[]): Match {
+    const match: Match = {
+        help: false,
+        write: false,
+        free: [],
+    };
+
+    for (const arg of args) {
+        if (arg === '-h' || arg === '--help') {
+            match.help = true;
+        }
+        else if (arg === '-w' || arg === '--write') {
+            match.write = true;
+        }
+        else if (arg.startsWith('-')) {
+            throw `unknown option: ${arg}`;
+        }
+        else {
+            match.free.push(arg);
+        }
+    }
+
+    return match;
+}
+
+function showHelp() {
+    const lines = [
+        'Usage: uguisu fmt [options] [filePath]',
+        '',
+        'Examples:',
+        '    uguisu fmt <filePath>',
+        '    uguisu fmt --write <filePath>',
+        '',
+        'Options:',
+        '    -h, --help          Print help message.',
+        '    -w, --write         Overwrite the file with the formatted code.',
+    ];
+    console.log(lines.join('\n'));
+}
+
+export function command(args: string[]) {
+    let match;
+    try {
+        match = getopts(args);
+    } catch (err) {
+        console.log(err);
+        return;
+    }
+
+    if (match.help) {
+        showHelp();
+        return;
+    }
+
+    if (match.free.length == 0) {
+        showHelp();
+        return;
+    }
+
+    const filePath = match.free[0];
+
+    // format
+    try {
+        const sourceCode = fs.readFileSync(filePath, { encoding: 'utf8' });
+        const formatted = formatCode(sourceCode, filePath);
+        if (match.write) {
+            fs.writeFileSync(filePath, formatted, { encoding: 'utf8' });
+        } else {
+            process.stdout.write(formatted);
+        }
+    }
+    catch (e) {
+        console.log(e);
+        process.exitCode = -1;
+        process.exit();
+    }
+}
@@ -42,6 +42,9 @@ function showHelp() {
         '    uguisu run <projectDir>',
         '    uguisu check <projectDir>',
         '    uguisu run --skip-check <projectDir>',
+        '    uguisu run - < main.ug',
+        '    uguisu builtins',
+        '    uguisu fmt <filePath>',
         '    uguisu <command> -h',
         '    uguisu -v',
         '',
@@ -53,6 +56,8 @@ function showHelp() {
         '    new                 Create a new uguisu project.',
         '    run                 Run a uguisu project.',
         '    check               Perform the check for a project.',
+        '    builtins            List the builtin functions.',
+        '    fmt                 Format a source file.',
     ];
     console.log(lines.join('\n'));
 }
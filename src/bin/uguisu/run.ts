@@ -1,8 +1,15 @@
-import { Uguisu } from '../../lib/index.js';
+import fs from 'fs';
+import { RunOptions, Uguisu } from '../../lib/index.js';
 
 type Match = {
     help: boolean,
     skipCheck: boolean,
+    dumpAst: boolean,
+    dumpHir: boolean,
+    trace: boolean,
+    time: boolean,
+    warnEndlessLoop: boolean,
+    stdin: boolean,
     free: string[],
 };
 
@@ -10,6 +17,12 @@ function getopts(args: string[]): Match {
     const match: Match = {
         help: false,
         skipCheck: false,
+        dumpAst: false,
+        dumpHir: false,
+        trace: false,
+        time: false,
+        warnEndlessLoop: false,
+        stdin: false,
         free: [],
     };
 
@@ -17,9 +30,27 @@ function getopts(args: string[]): Match {
         if (arg === '-h' || arg === '--help') {
             match.help = true;
         }
-        if (arg === '--skip-check') {
+        else if (arg === '--skip-check') {
             match.skipCheck = true;
         }
+        else if (arg === '--dump-ast') {
+            match.dumpAst = true;
+        }
+        else if (arg === '--dump-hir') {
+            match.dumpHir = true;
+        }
+        else if (arg === '--trace') {
+            match.trace = true;
+        }
+        else if (arg === '--time') {
+            match.time = true;
+        }
+        else if (arg === '--warn-endless-loop') {
+            match.warnEndlessLoop = true;
+        }
+        else if (arg === '-' || arg === '--stdin') {
+            match.stdin = true;
+        }
         else if (arg.startsWith('-')) {
             throw `unknown option: ${arg}`;
         }
@@ -38,14 +69,34 @@ function showHelp() {
         'Examples:',
         '    uguisu run <projectDir>',
         '    uguisu run --skip-check <projectDir>',
+        '    uguisu run - < main.ug',
+        '    cat main.ug | uguisu run',
+        '    uguisu run --dump-ast <projectDir>',
         '',
         'Options:',
         '        --skip-check    Skip the static checking phase.',
+        '        --dump-ast      Print the AST. The program is not run unless --dump-hir is specified.',
+        '        --dump-hir      Print the analysis result before running.',
+        '        --trace         Print the trace of the analysis and the execution.',
+        '        --time          Print the duration of each phase (parse, analyze, run).',
+        '        --warn-endless-loop',
+        '                        Report the loop that has no `break` or `return`.',
+        '    -,  --stdin         Read the program from the standard input.',
+        '                        It is also read when no projectDir is given and the input is not a terminal.',
         '    -h, --help          Print help message.',
     ];
     console.log(lines.join('\n'));
 }
 
+/**
+ * Read the whole program from the file descriptor until EOF and run it.
+ * The standard input is used by default.
+*/
+export function runStdin(uguisu: Uguisu, opts: RunOptions, fd: number = process.stdin.fd): number | undefined {
+    const sourceCode = fs.readFileSync(fd, { encoding: 'utf8' });
+    return uguisu.runSource(sourceCode, opts);
+}
+
 export function command(args: string[]) {
     let match;
     try {
@@ -60,21 +111,37 @@ export function command(args: string[]) {
         return;
     }
 
-    if (match.free.length == 0) {
-        showHelp();
-        return;
+    if (!match.stdin && match.free.length == 0) {
+        // read the program from the pipe if no project is specified
+        if (process.stdin.isTTY) {
+            showHelp();
+            return;
+        }
+        match.stdin = true;
     }
 
-    const dirPath = match.free[0];
-
     // run script
     try {
-        const uguisu = new Uguisu({
-            stdout(str) {
-                console.log(str);
-            }
-        });
-        uguisu.run(dirPath, { skipCheck: match.skipCheck });
+        const uguisu = new Uguisu();
+        const opts = {
+            skipCheck: match.skipCheck,
+            dumpAst: match.dumpAst,
+            dumpSymbolTable: match.dumpHir,
+            trace: match.trace,
+            time: match.time,
+            warnEndlessLoop: match.warnEndlessLoop,
+        };
+        let exitCode;
+        if (match.stdin) {
+            exitCode = runStdin(uguisu, opts);
+        } else {
+            const dirPath = match.free[0];
+            exitCode = uguisu.run(dirPath, opts);
+        }
+        // the number returned by the main function is used as the exit code
+        if (exitCode != null) {
+            process.exitCode = exitCode;
+        }
     }
     catch (e) {
         console.log(e);
@@ -1,20 +1,155 @@
 import fs from 'fs';
 import path from 'path';
-import { UguisuError } from './misc/errors.js';
+import { performance } from 'perf_hooks';
+import { Interpreter } from './interpreter.js';
+import { Diagnostic, UguisuError } from './misc/errors.js';
 import { UguisuOptions } from './misc/options.js';
+import { Trace } from './misc/trace.js';
 import { getDefaultProjectInfo, parseProjectFile, ProjectInfo } from './project-file.js';
-import { run } from './running/run.js';
-import { RunningEnv } from './running/tools.js';
-import { analyze } from './semantics/analyze.js';
-import { AnalysisEnv } from './semantics/tools.js';
-import { parse } from './syntax/parse.js';
+import { evalExpression, run } from './running/run.js';
+import { FunctionValue, NoneValue, RunningEnv, Value } from './running/tools.js';
+import { analyze, analyzeExpression } from './semantics/analyze.js';
+import { getDeclarations, getHostType, setHostDeclaration } from './semantics/builtins.js';
+import { dumpSymbolTable } from './semantics/dump.js';
+import { AnalysisEnv, AnalyzeOptions, getTypeString, ValidType } from './semantics/tools.js';
+import { dumpAst } from './syntax/dump.js';
+import { foldConstants } from './syntax/fold.js';
+import { formatSource } from './syntax/format.js';
+import { parse, parseExpression } from './syntax/parse.js';
+import { resolveImports } from './syntax/resolve.js';
+import { fromUguisuValue, toUguisuValue, UguisuValue } from './value.js';
 
 export {
+    Interpreter,
     UguisuError
 };
 
+export type {
+    Diagnostic,
+    UguisuOptions,
+    UguisuValue
+};
+
+// inspection API
+export { inspectSource, Program } from './inspect.js';
+export type { AnalyzeOptions, Type } from './semantics/tools.js';
+export type {
+    AstNode,
+    ExprNode,
+    FileNode,
+    Pos,
+    SourceFile,
+    Span,
+    StatementNode
+} from './syntax/tools.js';
+
+export type BuiltinInfo = {
+    name: string,
+    params: string[],
+    returnType: string,
+};
+
+/**
+ * Get the names and signatures of the builtin functions.
+*/
+export function listBuiltins(): BuiltinInfo[] {
+    return getDeclarations().map(x => ({
+        name: x.name,
+        params: x.paramTypes.map(ty => getTypeString(ty)),
+        returnType: getTypeString(x.returnType),
+    }));
+}
+
+/**
+ * Format the source code in the canonical layout.
+ * @throws UguisuError (Syntax error)
+*/
+export function formatCode(sourceCode: string, filename?: string): string {
+    const projectInfo = getDefaultProjectInfo();
+    const source = parse(sourceCode, filename ?? projectInfo.filename, projectInfo);
+    return formatSource(source);
+}
+
+export type RunOptions = {
+    /** skip the static checking phase */
+    skipCheck?: boolean,
+    /**
+     * print the AST before running.
+     * If it is specified without `dumpSymbolTable`, the program is only parsed.
+    */
+    dumpAst?: boolean,
+    /** print the analysis result (the symbol of each node) before running */
+    dumpSymbolTable?: boolean,
+    /** print the trace of the analysis and the execution */
+    trace?: boolean,
+    /** evaluate the declarations without calling the `main` function. The program may not have `main`. */
+    skipMain?: boolean,
+    /** fold the operations of the literals before running */
+    optimize?: boolean,
+    /** print the duration of each phase (parse, analyze, run) */
+    time?: boolean,
+    /** report the loop that never ends, since it has no `break` or `return` */
+    warnEndlessLoop?: boolean,
+};
+
+type Tasks = {
+    check: boolean,
+    run: boolean,
+    dumpAst?: boolean,
+    dumpSymbolTable?: boolean,
+    trace?: boolean,
+    skipMain?: boolean,
+    optimize?: boolean,
+    time?: boolean,
+    warnEndlessLoop?: boolean,
+};
+
+function getRunTasks(opts: RunOptions): Tasks {
+    const dumpAst = opts.dumpAst ?? false;
+    const dumpSymbolTable = opts.dumpSymbolTable ?? false;
+    // dumping only the AST stops after parsing
+    const parseOnly = dumpAst && !dumpSymbolTable;
+    return {
+        check: !(opts.skipCheck ?? false) && !parseOnly,
+        run: !parseOnly,
+        dumpAst,
+        dumpSymbolTable,
+        trace: opts.trace ?? false,
+        skipMain: opts.skipMain ?? false,
+        optimize: opts.optimize ?? false,
+        time: opts.time ?? false,
+        warnEndlessLoop: opts.warnEndlessLoop ?? false,
+    };
+}
+
+/**
+ * Call the function of the phase. The duration is printed if enabled.
+*/
+function measurePhase<T>(enabled: boolean | undefined, phase: string, fn: () => T): T {
+    if (!enabled) {
+        return fn();
+    }
+    const start = performance.now();
+    const result = fn();
+    console.log(`[time] ${phase}: ${(performance.now() - start).toFixed(3)}ms`);
+    return result;
+}
+
+/**
+ * The function implemented by the host.
+*/
+export type HostFunction = (args: UguisuValue[]) => UguisuValue;
+
+type HostFunctionEntry = {
+    info: BuiltinInfo,
+    paramTypes: ValidType[],
+    returnType: ValidType,
+    handler: HostFunction,
+};
+
 export class Uguisu {
     private _options: UguisuOptions;
+    private _hostFunctions: HostFunctionEntry[];
 
     /**
      * @throws TypeError (Invalid arguments)
@@ -26,34 +161,173 @@ export class Uguisu {
         if (options?.stdin != null && typeof options.stdin != 'function') {
             throw new TypeError('Invalid arguments');
         }
-        this._options = options ?? {};
+        if (options?.env != null && typeof options.env != 'object') {
+            throw new TypeError('Invalid arguments');
+        }
+        if (options?.randomSeed != null && typeof options.randomSeed != 'number') {
+            throw new TypeError('Invalid arguments');
+        }
+        if (options?.clock != null && typeof options.clock != 'function') {
+            throw new TypeError('Invalid arguments');
+        }
+        if (options?.readFile != null && typeof options.readFile != 'function') {
+            throw new TypeError('Invalid arguments');
+        }
+        this._options = {
+            ...options,
+            // the output of the program is written to the console by default
+            stdout: options?.stdout ?? ((str: string) => console.log(str)),
+        };
+        this._hostFunctions = [];
+    }
+
+    /**
+     * Register the function implemented by the host. The program can call it by the name.
+     * The signature is specified with the type names in the same form as `listBuiltins` (e.g. `number`, `void`).
+     *
+     * ```js
+     * uguisu.registerFunction({ name: 'double', params: ['number'], returnType: 'number' }, args => {
+     *     return { kind: 'number', value: args[0].value * 2 };
+     * });
+     * ```
+     * @throws TypeError (Invalid arguments)
+    */
+    registerFunction(info: BuiltinInfo, handler: HostFunction) {
+        if (typeof info?.name != 'string' || !Array.isArray(info.params) || typeof handler != 'function') {
+            throw new TypeError('Invalid arguments.');
+        }
+        if (!/^[A-Za-z_][A-Za-z0-9_]*$/.test(info.name)) {
+            throw new TypeError(`Invalid function name: ${info.name}`);
+        }
+        if (listBuiltins().some(x => x.name == info.name || x.name.startsWith(`${info.name}.`))) {
+            throw new TypeError(`The function name is already used by a builtin: ${info.name}`);
+        }
+        if (this._hostFunctions.some(x => x.info.name == info.name)) {
+            throw new TypeError(`The function is already registered: ${info.name}`);
+        }
+        const paramTypes: ValidType[] = [];
+        for (const param of info.params) {
+            const ty = getHostType(param);
+            if (ty == null || ty.kind == 'VoidType') {
+                throw new TypeError(`Invalid parameter type: ${param}`);
+            }
+            paramTypes.push(ty);
+        }
+        const returnType = getHostType(info.returnType);
+        if (returnType == null) {
+            throw new TypeError(`Invalid return type: ${info.returnType}`);
+        }
+        this._hostFunctions.push({
+            info: { name: info.name, params: [...info.params], returnType: info.returnType },
+            paramTypes,
+            returnType,
+            handler,
+        });
+    }
+
+    /**
+     * Get the names and signatures of the functions that the program can call,
+     * the builtins followed by the functions registered with `registerFunction`.
+    */
+    listFunctions(): BuiltinInfo[] {
+        const hostFunctions = this._hostFunctions.map(x => ({ ...x.info, params: [...x.info.params] }));
+        return [...listBuiltins(), ...hostFunctions];
     }
 
     /**
      * @throws TypeError (Invalid arguments)
      * @throws UguisuError
     */
-    check(dirPath: string) {
+    check(dirPath: string, opts?: AnalyzeOptions) {
         this._perform(dirPath, {
             check: true,
             run: false,
+            warnEndlessLoop: opts?.warnEndlessLoop ?? false,
         });
     }
 
     /**
+     * Run the project and get the exit code, the number returned by the `main` function.
+     * Returns undefined if the `main` function does not return a number.
      * @throws TypeError (Invalid arguments)
      * @throws UguisuError
     */
-    run(dirPath: string, opts?: { skipCheck?: boolean }) {
-        opts = opts ?? {};
-        const skipCheck = opts.skipCheck ?? false;
-        this._perform(dirPath, {
-            check: !skipCheck,
-            run: true,
-        });
+    run(dirPath: string, opts?: RunOptions): number | undefined {
+        return getExitCode(this.runForValue(dirPath, opts));
+    }
+
+    /**
+     * Run the project and get the value returned by the `main` function.
+     * @throws TypeError (Invalid arguments)
+     * @throws UguisuError
+    */
+    runForValue(dirPath: string, opts?: RunOptions): UguisuValue {
+        const value = this._perform(dirPath, getRunTasks(opts ?? {}));
+        return toUguisuValue(value ?? new NoneValue());
+    }
+
+    /**
+     * Run the source code without a project directory and get the exit code in the same way as `run`.
+     * The imported files are resolved relative to the current directory.
+     * @throws TypeError (Invalid arguments)
+     * @throws UguisuError
+    */
+    runSource(sourceCode: string, opts?: RunOptions): number | undefined {
+        return getExitCode(this.runSourceForValue(sourceCode, opts));
+    }
+
+    /**
+     * Run the source code without a project directory and get the value returned by the `main` function.
+     * @throws TypeError (Invalid arguments)
+     * @throws UguisuError
+    */
+    runSourceForValue(sourceCode: string, opts?: RunOptions): UguisuValue {
+        if (typeof sourceCode != 'string') {
+            throw new TypeError('Invalid arguments.');
+        }
+        const projectInfo = getDefaultProjectInfo();
+        const scriptFilePath = path.resolve(projectInfo.filename);
+        const value = this._performSource(sourceCode, scriptFilePath, projectInfo, getRunTasks(opts ?? {}));
+        return toUguisuValue(value ?? new NoneValue());
+    }
+
+    /**
+     * Evaluate the single expression without the `main` function and get the value.
+     * The builtins and the registered functions can be used in the expression.
+     *
+     * ```js
+     * const value = uguisu.evalExpression('1 + 2 * 3'); // { kind: 'number', value: 7 }
+     * ```
+     * @throws TypeError (Invalid arguments)
+     * @throws UguisuError
+    */
+    evalExpression(expression: string): UguisuValue {
+        if (typeof expression != 'string') {
+            throw new TypeError('Invalid arguments.');
+        }
+        const projectInfo = getDefaultProjectInfo();
+        const expr = parseExpression(expression, projectInfo);
+        const result = analyzeExpression(expr, this._createAnalysisEnv(), new Map(), projectInfo);
+        if (!result.success) {
+            throw new UguisuError('Syntax error.', result.diagnostics);
+        }
+        const value = evalExpression(expr, this._createRunningEnv(), this._options, projectInfo);
+        return toUguisuValue(value);
+    }
+
+    /**
+     * Create the interpreter that keeps the declarations between the runs.
+     * The builtins and the registered functions can be used in the same way as `runSource`.
+    */
+    createInterpreter(): Interpreter {
+        return new Interpreter(this._createAnalysisEnv(), this._createRunningEnv(), this._options, this._getReadFile());
     }
 
-    private _perform(dirPath: string, tasks: { check: boolean, run: boolean }) {
+    private _getReadFile(): (filePath: string) => string {
+        return this._options.readFile ?? ((filePath: string) => fs.readFileSync(filePath, { encoding: 'utf8' }));
+    }
+
+    private _perform(dirPath: string, tasks: Tasks): Value | undefined {
         if (typeof dirPath != 'string') {
             throw new TypeError('Invalid arguments.');
         }
@@ -82,37 +356,108 @@ export class Uguisu {
         }
 
         // load
+        const readFile = this._getReadFile();
         const scriptFilePath = path.resolve(dirPath, projectInfo.filename);
         let sourceCode;
         try {
-            sourceCode = fs.readFileSync(scriptFilePath, { encoding: 'utf8' });
+            sourceCode = readFile(scriptFilePath);
         } catch (err) {
             throw new UguisuError('Failed to load the script file.');
         }
 
+        return this._performSource(sourceCode, scriptFilePath, projectInfo, tasks);
+    }
+
+    private _performSource(
+        sourceCode: string,
+        scriptFilePath: string,
+        projectInfo: ProjectInfo,
+        tasks: Tasks,
+    ): Value | undefined {
+        const trace = tasks.trace ? Trace.getDefault() : undefined;
+
         // parse
-        const sourceFile = parse(sourceCode, scriptFilePath, projectInfo);
+        const sourceFile = measurePhase(tasks.time, 'parse', () => {
+            return resolveImports(parse(sourceCode, scriptFilePath, projectInfo), projectInfo, this._getReadFile());
+        });
+
+        if (tasks.dumpAst) {
+            console.log(dumpAst(sourceFile));
+        }
 
         // static analysis
-        if (tasks.check) {
-            const analysisEnv = new AnalysisEnv();
+        if (tasks.check || tasks.dumpSymbolTable) {
+            const analysisEnv = this._createAnalysisEnv(trace);
             const symbolTable = new Map();
-            const result = analyze(sourceFile, analysisEnv, symbolTable, projectInfo);
-            for (const message of result.errors) {
-                console.log(`Syntax Error: ${message}`);
+            const result = measurePhase(tasks.time, 'analyze', () => {
+                return analyze(sourceFile, analysisEnv, symbolTable, projectInfo, { warnEndlessLoop: tasks.warnEndlessLoop });
+            });
+            if (tasks.dumpSymbolTable) {
+                console.log(dumpSymbolTable(sourceFile, symbolTable));
             }
-            for (const warn of result.warnings) {
-                console.log(`Warning: ${warn}`);
-            }
-            if (!result.success) {
-                throw new UguisuError('Syntax error.');
+            // the analysis only for the dump does not check the program
+            if (tasks.check) {
+                for (const message of result.errors) {
+                    console.log(`Syntax Error: ${message}`);
+                }
+                for (const warn of result.warnings) {
+                    console.log(`Warning: ${warn}`);
+                }
+                if (!result.success) {
+                    throw new UguisuError('Syntax error.', result.diagnostics);
+                }
             }
         }
 
+        // optimize
+        if (tasks.optimize) {
+            measurePhase(tasks.time, 'optimize', () => foldConstants(sourceFile));
+        }
+
         // run
         if (tasks.run) {
-            const runningEnv = new RunningEnv();
-            run(sourceFile, runningEnv, this._options, projectInfo);
+            const runningEnv = this._createRunningEnv(trace);
+            return measurePhase(tasks.time, 'run', () => {
+                return run(sourceFile, runningEnv, this._options, projectInfo, !tasks.skipMain);
+            });
         }
+        return undefined;
+    }
+
+    private _createAnalysisEnv(trace?: Trace): AnalysisEnv {
+        const env = new AnalysisEnv(undefined, trace);
+        for (const entry of this._hostFunctions) {
+            setHostDeclaration(env, entry.info.name, entry.paramTypes, entry.returnType);
+        }
+        return env;
+    }
+
+    private _createRunningEnv(trace?: Trace): RunningEnv {
+        const env = new RunningEnv(undefined, trace);
+        for (const entry of this._hostFunctions) {
+            env.declare(entry.info.name, createHostFunctionValue(entry));
+        }
+        return env;
+    }
+}
+
+function createHostFunctionValue(entry: HostFunctionEntry): FunctionValue {
+    return FunctionValue.createNative((args) => {
+        if (args.length != entry.paramTypes.length) {
+            throw new UguisuError('invalid arguments count');
+        }
+        const result = entry.handler(args.map(x => toUguisuValue(x)));
+        // the host may return a value of any kind
+        if (result?.kind != entry.info.returnType) {
+            throw new UguisuError(`function \`${entry.info.name}\` returned an invalid value. expected \`${entry.info.returnType}\`.`);
+        }
+        return fromUguisuValue(result);
+    });
+}
+
+function getExitCode(value: UguisuValue): number | undefined {
+    if (value.kind == 'number') {
+        return value.value;
     }
+    return undefined;
 }
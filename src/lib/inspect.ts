@@ -0,0 +1,164 @@
+import { getDefaultProjectInfo } from './project-file.js';
+import { Diagnostic } from './misc/errors.js';
+import { analyze, AnalyzeResult } from './semantics/analyze.js';
+import { AnalysisEnv, AnalyzeOptions, createNamedType, getTypeString, Symbol, Type } from './semantics/tools.js';
+import { dumpAstJson } from './syntax/dump.js';
+import { parse } from './syntax/parse.js';
+import { AstNode, getChildNodes, getSpan, Pos, SourceFile, Span } from './syntax/tools.js';
+
+/**
+ * The read-only view of a parsed and analyzed source file.
+ *
+ * ```js
+ * const program = inspectSource('fn main() { var x = 1; }');
+ * for (const node of program.nodes()) {
+ *     if (node.kind == 'VariableDecl') {
+ *         console.log(node.name, program.getTypeString(node)); // x number
+ *     }
+ * }
+ * ```
+*/
+export class Program {
+    readonly source: SourceFile;
+    readonly errors: readonly string[];
+    readonly warnings: readonly string[];
+    /** the errors and the warnings with the positions */
+    readonly diagnostics: readonly Diagnostic[];
+    private _symbolTable: Map<AstNode, Symbol>;
+    private _declarations?: Map<Symbol, AstNode>;
+
+    constructor(source: SourceFile, symbolTable: Map<AstNode, Symbol>, result: AnalyzeResult) {
+        this.source = source;
+        this._symbolTable = symbolTable;
+        this.errors = result.errors;
+        this.warnings = result.warnings;
+        this.diagnostics = result.diagnostics;
+    }
+
+    get success(): boolean {
+        return (this.errors.length == 0);
+    }
+
+    /**
+     * Iterate all nodes in the order of appearance in the source code.
+    */
+    *nodes(): IterableIterator<AstNode> {
+        const stack: AstNode[] = [this.source];
+        while (stack.length > 0) {
+            const node = stack.pop()!;
+            yield node;
+            stack.push(...getChildNodes(node).reverse());
+        }
+    }
+
+    /**
+     * Get the type of the node. Returns undefined if the node has no type information.
+    */
+    getType(node: AstNode): Type | undefined {
+        const symbol = this._symbolTable.get(node);
+        if (symbol == null) {
+            return undefined;
+        }
+        switch (symbol.kind) {
+            case 'FnSymbol':
+            case 'NativeFnSymbol':
+            case 'VariableSymbol':
+            case 'ExprSymbol': {
+                return symbol.ty;
+            }
+            case 'StructSymbol':
+            case 'EnumSymbol': {
+                return createNamedType(symbol.name);
+            }
+        }
+    }
+
+    /**
+     * Get the declaration that the identifier refers to (e.g. the `FunctionDecl` for the callee of the call).
+     * Returns the node itself if it is a declaration, and undefined for the builtins.
+    */
+    getDeclaration(node: AstNode): AstNode | undefined {
+        const symbol = this._symbolTable.get(node);
+        if (symbol == null) {
+            return undefined;
+        }
+        if (this._declarations == null) {
+            this._declarations = new Map();
+            for (const [declNode, declSymbol] of this._symbolTable) {
+                if (isDeclaration(declNode)) {
+                    this._declarations.set(declSymbol, declNode);
+                }
+            }
+        }
+        return this._declarations.get(symbol);
+    }
+
+    /**
+     * Get the identifiers and the field accesses that refer to the declaration, in the order of appearance in the source code.
+     * The field accesses are found with the declaration of the struct field (`StructDeclField`).
+    */
+    getReferences(decl: AstNode): AstNode[] {
+        const symbol = this._symbolTable.get(decl);
+        if (symbol == null) {
+            return [];
+        }
+        return [...this.nodes()].filter(x => (x.kind == 'Identifier' || x.kind == 'FieldAccess') && this._symbolTable.get(x) == symbol);
+    }
+
+    /**
+     * Get the type name of the node (e.g. `number`, `(number) => bool`).
+    */
+    getTypeString(node: AstNode): string | undefined {
+        const ty = this.getType(node);
+        return (ty != null) ? getTypeString(ty) : undefined;
+    }
+
+    /**
+     * Get the position (line, column) of the node.
+    */
+    getPos(node: AstNode): Pos {
+        return node.pos;
+    }
+
+    /**
+     * Get the range of the source code that the node covers. The end position is exclusive.
+    */
+    getSpan(node: AstNode): Span {
+        return getSpan(node);
+    }
+
+    /**
+     * Serialize the syntax tree as JSON. Each node includes its position.
+    */
+    toJson(space?: number): string {
+        return dumpAstJson(this.source, space);
+    }
+}
+
+function isDeclaration(node: AstNode): boolean {
+    switch (node.kind) {
+        case 'FunctionDecl':
+        case 'FnDeclParam':
+        case 'StructDecl':
+        case 'StructDeclField':
+        case 'EnumDecl':
+        case 'VariableDecl': {
+            return true;
+        }
+        default: {
+            return false;
+        }
+    }
+}
+
+/**
+ * Parse and analyze the source code without running it.
+ * @throws UguisuError (Syntax error)
+*/
+export function inspectSource(sourceCode: string, filename?: string, options?: AnalyzeOptions): Program {
+    const projectInfo = getDefaultProjectInfo();
+    const source = parse(sourceCode, filename ?? projectInfo.filename, projectInfo);
+    const symbolTable = new Map<AstNode, Symbol>();
+    const result = analyze(source, new AnalysisEnv(), symbolTable, projectInfo, options);
+    return new Program(source, symbolTable, result);
+}
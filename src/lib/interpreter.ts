@@ -0,0 +1,95 @@
+import path from 'path';
+import { UguisuError } from './misc/errors.js';
+import { UguisuOptions } from './misc/options.js';
+import { getDefaultProjectInfo, ProjectInfo } from './project-file.js';
+import { evalExpression, run } from './running/run.js';
+import { RunningEnv } from './running/tools.js';
+import { analyze, analyzeExpression } from './semantics/analyze.js';
+import { AnalysisEnv, Symbol } from './semantics/tools.js';
+import { parse, parseExpression } from './syntax/parse.js';
+import { resolveImports } from './syntax/resolve.js';
+import { AstNode } from './syntax/tools.js';
+import { toUguisuValue, UguisuValue } from './value.js';
+
+/**
+ * The interpreter that keeps the declarations between the runs.
+ * It is created by `Uguisu.createInterpreter`.
+ *
+ * ```js
+ * const interpreter = uguisu.createInterpreter();
+ * interpreter.feed('fn square(x: number): number { return x * x; }');
+ * interpreter.feed('var total = square(3);');
+ * const value = interpreter.eval('total + 1'); // { kind: 'number', value: 10 }
+ * ```
+*/
+export class Interpreter {
+    private _analysisEnv: AnalysisEnv;
+    private _runningEnv: RunningEnv;
+    private _symbolTable: Map<AstNode, Symbol>;
+    private _options: UguisuOptions;
+    private _projectInfo: ProjectInfo;
+    private _readFile: (filePath: string) => string;
+
+    constructor(analysisEnv: AnalysisEnv, runningEnv: RunningEnv, options: UguisuOptions, readFile: (filePath: string) => string) {
+        this._analysisEnv = analysisEnv;
+        this._runningEnv = runningEnv;
+        this._symbolTable = new Map();
+        this._options = options;
+        this._projectInfo = getDefaultProjectInfo();
+        this._readFile = readFile;
+    }
+
+    /**
+     * Add the declarations of the source code. The `main` function is not called.
+     * The declarations can refer to the ones added before.
+     * If the source code has errors or the initialization of the global variables fails,
+     * none of its declarations are added.
+     * @throws TypeError (Invalid arguments)
+     * @throws UguisuError
+    */
+    feed(sourceCode: string) {
+        if (typeof sourceCode != 'string') {
+            throw new TypeError('Invalid arguments.');
+        }
+        const scriptFilePath = path.resolve(this._projectInfo.filename);
+        const sourceFile = resolveImports(parse(sourceCode, scriptFilePath, this._projectInfo), this._projectInfo, this._readFile);
+
+        // the declarations are analyzed in the new layer
+        const analysisEnv = new AnalysisEnv(this._analysisEnv);
+        analysisEnv.enter();
+        const result = analyze(sourceFile, analysisEnv, this._symbolTable, this._projectInfo);
+        if (!result.success) {
+            throw new UguisuError('Syntax error.', result.diagnostics);
+        }
+
+        // the declarations are also evaluated in the new layer, and kept only when both succeed
+        const runningEnv = new RunningEnv(this._runningEnv);
+        runningEnv.enter();
+        run(sourceFile, runningEnv, this._options, this._projectInfo, false);
+
+        for (const [name, symbol] of analysisEnv.entries()) {
+            this._analysisEnv.set(name, symbol);
+        }
+        for (const [name, symbol] of runningEnv.entries()) {
+            this._runningEnv.set(name, symbol);
+        }
+    }
+
+    /**
+     * Evaluate the single expression with the declarations added so far and get the value.
+     * @throws TypeError (Invalid arguments)
+     * @throws UguisuError
+    */
+    eval(expression: string): UguisuValue {
+        if (typeof expression != 'string') {
+            throw new TypeError('Invalid arguments.');
+        }
+        const expr = parseExpression(expression, this._projectInfo);
+        const result = analyzeExpression(expr, this._analysisEnv, this._symbolTable, this._projectInfo);
+        if (!result.success) {
+            throw new UguisuError('Syntax error.', result.diagnostics);
+        }
+        const value = evalExpression(expr, this._runningEnv, this._options, this._projectInfo);
+        return toUguisuValue(value);
+    }
+}
@@ -1,5 +1,31 @@
+/**
+ * The error or the warning reported with the position in the source code.
+*/
+export type Diagnostic = {
+    severity: 'error' | 'warning',
+    message: string,
+    /** the line and the column where the problem starts (1-based) */
+    start?: [number, number],
+    /** the line and the column where the problem ends (1-based, exclusive) */
+    end?: [number, number],
+};
+
+/**
+ * Format the diagnostic as `message (line:column)`.
+*/
+export function formatDiagnostic(diagnostic: Diagnostic): string {
+    if (diagnostic.start != null) {
+        return `${diagnostic.message} (${diagnostic.start[0]}:${diagnostic.start[1]})`;
+    }
+    return diagnostic.message;
+}
+
 export class UguisuError extends Error {
-    constructor(message: string) {
+    /** the errors found in the source code, if any */
+    diagnostics: Diagnostic[];
+
+    constructor(message: string, diagnostics?: Diagnostic[]) {
         super(message);
+        this.diagnostics = diagnostics ?? [];
     }
 }
@@ -1,4 +1,15 @@
 export type UguisuOptions = {
+    /** reads a line from the input */
     stdin?: () => string,
+    /** receives the output of the program. The `Uguisu` class writes it to the console if not specified. */
     stdout?: (buf: string) => void,
+    env?: Record<string, string | undefined>,
+    /** seed of the random number generator */
+    randomSeed?: number,
+    /** returns the current time in milliseconds */
+    clock?: () => number,
+    /** reads the source file of the specified path */
+    readFile?: (filePath: string) => string,
+    /** the maximum depth of the function calls. The default is 1000. */
+    maxCallDepth?: number,
 };
@@ -0,0 +1,18 @@
+/**
+ * Create a random number generator that returns a value in the range [0, 1).
+ * If a seed is specified, the generator produces the same sequence for the same seed.
+*/
+export function createRandom(seed?: number): () => number {
+    if (seed == null) {
+        return Math.random;
+    }
+    // mulberry32
+    let state = seed >>> 0;
+    return () => {
+        state = (state + 0x6D2B79F5) >>> 0;
+        let t = state;
+        t = Math.imul(t ^ (t >>> 15), t | 1);
+        t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+        return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+    };
+}
@@ -1,13 +1,21 @@
 import charRegex from 'char-regex';
 import { UguisuError } from '../misc/errors.js';
 import { UguisuOptions } from '../misc/options.js';
+import { createRandom } from '../misc/random.js';
 import {
     ArrayValue,
     assertValue,
+    BoolValue,
     CharValue,
+    createRuntimeError,
+    deepClone,
+    equalValue,
+    FloatValue,
     FunctionValue,
     NoneValue,
     NumberValue,
+    OptionalValue,
+    powNumber,
     RunningEnv,
     StringValue,
     StructValue,
@@ -24,15 +32,90 @@ function group(name: string, env: RunningEnv, handle: (setItem: (name: string, v
     env.declare(name, new StructValue(fields));
 }
 
+/**
+ * Format the float value. The integral value is formatted with a fraction part (e.g. `1.0`).
+*/
+function formatFloat(value: number): string {
+    if (Number.isInteger(value)) {
+        return value.toFixed(1);
+    }
+    return value.toString();
+}
+
+/**
+ * Format the value for the messages of the assertions.
+*/
+function formatValue(value: Value): string {
+    switch (value.kind) {
+        case 'NoneValue': {
+            return 'void';
+        }
+        case 'NumberValue':
+        case 'BoolValue': {
+            return value.getValue().toString();
+        }
+        case 'FloatValue': {
+            return formatFloat(value.getValue());
+        }
+        case 'CharValue': {
+            return `'${value.getValue()}'`;
+        }
+        case 'StringValue': {
+            return JSON.stringify(value.getValue());
+        }
+        case 'StructValue': {
+            const fields = [...value.getFieldNames()].map(name => {
+                const field = value.lookupField(name)!;
+                return `${name}: ${field.value != null ? formatValue(field.value) : '?'}`;
+            });
+            return `{ ${fields.join(', ')} }`;
+        }
+        case 'ArrayValue': {
+            const items: string[] = [];
+            for (let i = 0; i < value.count(); i++) {
+                const item = value.at(i)!;
+                items.push(item.value != null ? formatValue(item.value) : '?');
+            }
+            return `[${items.join(', ')}]`;
+        }
+        case 'TupleValue': {
+            const items: string[] = [];
+            for (let i = 0; i < value.count(); i++) {
+                const item = value.at(i)!;
+                items.push(item.value != null ? formatValue(item.value) : '?');
+            }
+            return `(${items.join(', ')})`;
+        }
+        case 'EnumValue': {
+            return `${value.getEnumName()}.${value.getVariant()}`;
+        }
+        case 'OptionalValue': {
+            const inner = value.getValue();
+            return (inner != null) ? `some(${formatValue(inner)})` : 'none';
+        }
+        case 'FunctionValue': {
+            return 'fn';
+        }
+    }
+}
+
 export function setRuntime(env: RunningEnv, options: UguisuOptions) {
+    const nextRandom = createRandom(options.randomSeed);
+    const clock = options.clock ?? Date.now;
+
     group('number', env, setItem => {
         const parse = FunctionValue.createNative((args) => {
             if (args.length != 1) {
                 throw new UguisuError('invalid arguments count');
             }
             assertValue(args[0], 'StringValue');
-            const parsedValue = Number(args[0].getValue());
-            return new NumberValue(parsedValue);
+            const source = args[0].getValue();
+            // only the decimal integer is accepted. `Number` alone accepts the empty string, the spaces and the fraction.
+            const parsedValue = /^[+-]?[0-9]+$/.test(source) ? Number(source) : NaN;
+            if (!Number.isSafeInteger(parsedValue)) {
+                return new OptionalValue();
+            }
+            return new OptionalValue(new NumberValue(parsedValue));
         });
         setItem('parse', parse);
 
@@ -59,6 +142,135 @@ export function setRuntime(env: RunningEnv, options: UguisuOptions) {
             return new NoneValue();
         });
         setItem('assertEq', assertEq);
+
+        const assertNear = FunctionValue.createNative((args) => {
+            if (args.length != 3) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'NumberValue');
+            assertValue(args[1], 'NumberValue');
+            assertValue(args[2], 'NumberValue');
+            const actual = args[0].getValue();
+            const expected = args[1].getValue();
+            const epsilon = args[2].getValue();
+            if (!(Math.abs(actual - expected) <= epsilon)) {
+                throw new UguisuError(`assertion error. expected \`${expected}\` within \`${epsilon}\`, actual \`${actual}\`.`);
+            }
+            return new NoneValue();
+        });
+        setItem('assertNear', assertNear);
+
+        const random = FunctionValue.createNative((args) => {
+            if (args.length != 2) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'NumberValue');
+            assertValue(args[1], 'NumberValue');
+            const min = Math.ceil(args[0].getValue());
+            const max = Math.floor(args[1].getValue());
+            if (min >= max) {
+                throw new UguisuError('invalid range');
+            }
+            return new NumberValue(Math.floor(nextRandom() * (max - min)) + min);
+        });
+        setItem('random', random);
+
+        const abs = FunctionValue.createNative((args) => {
+            if (args.length != 1) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'NumberValue');
+            return new NumberValue(Math.abs(args[0].getValue()));
+        });
+        setItem('abs', abs);
+
+        const min = FunctionValue.createNative((args) => {
+            if (args.length != 2) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'NumberValue');
+            assertValue(args[1], 'NumberValue');
+            return new NumberValue(Math.min(args[0].getValue(), args[1].getValue()));
+        });
+        setItem('min', min);
+
+        const max = FunctionValue.createNative((args) => {
+            if (args.length != 2) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'NumberValue');
+            assertValue(args[1], 'NumberValue');
+            return new NumberValue(Math.max(args[0].getValue(), args[1].getValue()));
+        });
+        setItem('max', max);
+
+        const pow = FunctionValue.createNative((args) => {
+            if (args.length != 2) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'NumberValue');
+            assertValue(args[1], 'NumberValue');
+            // the same errors as the `**` operator
+            return new NumberValue(powNumber(args[0].getValue(), args[1].getValue()));
+        });
+        setItem('pow', pow);
+    });
+
+    group('float', env, setItem => {
+        const fromNumber = FunctionValue.createNative((args) => {
+            if (args.length != 1) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'NumberValue');
+            return new FloatValue(args[0].getValue());
+        });
+        setItem('fromNumber', fromNumber);
+
+        const toNumber = FunctionValue.createNative((args) => {
+            if (args.length != 1) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'FloatValue');
+            return new NumberValue(Math.trunc(args[0].getValue()));
+        });
+        setItem('toNumber', toNumber);
+
+        const toString = FunctionValue.createNative((args) => {
+            if (args.length != 1) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'FloatValue');
+            return new StringValue(formatFloat(args[0].getValue()));
+        });
+        setItem('toString', toString);
+
+        const assertNear = FunctionValue.createNative((args) => {
+            if (args.length != 3) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'FloatValue');
+            assertValue(args[1], 'FloatValue');
+            assertValue(args[2], 'FloatValue');
+            const actual = args[0].getValue();
+            const expected = args[1].getValue();
+            const epsilon = args[2].getValue();
+            if (!(Math.abs(actual - expected) <= epsilon)) {
+                throw new UguisuError(`assertion error. expected \`${formatFloat(expected)}\` within \`${formatFloat(epsilon)}\`, actual \`${formatFloat(actual)}\`.`);
+            }
+            return new NoneValue();
+        });
+        setItem('assertNear', assertNear);
+    });
+
+    group('bool', env, setItem => {
+        const toString = FunctionValue.createNative((args) => {
+            if (args.length != 1) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'BoolValue');
+            return new StringValue(args[0].getValue() ? 'true' : 'false');
+        });
+        setItem('toString', toString);
     });
 
     group('char', env, setItem => {
@@ -137,6 +349,39 @@ export function setRuntime(env: RunningEnv, options: UguisuOptions) {
         });
         setItem('toChars', toChars);
 
+        // the string is indexed by the Unicode scalar values
+        const length = FunctionValue.createNative((args) => {
+            if (args.length != 1) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'StringValue');
+            return new NumberValue(Array.from(args[0].getValue()).length);
+        });
+        setItem('length', length);
+
+        const substring = FunctionValue.createNative((args) => {
+            if (args.length != 3) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'StringValue');
+            assertValue(args[1], 'NumberValue');
+            assertValue(args[2], 'NumberValue');
+            const chars = Array.from(args[0].getValue());
+            const start = args[1].getValue();
+            const end = args[2].getValue();
+            if (!Number.isInteger(start) || !Number.isInteger(end)) {
+                throw new UguisuError(`the index is not an integer: the range is ${start}..${end}`);
+            }
+            if (start < 0 || end > chars.length) {
+                throw new UguisuError(`index out of bounds: the length is ${chars.length} but the range is ${start}..${end}`);
+            }
+            if (start > end) {
+                throw new UguisuError(`the start index is greater than the end index: ${start} > ${end}`);
+            }
+            return new StringValue(chars.slice(start, end).join(''));
+        });
+        setItem('substring', substring);
+
         const assertEq = FunctionValue.createNative((args) => {
             if (args.length != 2) {
                 throw new UguisuError('invalid arguments count');
@@ -162,7 +407,7 @@ export function setRuntime(env: RunningEnv, options: UguisuOptions) {
             assertValue(args[1], 'NumberValue');
             const target = args[0];
             const index = args[1].getValue();
-            const symbol = new Symbol(args[2]);
+            const symbol = new Symbol(deepClone(args[2]));
             target.insert(index, symbol);
             return new NoneValue();
         });
@@ -174,7 +419,7 @@ export function setRuntime(env: RunningEnv, options: UguisuOptions) {
             }
             assertValue(args[0], 'ArrayValue');
             const target = args[0];
-            const symbol = new Symbol(args[1]);
+            const symbol = new Symbol(deepClone(args[1]));
             target.insert(target.count(), symbol);
             return new NoneValue();
         });
@@ -229,6 +474,30 @@ export function setRuntime(env: RunningEnv, options: UguisuOptions) {
         });
         setItem('writeNum', writeNum);
 
+        const writeFloat = FunctionValue.createNative((args) => {
+            if (args.length != 1) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'FloatValue');
+            if (options.stdout) {
+                options.stdout(formatFloat(args[0].getValue()));
+            }
+            return new NoneValue();
+        });
+        setItem('writeFloat', writeFloat);
+
+        const writeBool = FunctionValue.createNative((args) => {
+            if (args.length != 1) {
+                throw new UguisuError('invalid arguments count');
+            }
+            assertValue(args[0], 'BoolValue');
+            if (options.stdout) {
+                options.stdout(args[0].getValue() ? 'true' : 'false');
+            }
+            return new NoneValue();
+        });
+        setItem('writeBool', writeBool);
+
         const read = FunctionValue.createNative((args) => {
             if (args.length != 0) {
                 throw new UguisuError('invalid arguments count');
@@ -241,12 +510,125 @@ export function setRuntime(env: RunningEnv, options: UguisuOptions) {
         setItem('read', read);
     });
 
+    const print = FunctionValue.createNative((args) => {
+        if (args.length != 1) {
+            throw new UguisuError('invalid arguments count');
+        }
+        const value = args[0];
+        let text;
+        switch (value.kind) {
+            case 'NumberValue':
+            case 'CharValue':
+            case 'StringValue': {
+                text = value.getValue().toString();
+                break;
+            }
+            case 'FloatValue': {
+                text = formatFloat(value.getValue());
+                break;
+            }
+            case 'BoolValue': {
+                text = value.getValue() ? 'true' : 'false';
+                break;
+            }
+            default: {
+                throw new UguisuError('invalid argument');
+            }
+        }
+        if (options.stdout) {
+            options.stdout(text);
+        }
+        return new NoneValue();
+    });
+    env.declare('print', print);
+
     const getUnixtime = FunctionValue.createNative((args) => {
         if (args.length != 0) {
             throw new UguisuError('invalid arguments count');
         }
-        const unixTime = Math.floor(Date.now() / 1000);
+        const unixTime = Math.floor(clock() / 1000);
         return new NumberValue(unixTime);
     });
     env.declare('getUnixtime', getUnixtime);
+
+    const getEnv = FunctionValue.createNative((args) => {
+        if (args.length != 1) {
+            throw new UguisuError('invalid arguments count');
+        }
+        assertValue(args[0], 'StringValue');
+        const variables = options.env ?? process.env;
+        const value = variables[args[0].getValue()];
+        return new StringValue(value ?? '');
+    });
+    env.declare('getEnv', getEnv);
+
+    const assert = FunctionValue.createNative((args, _options, callSite) => {
+        if (args.length != 1) {
+            throw new UguisuError('invalid arguments count');
+        }
+        assertValue(args[0], 'BoolValue');
+        if (!args[0].getValue()) {
+            throw createRuntimeError('assertion failed.', callSite);
+        }
+        return new NoneValue();
+    });
+    env.declare('assert', assert);
+
+    const some = FunctionValue.createNative((args) => {
+        if (args.length != 1) {
+            throw new UguisuError('invalid arguments count');
+        }
+        return new OptionalValue(deepClone(args[0]));
+    });
+    env.declare('some', some);
+
+    const isSome = FunctionValue.createNative((args) => {
+        if (args.length != 1) {
+            throw new UguisuError('invalid arguments count');
+        }
+        assertValue(args[0], 'OptionalValue');
+        return new BoolValue(args[0].getValue() != null);
+    });
+    env.declare('isSome', isSome);
+
+    const unwrap = FunctionValue.createNative((args) => {
+        if (args.length != 1) {
+            throw new UguisuError('invalid arguments count');
+        }
+        assertValue(args[0], 'OptionalValue');
+        const value = args[0].getValue();
+        if (value == null) {
+            throw new UguisuError('called `unwrap` on `none`');
+        }
+        return value;
+    });
+    env.declare('unwrap', unwrap);
+
+    const panic = FunctionValue.createNative((args, _options, callSite) => {
+        if (args.length != 1) {
+            throw new UguisuError('invalid arguments count');
+        }
+        assertValue(args[0], 'StringValue');
+        throw createRuntimeError(`panic: ${args[0].getValue()}`, callSite);
+    });
+    env.declare('panic', panic);
+
+    const assertEq = FunctionValue.createNative((args) => {
+        if (args.length != 2) {
+            throw new UguisuError('invalid arguments count');
+        }
+        if (!equalValue(args[0], args[1])) {
+            throw new UguisuError(`assertion error. expected \`${formatValue(args[1])}\`, actual \`${formatValue(args[0])}\`.`);
+        }
+        return new NoneValue();
+    });
+    env.declare('assertEq', assertEq);
+
+    const share = FunctionValue.createNative((args) => {
+        if (args.length != 1) {
+            throw new UguisuError('invalid arguments count');
+        }
+        return args[0];
+    });
+    env.declare('share', share);
 }
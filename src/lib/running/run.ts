@@ -3,11 +3,17 @@ import { UguisuOptions } from '../misc/options.js';
 import { Trace } from '../misc/trace.js';
 import { ProjectInfo } from '../project-file.js';
 import {
+    ArithmeticOperator,
+    AstNode,
+    Call,
     ExprNode,
+    fieldIterationTarget,
+    getChildNodes,
     isEquivalentOperator,
     isExprNode,
     isLogicalBinaryOperator,
     isOrderingOperator,
+    MatchPattern,
     SourceFile,
     StatementNode
 } from '../syntax/tools.js';
@@ -18,40 +24,75 @@ import {
     BoolValue,
     CharValue,
     createBreakResult,
+    createContinueResult,
     createOkResult,
     createReturnResult,
+    deepClone,
+    EnumValue,
+    equalValue,
+    FloatValue,
     FunctionValue,
     getTypeName,
     NoneValue,
     NumberValue,
+    OptionalValue,
+    powNumber,
     RunningEnv,
     StatementResult,
     StringValue,
     StructValue,
     Symbol,
+    TupleValue,
     Value
 } from './tools.js';
 
 const trace = Trace.getDefault().createChild(false);
 
+const defaultMaxCallDepth = 1000;
+
 class RunContext {
     env: RunningEnv;
     options: UguisuOptions;
     projectInfo: ProjectInfo;
+    /** the number of the user functions being called */
+    callDepth: number;
 
-    constructor(env: RunningEnv, options: UguisuOptions, projectInfo: ProjectInfo) {
+    constructor(env: RunningEnv, options: UguisuOptions, projectInfo: ProjectInfo, callDepth: number = 0) {
         this.env = env;
         this.options = options;
         this.projectInfo = projectInfo;
+        this.callDepth = callDepth;
     }
 }
 
-export function run(source: SourceFile, env: RunningEnv, options: UguisuOptions, projectInfo: ProjectInfo) {
+/**
+ * Run the source file and get the value returned by the entry point.
+ * If `callEntryPoint` is false, only the declarations are evaluated and the entry point is not required.
+*/
+export function run(
+    source: SourceFile,
+    env: RunningEnv,
+    options: UguisuOptions,
+    projectInfo: ProjectInfo,
+    callEntryPoint: boolean = true,
+): Value {
     const r = new RunContext(env, options, projectInfo);
     builtins.setRuntime(r.env, options);
     evalSourceFile(r, source);
+    if (!callEntryPoint) {
+        return new NoneValue();
+    }
     const entryPoint = getEntryPoint(r);
-    call(r, entryPoint, []);
+    return call(r, entryPoint, []);
+}
+
+/**
+ * Evaluate the single expression without the entry point.
+*/
+export function evalExpression(expr: ExprNode, env: RunningEnv, options: UguisuOptions, projectInfo: ProjectInfo): Value {
+    const r = new RunContext(env, options, projectInfo);
+    builtins.setRuntime(r.env, options);
+    return evalExpr(r, expr);
 }
 
 function getEntryPoint(r: RunContext): FunctionValue {
@@ -67,17 +108,30 @@ function getEntryPoint(r: RunContext): FunctionValue {
     return symbol.value;
 }
 
-function call(r: RunContext, func: FunctionValue, args: Value[]): Value {
+function call(r: RunContext, func: FunctionValue, args: Value[], callSite?: Call): Value {
     if (func.user != null) {
-        const env = new RunningEnv(func.user.env);
-        const ctx = new RunContext(env, r.options, r.projectInfo);
+        // the deep recursion is stopped before the stack of the host overflows
+        if (r.callDepth >= (r.options.maxCallDepth ?? defaultMaxCallDepth)) {
+            throw new UguisuError('maximum recursion depth exceeded');
+        }
+        const env = new RunningEnv(func.user.env, r.env.trace);
+        const ctx = new RunContext(env, r.options, r.projectInfo, r.callDepth + 1);
+        const name = (func.user.node.kind == 'FunctionDecl') ? func.user.node.name : '(lambda)';
+        ctx.env.trace?.enter(`call function: ${name}`);
         ctx.env.enter();
-        if (func.user.node.params.length != args.length) {
+        if (func.user.node.params.length < args.length) {
             throw new UguisuError('invalid arguments count');
         }
         for (let i = 0; i < func.user.node.params.length; i++) {
             const param = func.user.node.params[i];
-            const arg = args[i];
+            let arg = args[i];
+            // the default value is evaluated in the callee with the preceding params
+            if (arg == null) {
+                if (param.defaultValue == null) {
+                    throw new UguisuError('invalid arguments count');
+                }
+                arg = evalStoredExpr(ctx, param.defaultValue);
+            }
             ctx.env.declare(param.name, arg);
         }
         let result: StatementResult = createOkResult();
@@ -90,12 +144,17 @@ function call(r: RunContext, func: FunctionValue, args: Value[]): Value {
             }
         }
         ctx.env.leave();
+        ctx.env.trace?.leave();
         if (result.kind == 'return') {
             return result.value;
         }
+        // the value yielded by the function body
+        if (result.kind == 'ok' && result.value != null) {
+            return result.value;
+        }
         return new NoneValue();
     } else if (func.native != null) {
-        return func.native(args, r.options);
+        return func.native(args, r.options, callSite);
     } else {
         throw new UguisuError('invalid function');
     }
@@ -108,9 +167,25 @@ function evalSourceFile(r: RunContext, source: SourceFile) {
                 r.env.declare(decl.name, FunctionValue.create(decl, r.env));
                 break;
             }
-            case 'StructDecl': {
+            case 'EnumDecl': {
+                // the enum is declared as the group of the variants
+                const variants = new Map<string, Symbol>();
+                for (const variant of decl.variants) {
+                    variants.set(variant.name, new Symbol(new EnumValue(decl.name, variant.name)));
+                }
+                r.env.declare(decl.name, new StructValue(variants));
                 break;
             }
+            case 'StructDecl':
+            case 'VariableDecl': {
+                break;
+            }
+        }
+    }
+    // the global variables are initialized in order after all functions are declared
+    for (const decl of source.decls) {
+        if (decl.kind == 'VariableDecl') {
+            evalStatement(r, decl);
         }
     }
 }
@@ -122,7 +197,7 @@ function evalBlock(r: RunContext, block: StatementNode[]): StatementResult {
         result = evalStatement(r, statement);
         if (result.kind == 'return') {
             break;
-        } else if (result.kind == 'break') {
+        } else if (result.kind == 'break' || result.kind == 'continue') {
             break;
         }
     }
@@ -130,6 +205,27 @@ function evalBlock(r: RunContext, block: StatementNode[]): StatementResult {
     return result;
 }
 
+/**
+ * Get the result that stops the loop, or undefined if the loop goes on to the next iteration.
+ * The break and the continue targeting an outer loop are passed to the outer loop as is.
+*/
+function getLoopExit(result: StatementResult, label: string | undefined): StatementResult | undefined {
+    switch (result.kind) {
+        case 'ok': {
+            return undefined;
+        }
+        case 'return': {
+            return result;
+        }
+        case 'break': {
+            return (result.label == null || result.label == label) ? createOkResult() : result;
+        }
+        case 'continue': {
+            return (result.label == null || result.label == label) ? undefined : result;
+        }
+    }
+}
+
 function evalName(r: RunContext, expr: ExprNode): Symbol {
     switch (expr.kind) {
         case 'Identifier': {
@@ -141,10 +237,17 @@ function evalName(r: RunContext, expr: ExprNode): Symbol {
         }
         case 'FieldAccess': {
             const target = evalExpr(r, expr.target);
+            if (target.kind == 'TupleValue') {
+                const item = target.at(Number(expr.name));
+                if (item == null) {
+                    throw new UguisuError(`unknown field \`${expr.name}\``);
+                }
+                return item;
+            }
             assertValue(target, 'StructValue');
             const field = target.lookupField(expr.name);
             if (field == null) {
-                throw new UguisuError('unknown field');
+                throw new UguisuError(`unknown field \`${expr.name}\``);
             }
             return field;
         }
@@ -155,7 +258,7 @@ function evalName(r: RunContext, expr: ExprNode): Symbol {
             assertValue(index, 'NumberValue');
             const symbol = target.at(index.getValue());
             if (symbol == null) {
-                throw new UguisuError('index out of range');
+                throw new UguisuError(`index out of bounds: the count is ${target.count()} but the index is ${index.getValue()}`);
             }
             return symbol;
         }
@@ -166,6 +269,7 @@ function evalName(r: RunContext, expr: ExprNode): Symbol {
 }
 
 function evalStatement(r: RunContext, statement: StatementNode): StatementResult {
+    r.env.trace?.log(`eval statement: ${statement.kind}`);
     if (isExprNode(statement)) {
         evalExpr(r, statement);
         return createOkResult();
@@ -179,19 +283,100 @@ function evalStatement(r: RunContext, statement: StatementNode): StatementResult
                 }
             }
             case 'BreakStatement': {
-                return createBreakResult();
+                return createBreakResult(statement.label);
+            }
+            case 'ContinueStatement': {
+                return createContinueResult(statement.label);
+            }
+            case 'FunctionDecl': {
+                // the nested function is visible in the rest of the block.
+                // the layers are copied, because the current environment leaves them when the function returns.
+                r.env.declare(statement.name, FunctionValue.create(statement, new RunningEnv(r.env)));
+                return createOkResult();
+            }
+            case 'TailExpr': {
+                return createOkResult(evalExpr(r, statement.expr));
             }
             case 'LoopStatement': {
                 while (true) {
-                    const result = evalBlock(r, statement.block);
-                    if (result.kind == 'return') {
-                        return result;
-                    } else if (result.kind == 'break') {
+                    const exit = getLoopExit(evalBlock(r, statement.block), statement.label);
+                    if (exit != null) {
+                        return exit;
+                    }
+                }
+            }
+            case 'RepeatStatement': {
+                const count = evalExpr(r, statement.count);
+                assertValue(count, 'NumberValue');
+                // a negative count runs the block zero times
+                for (let i = 0; i < count.getValue(); i++) {
+                    const exit = getLoopExit(evalBlock(r, statement.block), statement.label);
+                    if (exit != null) {
+                        return exit;
+                    }
+                }
+                return createOkResult();
+            }
+            case 'WhileStatement': {
+                while (true) {
+                    const cond = evalExpr(r, statement.cond);
+                    assertValue(cond, 'BoolValue');
+                    if (!cond.getValue()) {
                         break;
                     }
+                    const exit = getLoopExit(evalBlock(r, statement.block), statement.label);
+                    if (exit != null) {
+                        return exit;
+                    }
                 }
                 return createOkResult();
             }
+            case 'ForStatement': {
+                // the variables declared in the initializer are scoped to the loop
+                r.env.enter();
+                let result: StatementResult = createOkResult();
+                if (statement.init != null) {
+                    evalStatement(r, statement.init);
+                }
+                while (true) {
+                    if (statement.cond != null) {
+                        const cond = evalExpr(r, statement.cond);
+                        assertValue(cond, 'BoolValue');
+                        if (!cond.getValue()) {
+                            break;
+                        }
+                    }
+                    const exit = getLoopExit(evalBlock(r, statement.block), statement.label);
+                    if (exit != null) {
+                        result = exit;
+                        break;
+                    }
+                    // the step is also evaluated after continue
+                    if (statement.step != null) {
+                        evalStatement(r, statement.step);
+                    }
+                }
+                r.env.leave();
+                return result;
+            }
+            case 'ForEachFieldStatement': {
+                // the loop is lowered into the block for each field by the analyzer
+                if (statement.lowered == null) {
+                    throw new UguisuError('field iteration is not analyzed');
+                }
+                r.env.enter();
+                r.env.declare(fieldIterationTarget, evalExpr(r, statement.target));
+                let result: StatementResult = createOkResult();
+                for (const block of statement.lowered) {
+                    const exit = getLoopExit(evalBlock(r, block), statement.label);
+                    if (exit != null) {
+                        result = exit;
+                        break;
+                    }
+                }
+                r.env.leave();
+                return result;
+            }
             case 'IfStatement': {
                 const cond = evalExpr(r, statement.cond);
                 assertValue(cond, 'BoolValue');
@@ -203,7 +388,7 @@ function evalStatement(r: RunContext, statement: StatementNode): StatementResult
             }
             case 'VariableDecl': {
                 if (statement.body != null) {
-                    const bodyValue = evalExpr(r, statement.body);
+                    const bodyValue = evalStoredExpr(r, statement.body);
                     if (bodyValue.kind == 'NoneValue') {
                         throw new UguisuError('no values');
                     }
@@ -220,7 +405,7 @@ function evalStatement(r: RunContext, statement: StatementNode): StatementResult
                 } else {
                     throw new UguisuError('unsupported assign target');
                 }
-                const bodyValue = evalExpr(r, statement.body);
+                const bodyValue = evalStoredExpr(r, statement.body);
                 if (bodyValue.kind == 'NoneValue') {
                     throw new UguisuError('no values');
                 }
@@ -233,50 +418,42 @@ function evalStatement(r: RunContext, statement: StatementNode): StatementResult
                         if (symbol.value == null) {
                             throw new UguisuError('variable is not defined');
                         }
-                        assertValue(symbol.value, 'NumberValue');
-                        assertValue(bodyValue, 'NumberValue');
-                        const value = new NumberValue(symbol.value.getValue() + bodyValue.getValue());
-                        symbol.value = value;
+                        symbol.value = evalArithmeticOp('+', symbol.value, bodyValue);
                         break;
                     }
                     case '-=': {
                         if (symbol.value == null) {
                             throw new UguisuError('variable is not defined');
                         }
-                        assertValue(symbol.value, 'NumberValue');
-                        assertValue(bodyValue, 'NumberValue');
-                        const value = new NumberValue(symbol.value.getValue() - bodyValue.getValue());
-                        symbol.value = value;
+                        symbol.value = evalArithmeticOp('-', symbol.value, bodyValue);
                         break;
                     }
                     case '*=': {
                         if (symbol.value == null) {
                             throw new UguisuError('variable is not defined');
                         }
-                        assertValue(symbol.value, 'NumberValue');
-                        assertValue(bodyValue, 'NumberValue');
-                        const value = new NumberValue(symbol.value.getValue() * bodyValue.getValue());
-                        symbol.value = value;
+                        symbol.value = evalArithmeticOp('*', symbol.value, bodyValue);
                         break;
                     }
                     case '/=': {
                         if (symbol.value == null) {
                             throw new UguisuError('variable is not defined');
                         }
-                        assertValue(symbol.value, 'NumberValue');
-                        assertValue(bodyValue, 'NumberValue');
-                        const value = new NumberValue(symbol.value.getValue() / bodyValue.getValue());
-                        symbol.value = value;
+                        symbol.value = evalArithmeticOp('/', symbol.value, bodyValue);
                         break;
                     }
                     case '%=': {
                         if (symbol.value == null) {
                             throw new UguisuError('variable is not defined');
                         }
-                        assertValue(symbol.value, 'NumberValue');
-                        assertValue(bodyValue, 'NumberValue');
-                        const value = new NumberValue(symbol.value.getValue() % bodyValue.getValue());
-                        symbol.value = value;
+                        symbol.value = evalArithmeticOp('%', symbol.value, bodyValue);
+                        break;
+                    }
+                    case '**=': {
+                        if (symbol.value == null) {
+                            throw new UguisuError('variable is not defined');
+                        }
+                        symbol.value = evalArithmeticOp('**', symbol.value, bodyValue);
                         break;
                     }
                 }
@@ -286,6 +463,19 @@ function evalStatement(r: RunContext, statement: StatementNode): StatementResult
     }
 }
 
+/**
+ * Evaluate the expression as a value to be stored in a variable, a field, an array item or a parameter.
+ * The value read from a variable, a field or an array item is copied, so the struct and the array have
+ * value semantics. Use the builtin `share` to store a reference to the same value.
+*/
+function evalStoredExpr(r: RunContext, expr: ExprNode): Value {
+    const value = evalExpr(r, expr);
+    if (expr.kind == 'Identifier' || expr.kind == 'FieldAccess' || expr.kind == 'IndexAccess') {
+        return deepClone(value);
+    }
+    return value;
+}
+
 function evalExpr(r: RunContext, expr: ExprNode): Value {
     switch (expr.kind) {
         case 'Identifier': {
@@ -298,7 +488,7 @@ function evalExpr(r: RunContext, expr: ExprNode): Value {
         case 'FieldAccess': {
             const field = evalName(r, expr);
             if (field.value == null) {
-                throw new UguisuError('field not defined');
+                throw new UguisuError(`field \`${expr.name}\` is not defined`);
             }
             return field.value;
         }
@@ -312,9 +502,15 @@ function evalExpr(r: RunContext, expr: ExprNode): Value {
         case 'NumberLiteral': {
             return new NumberValue(expr.value);
         }
+        case 'FloatLiteral': {
+            return new FloatValue(expr.value);
+        }
         case 'BoolLiteral': {
             return new BoolValue(expr.value);
         }
+        case 'NoneLiteral': {
+            return new OptionalValue();
+        }
         case 'CharLiteral': {
             return new CharValue(expr.value);
         }
@@ -324,42 +520,43 @@ function evalExpr(r: RunContext, expr: ExprNode): Value {
         case 'Call': {
             const callee = evalExpr(r, expr.callee);
             assertValue(callee, 'FunctionValue');
+            // the arguments are evaluated from left to right before entering the callee
             const args = expr.args.map(i => {
-                const value = evalExpr(r, i);
+                // the native function receives the original value
+                const value = (callee.user != null) ? evalStoredExpr(r, i) : evalExpr(r, i);
                 if (value.kind == 'NoneValue') {
                     throw new UguisuError('no values');
                 }
                 return value;
             });
-            return call(r, callee, args);
+            return call(r, callee, args, expr);
         }
         case 'BinaryOp': {
             const left = evalExpr(r, expr.left);
-            const right = evalExpr(r, expr.right);
             if (left.kind == 'NoneValue') {
                 throw new UguisuError('no values');
             }
-            if (right.kind == 'NoneValue') {
-                throw new UguisuError('no values');
-            }
             if (isLogicalBinaryOperator(expr.operator)) {
                 // Logical Operation
+                // the right operand is evaluated only if the left operand does not determine the result
                 assertValue(left, 'BoolValue');
-                assertValue(right, 'BoolValue');
-                switch (expr.operator) {
-                    case '&&': {
-                        return new BoolValue(left.getValue() && right.getValue());
-                    }
-                    case '||': {
-                        return new BoolValue(left.getValue() || right.getValue());
-                    }
+                if ((expr.operator == '&&' && !left.getValue()) || (expr.operator == '||' && left.getValue())) {
+                    return left;
                 }
-                throw new UguisuError('unexpected operation');
-            } else if (isEquivalentOperator(expr.operator)) {
+                const right = evalExpr(r, expr.right);
+                assertValue(right, 'BoolValue');
+                return right;
+            }
+            const right = evalExpr(r, expr.right);
+            if (right.kind == 'NoneValue') {
+                throw new UguisuError('no values');
+            }
+            if (isEquivalentOperator(expr.operator)) {
                 // Equivalent Operation
                 switch (left.kind) {
-                    case 'NumberValue': {
-                        assertValue(right, 'NumberValue');
+                    case 'NumberValue':
+                    case 'FloatValue': {
+                        assertValue(right, left.kind);
                         switch (expr.operator) {
                             case '==': {
                                 return new BoolValue(left.getValue() == right.getValue());
@@ -406,6 +603,31 @@ function evalExpr(r: RunContext, expr: ExprNode): Value {
                         }
                         break;
                     }
+                    case 'EnumValue': {
+                        assertValue(right, 'EnumValue');
+                        const equal = (left.getEnumName() == right.getEnumName() && left.getVariant() == right.getVariant());
+                        switch (expr.operator) {
+                            case '==': {
+                                return new BoolValue(equal);
+                            }
+                            case '!=': {
+                                return new BoolValue(!equal);
+                            }
+                        }
+                        break;
+                    }
+                    case 'OptionalValue': {
+                        assertValue(right, 'OptionalValue');
+                        switch (expr.operator) {
+                            case '==': {
+                                return new BoolValue(equalValue(left, right));
+                            }
+                            case '!=': {
+                                return new BoolValue(!equalValue(left, right));
+                            }
+                        }
+                        break;
+                    }
                     case 'FunctionValue': {
                         function equalFunc(left: FunctionValue, right: FunctionValue): boolean {
                             if ((left.user != null) && (right.user != null)) {
@@ -428,7 +650,8 @@ function evalExpr(r: RunContext, expr: ExprNode): Value {
                         break;
                     }
                     case 'StructValue':
-                    case 'ArrayValue': {
+                    case 'ArrayValue':
+                    case 'TupleValue': {
                         throw new UguisuError(`type \`${getTypeName(left.kind)}\` cannot be used for equivalence comparisons.`);
                         break;
                     }
@@ -437,8 +660,10 @@ function evalExpr(r: RunContext, expr: ExprNode): Value {
             } else if (isOrderingOperator(expr.operator)) {
                 // Ordering Operation
                 switch (left.kind) {
-                    case 'NumberValue': {
-                        assertValue(right, 'NumberValue');
+                    case 'NumberValue':
+                    case 'FloatValue':
+                    case 'StringValue': {
+                        assertValue(right, left.kind);
                         switch (expr.operator) {
                             case '<': {
                                 return new BoolValue(left.getValue() < right.getValue());
@@ -457,39 +682,31 @@ function evalExpr(r: RunContext, expr: ExprNode): Value {
                     }
                     case 'BoolValue':
                     case 'CharValue':
-                    case 'StringValue':
                     case 'FunctionValue':
                     case 'StructValue':
-                    case 'ArrayValue': {
+                    case 'ArrayValue':
+                    case 'TupleValue':
+                    case 'EnumValue':
+                    case 'OptionalValue': {
                         throw new UguisuError(`type \`${getTypeName(left.kind)}\` cannot be used to compare large and small relations.`);
                     }
                 }
             } else {
                 // Arithmetic Operation
-                assertValue(left, 'NumberValue');
-                assertValue(right, 'NumberValue');
-                switch (expr.operator) {
-                    case '+': {
-                        return new NumberValue(left.getValue() + right.getValue());
-                    }
-                    case '-': {
-                        return new NumberValue(left.getValue() - right.getValue());
-                    }
-                    case '*': {
-                        return new NumberValue(left.getValue() * right.getValue());
-                    }
-                    case '/': {
-                        return new NumberValue(left.getValue() / right.getValue());
-                    }
-                    case '%': {
-                        return new NumberValue(left.getValue() % right.getValue());
-                    }
-                }
+                return evalArithmeticOp(expr.operator, left, right);
             }
             throw new UguisuError('unexpected operation');
         }
         case 'UnaryOp': {
             const value = evalExpr(r, expr.expr);
+            if (expr.operator == '-') {
+                // Arithmetic Operation
+                if (value.kind == 'FloatValue') {
+                    return new FloatValue(-value.getValue());
+                }
+                assertValue(value, 'NumberValue');
+                return new NumberValue(-value.getValue());
+            }
             // Logical Operation
             assertValue(value, 'BoolValue');
             switch (expr.operator) {
@@ -502,18 +719,144 @@ function evalExpr(r: RunContext, expr: ExprNode): Value {
         case 'StructExpr': {
             const fields = new Map<string, Symbol>();
             for (const field of expr.fields) {
-                const value = evalExpr(r, field.body);
+                const value = evalStoredExpr(r, field.body);
                 const symbol = new Symbol(value);
                 fields.set(field.name, symbol);
             }
             return new StructValue(fields);
         }
+        case 'IfExpr': {
+            const cond = evalExpr(r, expr.cond);
+            assertValue(cond, 'BoolValue');
+            const result = evalBlock(r, cond.getValue() ? expr.thenBlock : expr.elseBlock);
+            if (result.kind != 'ok' || result.value == null) {
+                throw new UguisuError('the if expression did not yield a value');
+            }
+            return result.value;
+        }
         case 'ArrayNode': {
             const items = expr.items.map(x => {
-                const value = evalExpr(r, x);
+                const value = evalStoredExpr(r, x);
                 return new Symbol(value);
             });
             return new ArrayValue(items);
         }
+        case 'TupleNode': {
+            const items = expr.items.map(x => {
+                const value = evalStoredExpr(r, x);
+                return new Symbol(value);
+            });
+            return new TupleValue(items);
+        }
+        case 'ConditionalExpr': {
+            const cond = evalExpr(r, expr.cond);
+            assertValue(cond, 'BoolValue');
+            return evalExpr(r, cond.getValue() ? expr.thenExpr : expr.elseExpr);
+        }
+        case 'MatchExpr': {
+            const target = evalExpr(r, expr.target);
+            for (const arm of expr.arms) {
+                if (matchPattern(arm.pattern, target)) {
+                    return evalExpr(r, arm.body);
+                }
+            }
+            throw new UguisuError('no arm of the match expression matched');
+        }
+        case 'LambdaExpr': {
+            // the local variables are captured by value when the lambda is created
+            return FunctionValue.create(expr, r.env.capture(getReferredNames(expr)));
+        }
+    }
+}
+
+/**
+ * Get the identifiers used in the node. They are the candidates of the captured variables.
+*/
+function getReferredNames(node: AstNode): string[] {
+    const names: string[] = [];
+    const stack: AstNode[] = [node];
+    while (stack.length > 0) {
+        const current = stack.pop()!;
+        if (current.kind == 'Identifier' && !names.includes(current.name)) {
+            names.push(current.name);
+        }
+        stack.push(...getChildNodes(current));
+    }
+    return names;
+}
+
+function matchPattern(pattern: MatchPattern, value: Value): boolean {
+    switch (pattern.kind) {
+        case 'wildcard': {
+            return true;
+        }
+        case 'variant': {
+            return (value.kind == 'EnumValue' && value.getVariant() == pattern.name);
+        }
+        case 'number': {
+            return (value.kind == 'NumberValue' && value.getValue() == pattern.value);
+        }
+    }
+}
+
+/**
+ * The number values and the float values are calculated separately.
+ * Dividing a number by zero, the negative exponent and the power that overflows are runtime errors.
+*/
+function evalArithmeticOp(operator: ArithmeticOperator, left: Value, right: Value): Value {
+    if (left.kind == 'StringValue') {
+        assertValue(right, 'StringValue');
+        if (operator != '+') {
+            throw new UguisuError(`type \`string\` does not support the operator \`${operator}\`.`);
+        }
+        return new StringValue(left.getValue() + right.getValue());
+    }
+    if (left.kind == 'FloatValue') {
+        assertValue(right, 'FloatValue');
+        switch (operator) {
+            case '+': {
+                return new FloatValue(left.getValue() + right.getValue());
+            }
+            case '-': {
+                return new FloatValue(left.getValue() - right.getValue());
+            }
+            case '*': {
+                return new FloatValue(left.getValue() * right.getValue());
+            }
+            case '/': {
+                return new FloatValue(left.getValue() / right.getValue());
+            }
+            case '%': {
+                return new FloatValue(left.getValue() % right.getValue());
+            }
+        }
+    }
+    assertValue(left, 'NumberValue');
+    assertValue(right, 'NumberValue');
+    switch (operator) {
+        case '+': {
+            return new NumberValue(left.getValue() + right.getValue());
+        }
+        case '-': {
+            return new NumberValue(left.getValue() - right.getValue());
+        }
+        case '*': {
+            return new NumberValue(left.getValue() * right.getValue());
+        }
+        case '/': {
+            if (right.getValue() == 0) {
+                throw new UguisuError('division by zero');
+            }
+            return new NumberValue(left.getValue() / right.getValue());
+        }
+        case '%': {
+            if (right.getValue() == 0) {
+                throw new UguisuError('modulo by zero');
+            }
+            return new NumberValue(left.getValue() % right.getValue());
+        }
+        case '**': {
+            return new NumberValue(powNumber(left.getValue(), right.getValue()));
+        }
     }
 }
@@ -1,14 +1,14 @@
-import { UguisuError } from '../misc/errors.js';
+import { Diagnostic, formatDiagnostic, UguisuError } from '../misc/errors.js';
 import { UguisuOptions } from '../misc/options.js';
 import { Trace } from '../misc/trace.js';
-import { FunctionDecl } from '../syntax/tools.js';
+import { AstNode, Call, FunctionDecl, getSpan, LambdaExpr } from '../syntax/tools.js';
 
 export class RunningEnv {
     layers: Map<string, Symbol>[];
     trace?: Trace;
 
     constructor(baseEnv?: RunningEnv, trace?: Trace) {
-        this.trace = trace;
+        this.trace = trace ?? baseEnv?.trace;
         if (baseEnv != null) {
             this.layers = [...baseEnv.layers];
         } else {
@@ -32,6 +32,41 @@ export class RunningEnv {
         return undefined;
     }
 
+    /**
+     * Set the existing symbol to the current layer. The symbol is shared with the other layer.
+    */
+    set(name: string, symbol: Symbol) {
+        this.trace?.log(`set symbol: ${name}`);
+        this.layers[0].set(name, symbol);
+    }
+
+    /**
+     * Enumerate the symbols of the current layer.
+    */
+    entries(): IterableIterator<[string, Symbol]> {
+        return this.layers[0].entries();
+    }
+
+    /**
+     * Create the environment of the lambda. The local variables of the specified names are copied
+     * into the new layer, and the global layer is shared.
+    */
+    capture(names: string[]): RunningEnv {
+        const env = new RunningEnv(undefined, this.trace);
+        const globalLayer = this.layers[this.layers.length - 1];
+        const captured = new Map<string, Symbol>();
+        for (const name of names) {
+            const layer = this.layers.find(x => x.has(name));
+            if (layer == null || layer == globalLayer) {
+                continue;
+            }
+            const value = layer.get(name)!.value;
+            captured.set(name, new Symbol(value != null ? deepClone(value) : undefined));
+        }
+        env.layers = [captured, globalLayer];
+        return env;
+    }
+
     enter() {
         this.trace?.log(`enter scope`);
         this.layers.unshift(new Map());
@@ -53,12 +88,15 @@ export class Symbol {
     }
 }
 
-export type StatementResult = OkResult | ReturnResult | BreakResult;
+export type StatementResult = OkResult | ReturnResult | BreakResult | ContinueResult;
 
-export type OkResult = { kind: 'ok' };
+/**
+ * The value is set when the statement yields a value.
+*/
+export type OkResult = { kind: 'ok', value?: Value };
 
-export function createOkResult(): OkResult {
-    return { kind: 'ok' };
+export function createOkResult(value?: Value): OkResult {
+    return { kind: 'ok', value };
 }
 
 export type ReturnResult = { kind: 'return', value: Value };
@@ -67,24 +105,40 @@ export function createReturnResult(value: Value): ReturnResult {
     return { kind: 'return', value };
 }
 
-export type BreakResult = { kind: 'break' };
+/**
+ * The label is set when the break targets the labeled loop.
+*/
+export type BreakResult = { kind: 'break', label?: string };
+
+export function createBreakResult(label?: string): BreakResult {
+    return { kind: 'break', label };
+}
+
+/**
+ * The label is set when the continue targets the labeled loop.
+*/
+export type ContinueResult = { kind: 'continue', label?: string };
 
-export function createBreakResult(): BreakResult {
-    return { kind: 'break' };
+export function createContinueResult(label?: string): ContinueResult {
+    return { kind: 'continue', label };
 }
 
 //#region Values
 
-export type Value = NoneValue | NumberValue | BoolValue | CharValue | StringValue | StructValue | ArrayValue | FunctionValue;
+export type Value = NoneValue | NumberValue | FloatValue | BoolValue | CharValue | StringValue | StructValue | ArrayValue | TupleValue | EnumValue | OptionalValue | FunctionValue;
 
 export type ValueOf<T extends Value['kind']> =
     T extends 'NoneValue' ? NoneValue :
     T extends 'NumberValue' ? NumberValue :
+    T extends 'FloatValue' ? FloatValue :
     T extends 'BoolValue' ? BoolValue :
     T extends 'CharValue' ? CharValue :
     T extends 'StringValue' ? StringValue :
     T extends 'StructValue' ? StructValue :
     T extends 'ArrayValue' ? ArrayValue :
+    T extends 'TupleValue' ? TupleValue :
+    T extends 'EnumValue' ? EnumValue :
+    T extends 'OptionalValue' ? OptionalValue :
     T extends 'FunctionValue' ? FunctionValue :
     never;
 
@@ -96,6 +150,9 @@ export function getTypeName(valueKind: Value['kind']): string {
         case 'NumberValue': {
             return 'number';
         }
+        case 'FloatValue': {
+            return 'float';
+        }
         case 'BoolValue': {
             return 'bool';
         }
@@ -111,6 +168,15 @@ export function getTypeName(valueKind: Value['kind']): string {
         case 'ArrayValue': {
             return 'array';
         }
+        case 'TupleValue': {
+            return 'tuple';
+        }
+        case 'EnumValue': {
+            return 'enum';
+        }
+        case 'OptionalValue': {
+            return 'optional';
+        }
         case 'FunctionValue': {
             return 'fn';
         }
@@ -142,6 +208,18 @@ export class NumberValue {
     }
 }
 
+export class FloatValue {
+    kind: 'FloatValue';
+    private _value: number;
+    constructor(value: number) {
+        this.kind = 'FloatValue';
+        this._value = value;
+    }
+    getValue(): number {
+        return this._value;
+    }
+}
+
 export class BoolValue {
     kind: 'BoolValue';
     private _value: boolean;
@@ -201,7 +279,10 @@ export class ArrayValue {
         this._items = items;
     }
     at(index: number): Symbol | undefined {
-        return this._items.at(index);
+        if (!Number.isInteger(index) || index < 0) {
+            return undefined;
+        }
+        return this._items[index];
     }
     insert(index: number, item: Symbol) {
         this._items.splice(index, 0, item);
@@ -214,10 +295,66 @@ export class ArrayValue {
     }
 }
 
+/**
+ * The tuple has a fixed number of items.
+*/
+export class TupleValue {
+    kind: 'TupleValue';
+    private _items: Symbol[];
+    constructor(items: Symbol[]) {
+        this.kind = 'TupleValue';
+        this._items = items;
+    }
+    at(index: number): Symbol | undefined {
+        if (!Number.isInteger(index) || index < 0) {
+            return undefined;
+        }
+        return this._items[index];
+    }
+    count(): number {
+        return this._items.length;
+    }
+}
+
+/**
+ * The variant of the enum (e.g. `Color.Red`).
+*/
+export class EnumValue {
+    kind: 'EnumValue';
+    private _enumName: string;
+    private _variant: string;
+    constructor(enumName: string, variant: string) {
+        this.kind = 'EnumValue';
+        this._enumName = enumName;
+        this._variant = variant;
+    }
+    getEnumName(): string {
+        return this._enumName;
+    }
+    getVariant(): string {
+        return this._variant;
+    }
+}
+
+/**
+ * The value of the optional type. It has no inner value if it is `none`.
+*/
+export class OptionalValue {
+    kind: 'OptionalValue';
+    private _value?: Value;
+    constructor(value?: Value) {
+        this.kind = 'OptionalValue';
+        this._value = value;
+    }
+    getValue(): Value | undefined {
+        return this._value;
+    }
+}
+
 export class FunctionValue {
     kind: 'FunctionValue';
     user?: {
-        node: FunctionDecl;
+        node: FunctionDecl | LambdaExpr;
         env: RunningEnv; // lexical scope
     };
     native?: NativeFuncHandler;
@@ -226,7 +363,7 @@ export class FunctionValue {
         this.user = user;
         this.native = native;
     }
-    static create(node: FunctionDecl, env: RunningEnv): FunctionValue {
+    static create(node: FunctionDecl | LambdaExpr, env: RunningEnv): FunctionValue {
         return new FunctionValue({ node, env }, undefined);
     }
     static createNative(native: NativeFuncHandler): FunctionValue {
@@ -234,6 +371,157 @@ export class FunctionValue {
     }
 }
 
-export type NativeFuncHandler = (args: Value[], options: UguisuOptions) => Value;
+/**
+ * The native function receives the call expression to report the runtime error at the call site.
+*/
+export type NativeFuncHandler = (args: Value[], options: UguisuOptions, callSite?: Call) => Value;
+
+/**
+ * Create the runtime error with the position of the node, in the same form as the diagnostics of the analysis.
+*/
+export function createRuntimeError(message: string, node?: AstNode): UguisuError {
+    if (node == null) {
+        return new UguisuError(message);
+    }
+    const span = getSpan(node);
+    const diagnostic: Diagnostic = { severity: 'error', message, start: span.start, end: span.end };
+    return new UguisuError(formatDiagnostic(diagnostic), [diagnostic]);
+}
+
+/**
+ * Copy the value. The struct, the array and the tuple are copied recursively.
+*/
+export function deepClone(value: Value): Value {
+    switch (value.kind) {
+        case 'StructValue': {
+            const fields = new Map<string, Symbol>();
+            for (const name of value.getFieldNames()) {
+                const field = value.lookupField(name)!;
+                fields.set(name, new Symbol(field.value != null ? deepClone(field.value) : undefined));
+            }
+            return new StructValue(fields);
+        }
+        case 'ArrayValue':
+        case 'TupleValue': {
+            const items: Symbol[] = [];
+            for (let i = 0; i < value.count(); i++) {
+                const item = value.at(i)!;
+                items.push(new Symbol(item.value != null ? deepClone(item.value) : undefined));
+            }
+            return (value.kind == 'ArrayValue') ? new ArrayValue(items) : new TupleValue(items);
+        }
+        case 'OptionalValue': {
+            const inner = value.getValue();
+            return new OptionalValue(inner != null ? deepClone(inner) : undefined);
+        }
+        default: {
+            return value;
+        }
+    }
+}
+
+/**
+ * Compare the values structurally. The struct, the array and the tuple are compared recursively.
+*/
+export function equalValue(x: Value, y: Value): boolean {
+    switch (x.kind) {
+        case 'NoneValue': {
+            return (y.kind == 'NoneValue');
+        }
+        case 'NumberValue':
+        case 'FloatValue':
+        case 'BoolValue':
+        case 'CharValue':
+        case 'StringValue': {
+            return (y.kind == x.kind && (y as typeof x).getValue() == x.getValue());
+        }
+        case 'StructValue': {
+            if (y.kind != 'StructValue') {
+                return false;
+            }
+            const names = [...x.getFieldNames()];
+            if (names.length != [...y.getFieldNames()].length) {
+                return false;
+            }
+            return names.every(name => {
+                const xField = x.lookupField(name)!;
+                const yField = y.lookupField(name);
+                if (yField == null) {
+                    return false;
+                }
+                if (xField.value == null || yField.value == null) {
+                    return (xField.value == null && yField.value == null);
+                }
+                return equalValue(xField.value, yField.value);
+            });
+        }
+        case 'ArrayValue':
+        case 'TupleValue': {
+            if (y.kind != x.kind) {
+                return false;
+            }
+            y = y as ArrayValue | TupleValue;
+            if (y.count() != x.count()) {
+                return false;
+            }
+            for (let i = 0; i < x.count(); i++) {
+                const xItem = x.at(i)!;
+                const yItem = y.at(i)!;
+                if (xItem.value == null || yItem.value == null) {
+                    if (xItem.value != null || yItem.value != null) {
+                        return false;
+                    }
+                } else if (!equalValue(xItem.value, yItem.value)) {
+                    return false;
+                }
+            }
+            return true;
+        }
+        case 'EnumValue': {
+            return (y.kind == 'EnumValue' && y.getEnumName() == x.getEnumName() && y.getVariant() == x.getVariant());
+        }
+        case 'OptionalValue': {
+            if (y.kind != 'OptionalValue') {
+                return false;
+            }
+            const xInner = x.getValue();
+            const yInner = y.getValue();
+            if (xInner == null || yInner == null) {
+                return (xInner == null && yInner == null);
+            }
+            return equalValue(xInner, yInner);
+        }
+        case 'FunctionValue': {
+            if (y.kind != 'FunctionValue') {
+                return false;
+            }
+            if (x.user != null && y.user != null) {
+                return (x.user.node == y.user.node);
+            }
+            if (x.native != null && y.native != null) {
+                return (x.native == y.native);
+            }
+            return false;
+        }
+    }
+}
+
+/**
+ * The exponent must be a non-negative integer. The base may have the fraction part (e.g. the result of the division),
+ * and the result whose magnitude is out of the safe integer range is an overflow.
+*/
+export function powNumber(x: number, y: number): number {
+    if (y < 0) {
+        throw new UguisuError('negative exponent');
+    }
+    if (!Number.isInteger(y)) {
+        throw new UguisuError('non-integer exponent');
+    }
+    const value = x ** y;
+    if (!Number.isFinite(value) || Math.abs(value) > Number.MAX_SAFE_INTEGER) {
+        throw new UguisuError('integer overflow');
+    }
+    return value;
+}
 
 //#endregion Values
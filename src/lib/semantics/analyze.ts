@@ -1,46 +1,73 @@
 import charRegex from 'char-regex';
-import { UguisuError } from '../misc/errors.js';
+import { Diagnostic, formatDiagnostic, UguisuError } from '../misc/errors.js';
 import { ProjectInfo } from '../project-file.js';
 import {
+    ArithmeticOperator,
     AstNode,
+    Call,
+    createFieldAccess,
+    createIdentifier,
+    createStringLiteral,
+    createVariableDecl,
     ExprNode,
+    fieldIterationTarget,
     FileNode,
+    FunctionDecl,
+    IfExpr,
+    IfStatement,
+    isArithmeticUnaryOperator,
     isEquivalentOperator,
     isExprNode,
     isLogicalBinaryOperator,
     isOrderingOperator,
+    LambdaExpr,
+    LoopNode,
+    MatchExpr,
     ReferenceExpr,
     SourceFile,
     StatementCoreNode,
     StatementNode,
-    TyLabel
+    TyLabelNode,
+    VariableDecl
 } from '../syntax/tools.js';
 import * as builtins from './builtins.js';
 import {
     AnalysisEnv,
     AnalyzeContext,
+    AnalyzeOptions,
     anyType,
     arrayType,
     badType,
     boolType,
     charType,
     compareType,
+    createArrayType,
+    createEnumSymbol,
     createExprSymbol,
     createFunctionSymbol,
     createFunctionType,
     createNamedType,
+    createOptionalType,
     createStructSymbol,
+    createTupleType,
     createVariableSymbol,
     dispatchTypeError,
+    floatType,
     FnSymbol,
+    FunctionType,
+    getArrayItemType,
     getTypeString,
     isPendingType,
     isValidType,
     numberType,
     pendingType,
     stringType,
+    StructSymbol,
     Symbol,
     Type,
+    ValidType,
+    VariableDeclKind,
+    VariableSymbol,
     voidType
 } from './tools.js';
 
@@ -48,15 +75,18 @@ export type AnalyzeResult = {
     success: boolean,
     errors: string[],
     warnings: string[],
+    /** the errors and the warnings with the positions */
+    diagnostics: Diagnostic[],
 };
 
 export function analyze(
     source: SourceFile,
     env: AnalysisEnv,
     symbolTable: Map<AstNode, Symbol>,
-    projectInfo: ProjectInfo
+    projectInfo: ProjectInfo,
+    options: AnalyzeOptions = {}
 ): AnalyzeResult {
-    const a = new AnalyzeContext(env, symbolTable, projectInfo);
+    const a = new AnalyzeContext(env, symbolTable, projectInfo, options);
     builtins.setDeclarations(a);
 
     // 1st phase: declare
@@ -68,28 +98,100 @@ export function analyze(
         resolveTopLevel(node, a);
     }
     // 3rd phase: analyze
+    // the global variables are analyzed before the functions that use them
     for (const node of source.decls) {
-        analyzeTopLevel(node, a);
+        if (node.kind == 'VariableDecl') {
+            analyzeTopLevel(node, a);
+        }
+    }
+    for (const node of source.decls) {
+        if (node.kind != 'VariableDecl') {
+            analyzeTopLevel(node, a);
+        }
     }
 
     if (a.isUsedAnyType) {
         a.dispatchWarn('type checking of array elements is not supported yet.');
     }
 
+    return createAnalyzeResult(a);
+}
+
+/**
+ * Analyze the single expression. The expression is analyzed outside of any functions,
+ * so it can refer to the builtins and the symbols declared in the environment.
+*/
+export function analyzeExpression(
+    node: ExprNode,
+    env: AnalysisEnv,
+    symbolTable: Map<AstNode, Symbol>,
+    projectInfo: ProjectInfo,
+    options: AnalyzeOptions = {}
+): AnalyzeResult {
+    const a = new AnalyzeContext(env, symbolTable, projectInfo, options);
+    builtins.setDeclarations(a);
+    const funcSymbol = createFunctionSymbol('(expression)', [], createFunctionType([], voidType), []);
+    analyzeExpr(node, funcSymbol, a);
+    return createAnalyzeResult(a);
+}
+
+function createAnalyzeResult(a: AnalyzeContext): AnalyzeResult {
     return {
         success: (a.error.length == 0),
-        errors: a.error,
-        warnings: a.warn,
+        errors: a.error.map(x => formatDiagnostic(x)),
+        warnings: a.warn.map(x => formatDiagnostic(x)),
+        diagnostics: [...a.error, ...a.warn],
     };
 }
 
-function analyzeReferenceExpr(node: ReferenceExpr, funcSymbol: FnSymbol, a: AnalyzeContext): Symbol | undefined {
+/**
+ * Get the symbol of the variable that the reference starts from (e.g. `p` of `p.items[0]`).
+ * It is undefined when the reference does not start from a variable (e.g. `f().x`).
+ * The reference must have been analyzed.
+*/
+function getRootSymbol(node: ExprNode, a: AnalyzeContext): Symbol | undefined {
+    switch (node.kind) {
+        case 'Identifier': {
+            return a.symbolTable.get(node);
+        }
+        case 'FieldAccess':
+        case 'IndexAccess': {
+            return getRootSymbol(node.target, a);
+        }
+        default: {
+            return undefined;
+        }
+    }
+}
+
+function isDeclaredAs(symbol: Symbol | undefined, declKind: VariableDeclKind): boolean {
+    return (symbol?.kind == 'VariableSymbol' && symbol.declKind == declKind);
+}
+
+/**
+ * Analyze the reference and get the symbol that it refers to.
+ * The variable assigned by `isAssignTarget` is not marked as used, since it is not read
+ * (the target of the field or the item assignment reads the variable).
+*/
+function analyzeReferenceExpr(node: ReferenceExpr, funcSymbol: FnSymbol, a: AnalyzeContext, isAssignTarget: boolean = false): Symbol | undefined {
     switch (node.kind) {
         case 'Identifier': {
             // get symbol
             const symbol = a.env.get(node.name);
 
+            if (symbol != null) {
+                a.symbolTable.set(node, symbol);
+                if (symbol.kind == 'VariableSymbol' && !isAssignTarget) {
+                    a.usedVars.add(symbol);
+                }
+            }
+
             if (symbol == null) {
+                // the nested function does not capture the local variables
+                if (a.enclosingEnv?.get(node.name) != null) {
+                    a.dispatchError(`cannot use the local variable \`${node.name}\` of the enclosing function.`, node);
+                    return undefined;
+                }
                 a.dispatchError('unknown identifier.', node);
                 return undefined;
             }
@@ -97,6 +199,20 @@ function analyzeReferenceExpr(node: ReferenceExpr, funcSymbol: FnSymbol, a: Anal
             return symbol;
         }
         case 'FieldAccess': {
+            // the variant of the enum (e.g. `Color.Red`)
+            // the enum itself is not a value, so it is resolved without analyzing the target as an expression
+            if (node.target.kind == 'Identifier') {
+                const enumSymbol = a.env.get(node.target.name);
+                if (enumSymbol != null && enumSymbol.kind == 'EnumSymbol') {
+                    a.symbolTable.set(node.target, enumSymbol);
+                    if (!enumSymbol.variants.includes(node.name)) {
+                        a.dispatchError(`unknown variant \`${node.name}\` for enum \`${enumSymbol.name}\`.`, node);
+                        return undefined;
+                    }
+                    return createVariableSymbol(createNamedType(enumSymbol.name), true, 'const');
+                }
+            }
+
             // analyze target
             const targetTy = analyzeExpr(node.target, funcSymbol, a);
 
@@ -110,35 +226,43 @@ function analyzeReferenceExpr(node: ReferenceExpr, funcSymbol: FnSymbol, a: Anal
             switch (targetTy.kind) {
                 case 'NamedType': {
                     // get target symbol
-                    const symbol = a.env.get(targetTy.name)!;
+                    const symbol = a.env.get(targetTy.name);
 
-                    if (symbol.kind == 'StructSymbol') {
-                        // get field symbol
-                        const field = symbol.fields.get(node.name);
+                    if (symbol == null || symbol.kind != 'StructSymbol') {
+                        a.dispatchError(`type \`${targetTy.name}\` has no fields.`, node);
+                        return undefined;
+                    }
 
-                        // if specified field name is invalid
-                        if (field == null) {
-                            a.dispatchError('unknown field name.', node);
-                            return undefined;
-                        }
+                    // get field symbol
+                    const field = symbol.fields.get(node.name);
 
-                        return field;
-                    } else {
-                        a.dispatchError('invalid field access.', node);
+                    // if specified field name is invalid
+                    if (field == null) {
+                        a.dispatchError(`unknown field \`${node.name}\` for type \`${targetTy.name}\`.`, node);
                         return undefined;
                     }
-                    break;
+
+                    a.symbolTable.set(node, field);
+                    return field;
                 }
-                case 'GenericType': {
-                    throw new UguisuError('not implemented yet.'); // TODO
+                case 'TupleType': {
+                    // the item is accessed with its index (e.g. `x.0`)
+                    const index = Number(node.name);
+                    if (!Number.isInteger(index) || index >= targetTy.elementTypes.length) {
+                        a.dispatchError(`unknown field \`${node.name}\` for type \`${getTypeString(targetTy)}\`.`, node);
+                        return undefined;
+                    }
+                    return createVariableSymbol(targetTy.elementTypes[index], true, 'var');
                 }
                 case 'AnyType': {
                     // TODO: Ensure that the type `any` is handled correctly.
                     return undefined;
                 }
                 case 'FunctionType':
+                case 'GenericType':
+                case 'OptionalType':
                 case 'VoidType': {
-                    a.dispatchError('invalid field access');
+                    a.dispatchError(`type \`${getTypeString(targetTy)}\` has no fields.`, node);
                     return undefined;
                 }
             }
@@ -174,8 +298,8 @@ function analyzeReferenceExpr(node: ReferenceExpr, funcSymbol: FnSymbol, a: Anal
                 return undefined;
             }
 
-            // create index symbol
-            const symbol = createVariableSymbol(anyType, true);
+            // create index symbol with the item type
+            const symbol = createVariableSymbol(getArrayItemType(targetTy) ?? anyType, true, 'var');
             return symbol;
         }
     }
@@ -191,6 +315,11 @@ function getTypeFromSymbol(symbol: Symbol, errorNode: AstNode, a: AnalyzeContext
         case 'StructSymbol': {
             return createNamedType(symbol.name);
         }
+        case 'EnumSymbol': {
+            // only the variants are the values of the enum type (e.g. `Color.Red`)
+            a.dispatchError(`enum \`${symbol.name}\` cannot be used as a value; specify the variant.`, errorNode);
+            return badType;
+        }
         case 'VariableSymbol': {
             return symbol.ty;
         }
@@ -200,16 +329,83 @@ function getTypeFromSymbol(symbol: Symbol, errorNode: AstNode, a: AnalyzeContext
     }
 }
 
-function resolveTyLabel(node: TyLabel, a: AnalyzeContext): Type {
-    // builtin type
-    switch (node.name) {
-        case 'number':
-        case 'bool':
-        case 'char':
-        case 'string':
-        case 'array': {
-            return createNamedType(node.name);
+const builtinTypeNames = ['number', 'float', 'bool', 'char', 'string', 'array'];
+
+/**
+ * Get the operand type of the arithmetic and ordering operations from the type of the left operand.
+*/
+function getNumericType(ty: Type): Type {
+    if (ty.kind == 'NamedType' && ty.name == 'float') {
+        return floatType;
+    }
+    return numberType;
+}
+
+/**
+ * Get the operand type of the ordering operation from the type of the left operand.
+ * Strings are compared in lexicographic order.
+*/
+function getOrderingType(ty: Type): Type {
+    if (ty.kind == 'NamedType' && ty.name == 'string') {
+        return stringType;
+    }
+    return getNumericType(ty);
+}
+
+/**
+ * Get the operand type of the arithmetic operation from the operator and the type of the left operand.
+ * The `+` operator also concatenates strings. The `**` operator is defined only for numbers.
+*/
+function getArithmeticType(operator: ArithmeticOperator, ty: Type): Type {
+    if (operator == '+' && ty.kind == 'NamedType' && ty.name == 'string') {
+        return stringType;
+    }
+    if (operator == '**') {
+        return numberType;
+    }
+    return getNumericType(ty);
+}
+
+/**
+ * Resolve the type label to the type.
+ * The `voidError` is reported when `void` is specified, so it describes where the type label is used.
+*/
+function resolveTyLabel(node: TyLabelNode, a: AnalyzeContext, voidError: string = 'type `void` cannot be used here.'): Type {
+    // function type
+    if (node.kind == 'FunctionTyLabel') {
+        const paramTypes = node.params.map(x => resolveTyLabel(x, a, 'parameters cannot have type `void`.'));
+        // `void` can be specified only as the return type of the function type
+        let returnType: Type;
+        if (node.returnTy.kind == 'TyLabel' && node.returnTy.name == 'void') {
+            returnType = voidType;
+        } else {
+            returnType = resolveTyLabel(node.returnTy, a);
         }
+        return createFunctionType(paramTypes, returnType);
+    }
+
+    // optional type
+    if (node.kind == 'OptionalTyLabel') {
+        return createOptionalType(resolveTyLabel(node.innerTy, a, 'type `void` cannot be optional.'));
+    }
+
+    // tuple type
+    if (node.kind == 'TupleTyLabel') {
+        return createTupleType(node.items.map(x => resolveTyLabel(x, a, 'tuple elements cannot have type `void`.')));
+    }
+
+    // `void` is not the type of any value
+    if (node.name == 'void') {
+        a.dispatchError(voidError, node);
+        return badType;
+    }
+
+    // builtin type
+    if (node.name == 'array') {
+        return arrayType;
+    }
+    if (builtinTypeNames.includes(node.name)) {
+        return createNamedType(node.name);
     }
 
     // try get user defined type
@@ -220,7 +416,8 @@ function resolveTyLabel(node: TyLabel, a: AnalyzeContext): Type {
     }
 
     switch (symbol.kind) {
-        case 'StructSymbol': {
+        case 'StructSymbol':
+        case 'EnumSymbol': {
             return createNamedType(node.name);
         }
         case 'FnSymbol':
@@ -238,7 +435,7 @@ function declareTopLevel(node: FileNode, a: AnalyzeContext) {
         case 'FunctionDecl': {
             // check for duplicate
             if (a.env.get(node.name) != null) {
-                a.dispatchError(`\`${node.name}\` is already declared.`);
+                a.dispatchError(`\`${node.name}\` is already declared.`, node);
                 return;
             }
 
@@ -248,10 +445,20 @@ function declareTopLevel(node: FileNode, a: AnalyzeContext) {
             }
 
             // make param list
-            const params = node.params.map(x => ({ name: x.name }));
+            const params = node.params.map(x => ({ name: x.name, hasDefault: (x.defaultValue != null) }));
+
+            // the parameters with the default values must be at the end
+            let hasDefault = false;
+            for (const param of node.params) {
+                if (param.defaultValue != null) {
+                    hasDefault = true;
+                } else if (hasDefault) {
+                    a.dispatchError('a parameter without a default value cannot follow a parameter with a default value.', param);
+                }
+            }
 
             // declare function
-            const symbol = createFunctionSymbol(params, pendingType, []);
+            const symbol = createFunctionSymbol(node.name, params, pendingType, []);
             a.symbolTable.set(node, symbol);
             a.env.set(node.name, symbol);
             break;
@@ -259,7 +466,7 @@ function declareTopLevel(node: FileNode, a: AnalyzeContext) {
         case 'StructDecl': {
             // check for duplicate
             if (a.env.get(node.name) != null) {
-                a.dispatchError(`\`${node.name}\` is already declared.`);
+                a.dispatchError(`\`${node.name}\` is already declared.`, node);
                 return;
             }
 
@@ -271,8 +478,9 @@ function declareTopLevel(node: FileNode, a: AnalyzeContext) {
             // make fields
             const fields = new Map<string, Symbol>();
             for (const field of node.fields) {
-                const fieldSymbol = createVariableSymbol(pendingType, true);
+                const fieldSymbol = createVariableSymbol(pendingType, true, 'var');
                 fields.set(field.name, fieldSymbol);
+                a.symbolTable.set(field, fieldSymbol);
             }
 
             // declare struct
@@ -281,6 +489,47 @@ function declareTopLevel(node: FileNode, a: AnalyzeContext) {
             a.env.set(node.name, symbol);
             break;
         }
+        case 'EnumDecl': {
+            // check for duplicate
+            if (a.env.get(node.name) != null) {
+                a.dispatchError(`\`${node.name}\` is already declared.`, node);
+                return;
+            }
+
+            // export specifier
+            if (node.exported) {
+                a.dispatchWarn('exported function is not supported yet.', node);
+            }
+
+            // make variants
+            const variants: string[] = [];
+            for (const variant of node.variants) {
+                if (variants.includes(variant.name)) {
+                    a.dispatchError(`variant \`${variant.name}\` is duplicated.`, variant);
+                    continue;
+                }
+                variants.push(variant.name);
+            }
+
+            // declare enum
+            const symbol: Symbol = createEnumSymbol(node.name, variants);
+            a.symbolTable.set(node, symbol);
+            a.env.set(node.name, symbol);
+            break;
+        }
+        case 'VariableDecl': {
+            // check for duplicate
+            if (a.env.get(node.name) != null) {
+                a.dispatchError(`\`${node.name}\` is already declared.`, node);
+                return;
+            }
+
+            // declare variable
+            // The symbol is replaced with the analyzed one in the 3rd phase.
+            const symbol = createVariableSymbol(pendingType, false, node.isConst ? 'const' : 'var');
+            a.env.set(node.name, symbol);
+            break;
+        }
     }
 }
 
@@ -299,33 +548,8 @@ function resolveTopLevel(node: FileNode, a: AnalyzeContext) {
                 return;
             }
 
-            // make return type
-            let returnTy: Type;
-            if (node.returnTy != null) {
-                returnTy = resolveTyLabel(node.returnTy, a);
-            } else {
-                returnTy = voidType;
-            }
-
-            // make params type
-            let paramsTy: Type[] = [];
-            for (let i = 0; i < symbol.params.length; i++) {
-                const paramNode = node.params[i];
-
-                // if param type is not specified
-                if (paramNode.ty == null) {
-                    a.dispatchError('parameter type missing.', paramNode);
-                    paramsTy.push(badType);
-                    continue;
-                }
-
-                // get param type
-                const paramTy = resolveTyLabel(paramNode.ty, a);
-                paramsTy.push(paramTy);
-            }
-
             // replace function type
-            symbol.ty = createFunctionType(paramsTy, returnTy);
+            symbol.ty = resolveFunctionType(node, a);
             break;
         }
         case 'StructDecl': {
@@ -354,10 +578,15 @@ function resolveTopLevel(node: FileNode, a: AnalyzeContext) {
                 }
 
                 // replace field type
-                fieldSymbol.ty = resolveTyLabel(field.ty, a);
+                fieldSymbol.ty = resolveTyLabel(field.ty, a, 'fields cannot have type `void`.');
             }
             break;
         }
+        case 'EnumDecl':
+        case 'VariableDecl': {
+            // nop
+            break;
+        }
     }
 }
 
@@ -376,57 +605,548 @@ function analyzeTopLevel(node: FileNode, a: AnalyzeContext) {
                 return;
             }
 
-            // check the function type is valid
-            if (!isValidType(symbol.ty)) {
-                if (isPendingType(symbol.ty)) {
-                    a.dispatchError('function is not defined yet.', node);
-                }
+            analyzeFunctionBody(node, symbol, a);
+            break;
+        }
+        case 'StructDecl': {
+            // get struct symbol
+            const symbol = a.env.get(node.name);
+            if (symbol == null || symbol.kind != 'StructSymbol') {
                 return;
             }
 
-            a.env.enter();
-
-            // set function params to the env
-            for (let i = 0; i < node.params.length; i++) {
-                const paramSymbol = createVariableSymbol(symbol.ty.paramTypes[i], true);
-                a.symbolTable.set(node.params[i], paramSymbol);
-                a.env.set(node.params[i].name, paramSymbol);
-            }
-
-            // analyze function body
-            for (const statement of node.body) {
-                analyzeNode(statement, false, symbol, a);
+            // a struct that contains itself has infinite size
+            if (containsStruct(symbol, symbol.name, [], a)) {
+                a.dispatchError(`struct \`${symbol.name}\` is recursive and has infinite size.`, node);
             }
-
-            a.env.leave();
             break;
         }
-        case 'StructDecl': {
+        case 'EnumDecl': {
             // nop
             break;
         }
+        case 'VariableDecl': {
+            // the initializer of the global variable is analyzed outside of any functions
+            const initializerSymbol = createFunctionSymbol(node.name, [], createFunctionType([], voidType), []);
+            analyzeStatement(node, false, initializerSymbol, a);
+            break;
+        }
+    }
+}
+
+/**
+ * Resolve the type of the function from the type labels of the parameters and the return value.
+*/
+function resolveFunctionType(node: FunctionDecl | LambdaExpr, a: AnalyzeContext): FunctionType {
+    // make return type
+    let returnTy: Type;
+    if (node.returnTy != null) {
+        returnTy = resolveTyLabel(node.returnTy, a, 'return type `void` should be omitted.');
+    } else {
+        returnTy = voidType;
+    }
+
+    // make params type
+    let paramsTy: Type[] = [];
+    for (let i = 0; i < node.params.length; i++) {
+        const paramNode = node.params[i];
+
+        // if param type is not specified
+        if (paramNode.ty == null) {
+            a.dispatchError('parameter type missing.', paramNode);
+            paramsTy.push(badType);
+            continue;
+        }
+
+        // get param type
+        const paramTy = resolveTyLabel(paramNode.ty, a, 'parameters cannot have type `void`.');
+        paramsTy.push(paramTy);
     }
+
+    return createFunctionType(paramsTy, returnTy);
 }
 
-function analyzeBlock(nodes: StatementNode[], allowJump: boolean, funcSymbol: FnSymbol, a: AnalyzeContext) {
+/**
+ * Analyze the body of the function with the parameters. The function type is expected to be resolved.
+*/
+function analyzeFunctionBody(node: FunctionDecl | LambdaExpr, symbol: FnSymbol, a: AnalyzeContext) {
+    // check the function type is valid
+    if (!isValidType(symbol.ty)) {
+        if (isPendingType(symbol.ty)) {
+            a.dispatchError('function is not defined yet.', node);
+        }
+        return;
+    }
+
+    // the labels of the enclosing function cannot be used
+    const loopLabels = a.loopLabels;
+    const localVars = a.localVars;
+    a.loopLabels = [];
+    a.localVars = [];
+    const errorCount = a.error.length;
     a.env.enter();
-    // analyze inner
-    for (const node of nodes) {
-        analyzeNode(node, allowJump, funcSymbol, a);
+
+    // set function params to the env
+    for (let i = 0; i < node.params.length; i++) {
+        const paramTy = symbol.ty.paramTypes[i];
+
+        // the default value can refer to the preceding params
+        const defaultValue = node.params[i].defaultValue;
+        if (defaultValue != null) {
+            const defaultTy = analyzeExpr(defaultValue, symbol, a);
+            if (compareType(defaultTy, voidType) == 'compatible') {
+                a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, defaultValue);
+            } else if (compareType(defaultTy, paramTy) == 'incompatible') {
+                dispatchTypeError(defaultTy, paramTy, defaultValue, a);
+            }
+        }
+
+        const paramSymbol = createVariableSymbol(paramTy, true, 'param');
+        a.symbolTable.set(node.params[i], paramSymbol);
+        a.env.set(node.params[i].name, paramSymbol);
+    }
+
+    // analyze function body
+    const bodyTy = analyzeStatements(node.body, false, symbol, a);
+
+    // check the value yielded by the body
+    if (node.body.length > 0 && isValidType(bodyTy) && bodyTy.kind != 'VoidType') {
+        const tail = node.body[node.body.length - 1];
+        if (symbol.ty.returnType.kind == 'VoidType') {
+            a.dispatchError(`function \`${symbol.name}\` returns void; cannot return a value.`, tail);
+        } else if (compareType(bodyTy, symbol.ty.returnType) == 'incompatible') {
+            dispatchTypeError(bodyTy, symbol.ty.returnType, tail, a);
+        }
     }
+
+    // check the function returns a value on all paths
+    if (symbol.ty.returnType.kind != 'VoidType' && bodyTy.kind == 'VoidType' && !isReturningBlock(node.body, a)) {
+        a.dispatchError(`function \`${symbol.name}\` does not return a value on all paths.`, node);
+    }
+
+    // the references may be missing in the function with errors
+    if (a.error.length == errorCount) {
+        dispatchUnusedVariables(a);
+    }
+
     a.env.leave();
+    a.loopLabels = loopLabels;
+    a.localVars = localVars;
+}
+
+/**
+ * Report the local variables of the function that are never used. The variables whose names start with `_` are not reported.
+*/
+function dispatchUnusedVariables(a: AnalyzeContext) {
+    for (const { node, symbol } of a.localVars) {
+        if (a.usedVars.has(symbol) || node.name.startsWith('_')) {
+            continue;
+        }
+        a.dispatchWarn(`variable \`${node.name}\` is never used.`, node);
+    }
+}
+
+/**
+ * Check whether the struct contains the specified struct in the fields directly or indirectly.
+*/
+function containsStruct(symbol: StructSymbol, name: string, visited: string[], a: AnalyzeContext): boolean {
+    for (const [_name, field] of symbol.fields) {
+        if (field.kind != 'VariableSymbol' || field.ty.kind != 'NamedType') {
+            continue;
+        }
+        if (field.ty.name == name) {
+            return true;
+        }
+        if (visited.includes(field.ty.name)) {
+            continue;
+        }
+        const fieldStruct = a.env.get(field.ty.name);
+        if (fieldStruct != null && fieldStruct.kind == 'StructSymbol') {
+            visited.push(field.ty.name);
+            if (containsStruct(fieldStruct, name, visited, a)) {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+function analyzeBlock(
+    nodes: StatementNode[],
+    allowJump: boolean,
+    funcSymbol: FnSymbol,
+    a: AnalyzeContext,
+    beforeStatement?: (index: number) => void
+): Type {
+    a.env.enter();
+    // analyze inner
+    const ty = analyzeStatements(nodes, allowJump, funcSymbol, a, beforeStatement);
+    a.env.leave();
+    return ty;
+}
+
+/**
+ * Analyze the block that may not be executed, and get the variables assigned for the first time in it.
+ * These variables are marked as not assigned again, because the assignments are not guaranteed.
+ * The assignments in the first `guaranteedLength` statements are kept, when the block is known to reach them.
+*/
+function analyzeConditionalBlock(
+    nodes: StatementNode[],
+    allowJump: boolean,
+    funcSymbol: FnSymbol,
+    a: AnalyzeContext,
+    guaranteedLength: number = 0
+): { ty: Type, assigned: VariableSymbol[] } {
+    const assignedCount = a.assignedVars.length;
+    let guaranteedCount = assignedCount;
+    const ty = analyzeBlock(nodes, allowJump, funcSymbol, a, index => {
+        if (index == guaranteedLength) {
+            guaranteedCount = a.assignedVars.length;
+        }
+    });
+    if (guaranteedLength >= nodes.length) {
+        guaranteedCount = a.assignedVars.length;
+    }
+    const assigned = a.assignedVars.splice(guaranteedCount);
+    for (const symbol of assigned) {
+        symbol.isDefined = false;
+    }
+    return { ty, assigned };
+}
+
+/**
+ * Analyze the branches of the if statement (or the if expression) and get the types of them.
+ * The variable is assigned after the branches only when it is assigned in both of them.
+ * A branch that never reaches the end (e.g. returns) does not prevent the assignment.
+*/
+function analyzeBranches(
+    node: IfStatement | IfExpr,
+    allowJump: boolean,
+    funcSymbol: FnSymbol,
+    a: AnalyzeContext
+): { thenTy: Type, elseTy: Type } {
+    const thenBranch = analyzeConditionalBlock(node.thenBlock, allowJump, funcSymbol, a);
+    const elseBranch = analyzeConditionalBlock(node.elseBlock, allowJump, funcSymbol, a);
+
+    let assigned: VariableSymbol[];
+    if (isTerminatingBlock(node.thenBlock)) {
+        assigned = elseBranch.assigned;
+    } else if (isTerminatingBlock(node.elseBlock)) {
+        assigned = thenBranch.assigned;
+    } else {
+        assigned = thenBranch.assigned.filter(x => elseBranch.assigned.includes(x));
+    }
+    for (const symbol of assigned) {
+        symbol.isDefined = true;
+        a.assignedVars.push(symbol);
+    }
+
+    return { thenTy: thenBranch.ty, elseTy: elseBranch.ty };
+}
+
+/**
+ * Analyze the statements and get the type of the value yielded by the last statement.
+*/
+function analyzeStatements(
+    nodes: StatementNode[],
+    allowJump: boolean,
+    funcSymbol: FnSymbol,
+    a: AnalyzeContext,
+    beforeStatement?: (index: number) => void
+): Type {
+    let ty: Type = voidType;
+    let isTerminated = false;
+    for (let i = 0; i < nodes.length; i++) {
+        beforeStatement?.(i);
+
+        // the statement after the terminating statement is never executed
+        if (isTerminated) {
+            a.dispatchError('unreachable code.', nodes[i]);
+            isTerminated = false;
+        }
+
+        a.env.trace?.log(`analyze statement: ${nodes[i].kind}`);
+        ty = analyzeNode(nodes[i], allowJump, funcSymbol, a);
+
+        // only the last statement can yield a value
+        if (i < nodes.length - 1 && isValidType(ty) && ty.kind != 'VoidType') {
+            a.dispatchError('a value can only be yielded at the end of a block.', nodes[i]);
+        }
+
+        if (isTerminatingStatement(nodes[i])) {
+            isTerminated = true;
+        }
+    }
+    return ty;
+}
+
+/**
+ * Check whether the control never reaches the statement following the specified statement.
+ * The if statement is terminating when both branches are terminating.
+*/
+function isTerminatingStatement(node: StatementNode): boolean {
+    switch (node.kind) {
+        case 'ReturnStatement':
+        case 'BreakStatement':
+        case 'ContinueStatement': {
+            return true;
+        }
+        case 'IfStatement': {
+            return isTerminatingBlock(node.thenBlock) && isTerminatingBlock(node.elseBlock);
+        }
+        default: {
+            return false;
+        }
+    }
+}
+
+function isTerminatingBlock(nodes: StatementNode[]): boolean {
+    return nodes.some(x => isTerminatingStatement(x));
+}
+
+/**
+ * Check the patterns of the match expression against the type of the target.
+ * All variants of the enum must be handled, and the number requires the wildcard arm `_`.
+*/
+function checkMatchPatterns(node: MatchExpr, targetTy: ValidType, a: AnalyzeContext) {
+    const symbol = (targetTy.kind == 'NamedType') ? a.env.get(targetTy.name) : undefined;
+    const enumSymbol = (symbol != null && symbol.kind == 'EnumSymbol') ? symbol : undefined;
+    const handled: string[] = [];
+    let hasWildcard = false;
+    for (const arm of node.arms) {
+        const pattern = arm.pattern;
+        if (hasWildcard) {
+            a.dispatchWarn('unreachable pattern.', arm);
+            continue;
+        }
+        switch (pattern.kind) {
+            case 'wildcard': {
+                hasWildcard = true;
+                break;
+            }
+            case 'variant': {
+                if (enumSymbol == null) {
+                    a.dispatchError(`type \`${getTypeString(targetTy)}\` has no variants.`, arm);
+                    break;
+                }
+                if (!enumSymbol.variants.includes(pattern.name)) {
+                    a.dispatchError(`unknown variant \`${pattern.name}\` for enum \`${enumSymbol.name}\`.`, arm);
+                    break;
+                }
+                if (handled.includes(pattern.name)) {
+                    a.dispatchWarn('unreachable pattern.', arm);
+                    break;
+                }
+                handled.push(pattern.name);
+                break;
+            }
+            case 'number': {
+                if (compareType(targetTy, numberType) == 'incompatible') {
+                    dispatchTypeError(numberType, targetTy, arm, a);
+                    break;
+                }
+                if (handled.includes(pattern.value.toString())) {
+                    a.dispatchWarn('unreachable pattern.', arm);
+                    break;
+                }
+                handled.push(pattern.value.toString());
+                break;
+            }
+        }
+    }
+
+    if (hasWildcard) {
+        return;
+    }
+    if (enumSymbol != null) {
+        const missing = enumSymbol.variants.filter(x => !handled.includes(x));
+        if (missing.length > 0) {
+            a.dispatchError(`non-exhaustive patterns: ${missing.map(x => `\`${x}\``).join(', ')} not covered.`, node);
+        }
+    } else {
+        a.dispatchError('non-exhaustive patterns: the wildcard arm `_` is required.', node);
+    }
+}
+
+/**
+ * Check whether the control never leaves the function normally once the statement is executed.
+ * The if statement is returning when both branches are returning, and the loop statement is
+ * returning when it has no break statement. The call of `panic` never returns either.
+*/
+function isReturningStatement(node: StatementNode, a: AnalyzeContext): boolean {
+    switch (node.kind) {
+        case 'ReturnStatement': {
+            return true;
+        }
+        case 'IfStatement': {
+            return isReturningBlock(node.thenBlock, a) && isReturningBlock(node.elseBlock, a);
+        }
+        case 'LoopStatement': {
+            return !containsBreak(node.block, node.label);
+        }
+        case 'Call': {
+            return isDivergingCall(node, a);
+        }
+        case 'TailExpr': {
+            return isReturningStatement(node.expr, a);
+        }
+        default: {
+            return false;
+        }
+    }
+}
+
+function isReturningBlock(nodes: StatementNode[], a: AnalyzeContext): boolean {
+    return nodes.some(x => isReturningStatement(x, a));
+}
+
+/**
+ * Check whether the callee is the builtin that never returns (e.g. `panic`).
+*/
+function isDivergingCall(node: Call, a: AnalyzeContext): boolean {
+    const calleeSymbol = a.symbolTable.get(node.callee);
+    return (calleeSymbol?.kind == 'NativeFnSymbol' && calleeSymbol.diverges == true);
+}
+
+/**
+ * Check whether the block contains a break statement that exits the loop of the specified label.
+ * The break statements in the nested loops are not counted unless they target the outer loops.
+ * The continue statement targeting the outer loops also exits the loop.
+*/
+function containsBreak(nodes: StatementNode[], label: string | undefined, nestedLabels: (string | undefined)[] = []): boolean {
+    return nodes.some(node => {
+        switch (node.kind) {
+            case 'BreakStatement': {
+                if (node.label == null) {
+                    return (nestedLabels.length == 0);
+                }
+                return !nestedLabels.includes(node.label);
+            }
+            case 'ContinueStatement': {
+                if (node.label == null) {
+                    return false;
+                }
+                return (node.label != label && !nestedLabels.includes(node.label));
+            }
+            case 'IfStatement': {
+                return containsBreak(node.thenBlock, label, nestedLabels) || containsBreak(node.elseBlock, label, nestedLabels);
+            }
+            case 'LoopStatement':
+            case 'RepeatStatement':
+            case 'WhileStatement':
+            case 'ForStatement':
+            case 'ForEachFieldStatement': {
+                return containsBreak(node.block, label, [...nestedLabels, node.label]);
+            }
+            default: {
+                return false;
+            }
+        }
+    });
+}
+
+/**
+ * Remove the diagnostics reported after the specified index that have the same text as the preceding ones.
+*/
+function removeDuplicateDiagnostics(diagnostics: Diagnostic[], start: number) {
+    const texts = new Set<string>();
+    const unique = diagnostics.splice(start).filter(x => {
+        const text = formatDiagnostic(x);
+        if (texts.has(text)) {
+            return false;
+        }
+        texts.add(text);
+        return true;
+    });
+    diagnostics.push(...unique);
+}
+
+/**
+ * Analyze the block of the loop. The label of the loop can be used by break and continue in the block.
+*/
+function analyzeLoopBlock(node: LoopNode, funcSymbol: FnSymbol, a: AnalyzeContext, block: StatementNode[] = node.block): Type {
+    if (node.label != null && a.loopLabels.includes(node.label)) {
+        a.dispatchError(`label \`${node.label}\` is already used by the enclosing loop.`, node);
+    }
+    a.loopLabels.push(node.label);
+    // allow break and continue
+    // the assignments in the loop are not guaranteed, since the block may not be executed.
+    // the block of the loop statement is executed at least once, so the assignments before the statement
+    // that may exit the loop are guaranteed.
+    let guaranteedLength = 0;
+    if (node.kind == 'LoopStatement') {
+        const exitIndex = block.findIndex(x => containsBreak([x], node.label));
+        guaranteedLength = (exitIndex != -1) ? exitIndex : block.length;
+    }
+    const blockTy = analyzeConditionalBlock(block, true, funcSymbol, a, guaranteedLength).ty;
+    a.loopLabels.pop();
+    return blockTy;
 }
 
-function analyzeNode(node: StatementNode, allowJump: boolean, funcSymbol: FnSymbol, a: AnalyzeContext) {
+/**
+ * Check whether the block contains a return statement, including the ones in the nested blocks.
+ * The call that never returns (e.g. `panic`) also leaves the function.
+*/
+function containsReturn(nodes: StatementNode[], a: AnalyzeContext): boolean {
+    return nodes.some(node => {
+        switch (node.kind) {
+            case 'ReturnStatement': {
+                return true;
+            }
+            case 'Call': {
+                return isDivergingCall(node, a);
+            }
+            case 'TailExpr': {
+                return (node.expr.kind == 'Call' && isDivergingCall(node.expr, a));
+            }
+            case 'IfStatement': {
+                return containsReturn(node.thenBlock, a) || containsReturn(node.elseBlock, a);
+            }
+            case 'LoopStatement':
+            case 'RepeatStatement':
+            case 'WhileStatement':
+            case 'ForStatement':
+            case 'ForEachFieldStatement': {
+                return containsReturn(node.block, a);
+            }
+            default: {
+                return false;
+            }
+        }
+    });
+}
+
+/**
+ * Analyze the statement and get the type of the value yielded by the statement.
+*/
+function analyzeNode(node: StatementNode, allowJump: boolean, funcSymbol: FnSymbol, a: AnalyzeContext): Type {
     if (isExprNode(node)) {
         analyzeExpr(node, funcSymbol, a);
+        return voidType;
     } else {
-        analyzeStatement(node, allowJump, funcSymbol, a);
+        return analyzeStatement(node, allowJump, funcSymbol, a);
     }
 }
 
-function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbol: FnSymbol, a: AnalyzeContext) {
+function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbol: FnSymbol, a: AnalyzeContext): Type {
     switch (node.kind) {
+        case 'FunctionDecl': {
+            // the nested function is declared in the current scope
+            declareTopLevel(node, a);
+            if (a.symbolTable.get(node) == null) {
+                return voidType;
+            }
+            resolveTopLevel(node, a);
+
+            // the body can refer to the global symbols and the functions, but not to the local variables
+            const enclosingEnv = a.enclosingEnv;
+            a.enclosingEnv = a.env;
+            a.env = a.env.createInnerEnv(a.globalDepth, x => (x.kind == 'FnSymbol') ? x : undefined);
+            analyzeTopLevel(node, a);
+            a.env = a.enclosingEnv;
+            a.enclosingEnv = enclosingEnv;
+            return voidType;
+        }
         case 'ReturnStatement': {
             // if there is a return value
             if (node.expr != null) {
@@ -442,33 +1162,201 @@ function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbo
                     if (isPendingType(funcSymbol.ty)) {
                         throw new UguisuError('unexpected type');
                     }
-                    return;
+                    return voidType;
+                }
+
+                // if the function returns nothing
+                if (funcSymbol.ty.returnType.kind == 'VoidType') {
+                    a.dispatchError(`function \`${funcSymbol.name}\` returns void; cannot return a value.`, node.expr);
+                    return voidType;
                 }
 
                 // check type
                 if (compareType(ty, funcSymbol.ty.returnType) == 'incompatible') {
                     dispatchTypeError(ty, funcSymbol.ty.returnType, node.expr, a);
                 }
+            } else {
+                if (!isValidType(funcSymbol.ty)) {
+                    return voidType;
+                }
+
+                // if the function returns a value
+                if (funcSymbol.ty.returnType.kind != 'VoidType') {
+                    const returnTy = getTypeString(funcSymbol.ty.returnType);
+                    a.dispatchError(`function \`${funcSymbol.name}\` must return a value of type \`${returnTy}\`.`, node);
+                }
             }
-            return;
+            return voidType;
         }
-        case 'BreakStatement': {
+        case 'TailExpr': {
+            // the value of the block
+            return analyzeExpr(node.expr, funcSymbol, a);
+        }
+        case 'BreakStatement':
+        case 'ContinueStatement': {
             // if there is no associated loop
             if (!allowJump) {
-                a.dispatchError('invalid break statement.');
+                const keyword = (node.kind == 'BreakStatement') ? 'break' : 'continue';
+                a.dispatchError(`invalid ${keyword} statement.`);
+                return voidType;
+            }
+            if (node.label != null && !a.loopLabels.includes(node.label)) {
+                a.dispatchError(`unknown label \`${node.label}\`.`, node);
             }
-            return;
+            return voidType;
         }
         case 'LoopStatement': {
-            // allow break
-            allowJump = true;
-            analyzeBlock(node.block, allowJump, funcSymbol, a);
-            return;
+            const blockTy = analyzeLoopBlock(node, funcSymbol, a);
+            if (isValidType(blockTy) && blockTy.kind != 'VoidType') {
+                a.dispatchError('the value of the block is not used.', node);
+            }
+            // the loop may be intended, e.g. the main loop of a server
+            if (a.options.warnEndlessLoop && !containsBreak(node.block, node.label) && !containsReturn(node.block, a)) {
+                a.dispatchWarn('the loop never ends, since it has no `break` or `return`.', node);
+            }
+            return voidType;
+        }
+        case 'RepeatStatement': {
+            let countTy = analyzeExpr(node.count, funcSymbol, a);
+
+            // if the count expr returned nothing
+            if (compareType(countTy, voidType) == 'compatible') {
+                a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, node.count);
+                countTy = badType;
+            }
+
+            // check type
+            if (compareType(countTy, numberType) == 'incompatible') {
+                dispatchTypeError(countTy, numberType, node.count, a);
+            }
+
+            const blockTy = analyzeLoopBlock(node, funcSymbol, a);
+            if (isValidType(blockTy) && blockTy.kind != 'VoidType') {
+                a.dispatchError('the value of the block is not used.', node);
+            }
+            return voidType;
+        }
+        case 'WhileStatement': {
+            let condTy = analyzeExpr(node.cond, funcSymbol, a);
+
+            // if the condition expr returned nothing
+            if (compareType(condTy, voidType) == 'compatible') {
+                a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, node.cond);
+                condTy = badType;
+            }
+
+            // check type
+            if (compareType(condTy, boolType) == 'incompatible') {
+                dispatchTypeError(condTy, boolType, node.cond, a);
+            }
+
+            const blockTy = analyzeLoopBlock(node, funcSymbol, a);
+            if (isValidType(blockTy) && blockTy.kind != 'VoidType') {
+                a.dispatchError('the value of the block is not used.', node);
+            }
+            return voidType;
+        }
+        case 'ForStatement': {
+            // the variables declared in the initializer are scoped to the loop
+            a.env.enter();
+
+            if (node.init != null) {
+                analyzeNode(node.init, false, funcSymbol, a);
+            }
+
+            if (node.cond != null) {
+                let condTy = analyzeExpr(node.cond, funcSymbol, a);
+
+                // if the condition expr returned nothing
+                if (compareType(condTy, voidType) == 'compatible') {
+                    a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, node.cond);
+                    condTy = badType;
+                }
+
+                // check type
+                if (compareType(condTy, boolType) == 'incompatible') {
+                    dispatchTypeError(condTy, boolType, node.cond, a);
+                }
+            }
+
+            const blockTy = analyzeLoopBlock(node, funcSymbol, a);
+            if (isValidType(blockTy) && blockTy.kind != 'VoidType') {
+                a.dispatchError('the value of the block is not used.', node);
+            }
+
+            if (node.step != null) {
+                analyzeNode(node.step, false, funcSymbol, a);
+            }
+
+            a.env.leave();
+            return voidType;
+        }
+        case 'ForEachFieldStatement': {
+            const targetTy = analyzeExpr(node.target, funcSymbol, a);
+
+            if (!isValidType(targetTy)) {
+                if (isPendingType(targetTy)) {
+                    a.dispatchError('variable is not assigned yet.', node.target);
+                }
+                return voidType;
+            }
+
+            // expect struct
+            let structSymbol: StructSymbol | undefined;
+            if (targetTy.kind == 'NamedType' && !builtinTypeNames.includes(targetTy.name)) {
+                const symbol = a.env.get(targetTy.name);
+                if (symbol != null && symbol.kind == 'StructSymbol') {
+                    structSymbol = symbol;
+                }
+            }
+            if (structSymbol == null) {
+                a.dispatchError(`type \`${getTypeString(targetTy)}\` is not a struct.`, node.target);
+                return voidType;
+            }
+
+            // the loop is lowered into the block for each field, which accesses the field by its name.
+            // the first field uses the original block, and the others use the copies of it.
+            const fieldNames = [...structSymbol.fields.keys()];
+            const blocks = fieldNames.map((_x, i) => (i == 0) ? node.block : structuredClone(node.block));
+            const errorCount = a.error.length;
+            const warnCount = a.warn.length;
+            const inBlockCopy = a.inBlockCopy;
+
+            // the target is evaluated once
+            a.env.enter();
+            a.env.set(fieldIterationTarget, createVariableSymbol(targetTy, true, 'const'));
+            node.lowered = [];
+            for (let i = 0; i < fieldNames.length; i++) {
+                const nameDecl = createVariableDecl(node.pos, node.nameVar, false, undefined, createStringLiteral(node.pos, fieldNames[i]));
+                const valueAccess = createFieldAccess(node.pos, fieldNames[i], createIdentifier(node.pos, fieldIterationTarget));
+                const valueDecl = createVariableDecl(node.pos, node.valueVar, false, undefined, valueAccess);
+                node.lowered.push([nameDecl, valueDecl, ...blocks[i]]);
+
+                a.env.enter();
+                const nameSymbol = createVariableSymbol(stringType, true, 'var');
+                a.symbolTable.set(nameDecl, nameSymbol);
+                a.env.set(node.nameVar, nameSymbol);
+                const valueSymbol = createVariableSymbol(analyzeExpr(valueAccess, funcSymbol, a), true, 'var');
+                a.symbolTable.set(valueDecl, valueSymbol);
+                a.env.set(node.valueVar, valueSymbol);
+                a.inBlockCopy = inBlockCopy || (i > 0);
+                const blockTy = analyzeLoopBlock(node, funcSymbol, a, blocks[i]);
+                if (isValidType(blockTy) && blockTy.kind != 'VoidType') {
+                    a.dispatchError('the value of the block is not used.', node);
+                }
+                a.env.leave();
+            }
+            a.inBlockCopy = inBlockCopy;
+            a.env.leave();
+
+            // the copies of the block report the same diagnostics as the original one
+            removeDuplicateDiagnostics(a.error, errorCount);
+            removeDuplicateDiagnostics(a.warn, warnCount);
+            return voidType;
         }
         case 'IfStatement': {
             let condTy = analyzeExpr(node.cond, funcSymbol, a);
-            analyzeBlock(node.thenBlock, allowJump, funcSymbol, a);
-            analyzeBlock(node.elseBlock, allowJump, funcSymbol, a);
+            const { thenTy, elseTy } = analyzeBranches(node, allowJump, funcSymbol, a);
 
             // if the condition expr returned nothing
             if (compareType(condTy, voidType) == 'compatible') {
@@ -480,7 +1368,19 @@ function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbo
             if (compareType(condTy, boolType) == 'incompatible') {
                 dispatchTypeError(condTy, boolType, node.cond, a);
             }
-            return;
+
+            // the branches yield a value
+            if (thenTy.kind != 'VoidType' || elseTy.kind != 'VoidType') {
+                if (!isValidType(thenTy) || !isValidType(elseTy)) {
+                    return badType;
+                }
+                if (compareType(elseTy, thenTy) == 'incompatible') {
+                    a.dispatchError(`if branches must yield the same type. then: \`${getTypeString(thenTy)}\`, else: \`${getTypeString(elseTy)}\`.`, node);
+                    return badType;
+                }
+                return thenTy;
+            }
+            return voidType;
         }
         case 'VariableDecl': {
             let isDefined = false;
@@ -488,7 +1388,7 @@ function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbo
 
             // if an explicit type is specified
             if (node.ty != null) {
-                ty = resolveTyLabel(node.ty, a);
+                ty = resolveTyLabel(node.ty, a, 'variables cannot have type `void`.');
             }
 
             // initializer
@@ -515,11 +1415,17 @@ function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbo
             }
 
             // set symbol
-            const symbol = createVariableSymbol(ty, isDefined);
+            const symbol = createVariableSymbol(ty, isDefined, node.isConst ? 'const' : 'var');
             a.symbolTable.set(node, symbol);
             a.env.set(node.name, symbol);
 
-            return;
+            // the local variables are checked whether they are used at the end of the function
+            // the copy of the block is not checked, since it has the same variables as the original one
+            if (a.env.depth > a.globalDepth && !a.inBlockCopy) {
+                a.localVars.push({ node, symbol });
+            }
+
+            return voidType;
         }
         case 'AssignStatement': {
             let bodyTy = analyzeExpr(node.body, funcSymbol, a);
@@ -533,18 +1439,41 @@ function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbo
             // analyze target
             let symbol;
             if (node.target.kind == 'Identifier' || node.target.kind == 'FieldAccess' || node.target.kind == 'IndexAccess') {
-                symbol = analyzeReferenceExpr(node.target, funcSymbol, a);
+                symbol = analyzeReferenceExpr(node.target, funcSymbol, a, true);
             } else {
                 a.dispatchError('invalid assign target.');
             }
 
             // skip if target symbol is invalid
             if (symbol == null) {
-                return;
+                return voidType;
+            }
+
+            // the constant and the parameter cannot be reassigned
+            // the fields and the items of them cannot be assigned either
+            const rootSymbol = getRootSymbol(node.target, a);
+            if (isDeclaredAs(symbol, 'const') || isDeclaredAs(rootSymbol, 'const')) {
+                a.dispatchError('cannot assign to a constant.', node.target);
+                return voidType;
+            }
+            if (isDeclaredAs(symbol, 'param') || isDeclaredAs(rootSymbol, 'param')) {
+                a.dispatchError('cannot assign to a function parameter.', node.target);
+                return voidType;
+            }
+            // the captured struct or array cannot be modified either, since the lambda would keep the change
+            if (isDeclaredAs(symbol, 'capture') || isDeclaredAs(rootSymbol, 'capture')) {
+                a.dispatchError('cannot assign to a captured variable.', node.target);
+                return voidType;
             }
 
             let targetTy = getTypeFromSymbol(symbol, node.target, a);
 
+            // the compound assignment reads the variable, so it must be assigned before
+            if (symbol.kind == 'VariableSymbol' && !symbol.isDefined && node.mode != '=') {
+                a.dispatchError('variable is not assigned yet.', node.target);
+                return voidType;
+            }
+
             // if it was the first assignment
             if (symbol.kind == 'VariableSymbol' && !symbol.isDefined) {
                 // if need inference
@@ -553,6 +1482,7 @@ function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbo
                     symbol.ty = targetTy;
                 }
                 symbol.isDefined = true;
+                a.assignedVars.push(symbol);
             }
 
             // check type
@@ -567,17 +1497,28 @@ function analyzeStatement(node: StatementCoreNode, allowJump: boolean, funcSymbo
                 case '-=':
                 case '*=':
                 case '/=':
-                case '%=': {
-                    if (compareType(targetTy, numberType) == 'incompatible') {
-                        dispatchTypeError(targetTy, numberType, node.target, a);
+                case '%=':
+                case '**=': {
+                    if (targetTy.kind == 'NamedType' && targetTy.name == 'string') {
+                        // the string supports only the concatenation
+                        if (node.mode != '+=') {
+                            a.dispatchError(`operator \`${node.mode}\` is not defined for \`string\`.`, node.target);
+                        } else if (compareType(bodyTy, stringType) == 'incompatible') {
+                            dispatchTypeError(bodyTy, stringType, node.body, a);
+                        }
+                        break;
+                    }
+                    const numericTy = (node.mode == '**=') ? numberType : getNumericType(targetTy);
+                    if (compareType(targetTy, numericTy) == 'incompatible') {
+                        dispatchTypeError(targetTy, numericTy, node.target, a);
                     }
-                    if (compareType(bodyTy, numberType) == 'incompatible') {
-                        dispatchTypeError(bodyTy, numberType, node.body, a);
+                    if (compareType(bodyTy, numericTy) == 'incompatible') {
+                        dispatchTypeError(bodyTy, numericTy, node.body, a);
                     }
                     break;
                 }
             }
-            return;
+            return voidType;
         }
     }
     throw new UguisuError('unexpected node');
@@ -609,10 +1550,18 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
             // return expr type
             return numberType;
         }
+        case 'FloatLiteral': {
+            // return expr type
+            return floatType;
+        }
         case 'BoolLiteral': {
             // return expr type
             return boolType;
         }
+        case 'NoneLiteral': {
+            // `none` is compatible with any optional type
+            return createOptionalType(anyType);
+        }
         case 'CharLiteral': {
             // check if literal is valid
             const arr = node.value.match(charRegex());
@@ -652,6 +1601,10 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
                     a.dispatchError('struct is not callable.', node.callee);
                     return badType;
                 }
+                case 'EnumSymbol': {
+                    a.dispatchError('enum is not callable.', node.callee);
+                    return badType;
+                }
                 case 'VariableSymbol': {
                     // if the variable is not assigned
                     if (calleeSymbol.ty.kind == 'PendingType') {
@@ -680,15 +1633,22 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
                 return badType;
             }
 
+            // the trailing arguments can be omitted if the params have the default values
+            let requiredCount = calleeTy.paramTypes.length;
+            if (calleeSymbol.kind == 'FnSymbol') {
+                requiredCount = calleeSymbol.params.filter(x => !x.hasDefault).length;
+            }
             let isCorrectArgCount = true;
-            if (node.args.length != calleeTy.paramTypes.length) {
+            if (node.args.length < requiredCount || node.args.length > calleeTy.paramTypes.length) {
                 a.dispatchError('argument count incorrect.', node);
                 isCorrectArgCount = false;
             }
 
+            const argTypes: Type[] = [];
             if (isCorrectArgCount) {
-                for (let i = 0; i < calleeTy.paramTypes.length; i++) {
+                for (let i = 0; i < node.args.length; i++) {
                     let argTy = analyzeExpr(node.args[i], funcSymbol, a);
+                    argTypes.push(argTy);
 
                     // if the argument returns nothing
                     if (compareType(argTy, voidType) == 'compatible') {
@@ -711,6 +1671,17 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
                 }
             }
 
+            // the builtin may have the rules that cannot be written as the signature (e.g. `share` returns the argument type)
+            if (calleeSymbol.kind == 'NativeFnSymbol' && calleeSymbol.check != null && isCorrectArgCount) {
+                const ty = calleeSymbol.check(argTypes, node.args, a);
+                if (ty != null) {
+                    if (isValidType(ty)) {
+                        a.symbolTable.set(node, createExprSymbol(ty));
+                    }
+                    return ty;
+                }
+            }
+
             a.symbolTable.set(node, createExprSymbol(calleeTy.returnType));
             return calleeTy.returnType;
         }
@@ -775,12 +1746,22 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
                 return boolType;
             } else if (isOrderingOperator(node.operator)) {
                 // Ordering Operation
-                if (compareType(leftTy, numberType) == 'incompatible') {
-                    dispatchTypeError(leftTy, numberType, node.left, a);
+                // `1 < x < 10` is parsed as `(1 < x) < 10`, which compares bool with number
+                if (
+                    node.left.kind == 'BinaryOp' && isOrderingOperator(node.left.operator) &&
+                    compareType(rightTy, getNumericType(rightTy)) == 'compatible'
+                ) {
+                    a.dispatchError('comparison chaining is not supported; use `&&`.', node);
+                    return badType;
+                }
+
+                const operandTy = getOrderingType(leftTy);
+                if (compareType(leftTy, operandTy) == 'incompatible') {
+                    dispatchTypeError(leftTy, operandTy, node.left, a);
                 }
 
-                if (compareType(rightTy, numberType) == 'incompatible') {
-                    dispatchTypeError(rightTy, numberType, node.right, a);
+                if (compareType(rightTy, operandTy) == 'incompatible') {
+                    dispatchTypeError(rightTy, operandTy, node.right, a);
                 }
 
                 if (!isValidType(leftTy) || !isValidType(rightTy)) {
@@ -797,12 +1778,19 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
                 return boolType;
             } else {
                 // Arithmetic Operation
-                if (compareType(leftTy, numberType) == 'incompatible') {
-                    dispatchTypeError(leftTy, numberType, node.left, a);
+                // the string supports only the concatenation
+                if (node.operator != '+' && leftTy.kind == 'NamedType' && leftTy.name == 'string') {
+                    a.dispatchError(`operator \`${node.operator}\` is not defined for \`string\`.`, node.left);
+                    return badType;
+                }
+
+                const operandTy = getArithmeticType(node.operator, leftTy);
+                if (compareType(leftTy, operandTy) == 'incompatible') {
+                    dispatchTypeError(leftTy, operandTy, node.left, a);
                 }
 
-                if (compareType(rightTy, numberType) == 'incompatible') {
-                    dispatchTypeError(rightTy, numberType, node.right, a);
+                if (compareType(rightTy, operandTy) == 'incompatible') {
+                    dispatchTypeError(rightTy, operandTy, node.right, a);
                 }
 
                 if (!isValidType(leftTy) || !isValidType(rightTy)) {
@@ -815,8 +1803,8 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
                     return badType;
                 }
 
-                a.symbolTable.set(node, createExprSymbol(numberType));
-                return numberType;
+                a.symbolTable.set(node, createExprSymbol(operandTy));
+                return operandTy;
             }
             break;
         }
@@ -836,6 +1824,17 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
                 return badType;
             }
 
+            if (isArithmeticUnaryOperator(node.operator)) {
+                // Arithmetic Operation
+                const operandTy = getNumericType(ty);
+                if (compareType(ty, operandTy) == 'incompatible') {
+                    dispatchTypeError(ty, operandTy, node.expr, a);
+                    return badType;
+                }
+                a.symbolTable.set(node, createExprSymbol(operandTy));
+                return operandTy;
+            }
+
             // Logical Operation
             if (compareType(ty, boolType) == 'incompatible') {
                 dispatchTypeError(ty, boolType, node, a);
@@ -859,6 +1858,7 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
             }
 
             const defined: string[] = [];
+            const unknownFields: string[] = [];
             for (const fieldNode of node.fields) {
                 // check already defined
                 if (defined.indexOf(fieldNode.name) != -1) {
@@ -876,7 +1876,13 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
                 }
 
                 // get field symbol
-                const fieldSymbol = symbol.fields.get(fieldNode.name)!;
+                const fieldSymbol = symbol.fields.get(fieldNode.name);
+
+                // if specified field name is invalid
+                if (fieldSymbol == null) {
+                    unknownFields.push(fieldNode.name);
+                    continue;
+                }
 
                 // expect variable symbol
                 if (fieldSymbol.kind != 'VariableSymbol') {
@@ -885,27 +1891,219 @@ function analyzeExpr(node: ExprNode, funcSymbol: FnSymbol, a: AnalyzeContext): T
 
                 // check field type
                 if (compareType(bodyTy, fieldSymbol.ty) == 'incompatible') {
-                    dispatchTypeError(bodyTy, fieldSymbol.ty, fieldNode.body, a);
+                    a.dispatchError(`type mismatched for field \`${fieldNode.name}\`. expected \`${getTypeString(fieldSymbol.ty)}\`, found \`${getTypeString(bodyTy)}\``, fieldNode.body);
                 }
             }
 
             // check fields are all defined
+            const missingFields: string[] = [];
             for (const [name, _field] of symbol.fields) {
                 if (!defined.includes(name)) {
-                    a.dispatchError(`field \`${name}\` is not initialized.`, node);
+                    missingFields.push(name);
+                }
+            }
+
+            // report all of the invalid fields at once
+            if (missingFields.length > 0 || unknownFields.length > 0) {
+                let message = `invalid fields for struct \`${symbol.name}\`.`;
+                if (missingFields.length > 0) {
+                    message += ` missing: ${missingFields.map(x => `\`${x}\``).join(', ')}.`;
                 }
+                if (unknownFields.length > 0) {
+                    message += ` unknown: ${unknownFields.map(x => `\`${x}\``).join(', ')}.`;
+                }
+                a.dispatchError(message, node);
             }
 
             return createNamedType(symbol.name);
         }
         case 'ArrayNode': {
             // analyze elements
+            // the element type is inferred from the first element.
+            let itemTy: Type | undefined;
             for (const item of node.items) {
-                analyzeExpr(item, funcSymbol, a);
+                const ty = analyzeExpr(item, funcSymbol, a);
+                if (!isValidType(ty)) {
+                    continue;
+                }
+                if (compareType(ty, voidType) == 'compatible') {
+                    a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, item);
+                    continue;
+                }
+                if (itemTy == null) {
+                    itemTy = ty;
+                } else if (compareType(ty, itemTy) == 'incompatible') {
+                    dispatchTypeError(ty, itemTy, item, a);
+                }
             }
 
             // return expr type
-            return arrayType;
+            return createArrayType(itemTy ?? anyType);
+        }
+        case 'TupleNode': {
+            // analyze items
+            const elementTypes = node.items.map(item => {
+                const ty = analyzeExpr(item, funcSymbol, a);
+                if (isValidType(ty) && compareType(ty, voidType) == 'compatible') {
+                    a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, item);
+                    return badType;
+                }
+                return ty;
+            });
+
+            // return expr type
+            return createTupleType(elementTypes);
+        }
+        case 'IfExpr': {
+            let condTy = analyzeExpr(node.cond, funcSymbol, a);
+            const { thenTy, elseTy } = analyzeBranches(node, false, funcSymbol, a);
+
+            // if the condition expr returned nothing
+            if (compareType(condTy, voidType) == 'compatible') {
+                a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, node.cond);
+                condTy = badType;
+            }
+
+            // check type
+            if (compareType(condTy, boolType) == 'incompatible') {
+                dispatchTypeError(condTy, boolType, node.cond, a);
+            }
+
+            if (!isValidType(thenTy) || !isValidType(elseTy)) {
+                return badType;
+            }
+
+            // expect the branches to yield a value
+            if (thenTy.kind == 'VoidType' || elseTy.kind == 'VoidType') {
+                a.dispatchError('each branch of the if expression must yield a value.', node);
+                return badType;
+            }
+
+            // check the branches yield the same type
+            if (compareType(elseTy, thenTy) == 'incompatible') {
+                a.dispatchError(`if branches must yield the same type. then: \`${getTypeString(thenTy)}\`, else: \`${getTypeString(elseTy)}\`.`, node);
+                return badType;
+            }
+
+            a.symbolTable.set(node, createExprSymbol(thenTy));
+            return thenTy;
+        }
+        case 'ConditionalExpr': {
+            let condTy = analyzeExpr(node.cond, funcSymbol, a);
+            let thenTy = analyzeExpr(node.thenExpr, funcSymbol, a);
+            let elseTy = analyzeExpr(node.elseExpr, funcSymbol, a);
+
+            // if the condition expr returned nothing
+            if (compareType(condTy, voidType) == 'compatible') {
+                a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, node.cond);
+                condTy = badType;
+            }
+
+            // check type
+            if (compareType(condTy, boolType) == 'incompatible') {
+                dispatchTypeError(condTy, boolType, node.cond, a);
+            }
+
+            // expect the branches to yield a value
+            if (compareType(thenTy, voidType) == 'compatible') {
+                a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, node.thenExpr);
+                thenTy = badType;
+            }
+            if (compareType(elseTy, voidType) == 'compatible') {
+                a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, node.elseExpr);
+                elseTy = badType;
+            }
+
+            if (!isValidType(thenTy) || !isValidType(elseTy)) {
+                return badType;
+            }
+
+            // check the branches yield the same type
+            if (compareType(elseTy, thenTy) == 'incompatible') {
+                dispatchTypeError(elseTy, thenTy, node.elseExpr, a);
+                return badType;
+            }
+
+            a.symbolTable.set(node, createExprSymbol(thenTy));
+            return thenTy;
+        }
+        case 'MatchExpr': {
+            let targetTy = analyzeExpr(node.target, funcSymbol, a);
+
+            // if the target expr returned nothing
+            if (compareType(targetTy, voidType) == 'compatible') {
+                a.dispatchError(`A function call that does not return a value cannot be used as an expression.`, node.target);
+                targetTy = badType;
+            }
+
+            // check the patterns
+            if (isValidType(targetTy)) {
+                checkMatchPatterns(node, targetTy, a);
+            }
+
+            // expect the arms to yield the same type
+            // the type of the match expression is inferred from the first arm.
+            let resultTy: Type | undefined;
+            let isValid = true;
+            for (const arm of node.arms) {
+                const ty = analyzeExpr(arm.body, funcSymbol, a);
+                if (!isValidType(ty)) {
+                    isValid = false;
+                    continue;
+                }
+                if (ty.kind == 'VoidType') {
+                    a.dispatchError('each arm of the match expression must yield a value.', arm.body);
+                    isValid = false;
+                    continue;
+                }
+                if (resultTy == null) {
+                    resultTy = ty;
+                } else if (compareType(ty, resultTy) == 'incompatible') {
+                    dispatchTypeError(ty, resultTy, arm.body, a);
+                    isValid = false;
+                }
+            }
+            if (!isValid || resultTy == null) {
+                return badType;
+            }
+
+            a.symbolTable.set(node, createExprSymbol(resultTy));
+            return resultTy;
+        }
+        case 'LambdaExpr': {
+            // the lambda is called as a function value, so all arguments are always passed
+            for (const param of node.params) {
+                if (param.defaultValue != null) {
+                    a.dispatchError('the parameters of the lambda cannot have default values.', param);
+                }
+            }
+
+            const ty = resolveFunctionType(node, a);
+            const params = node.params.map(x => ({ name: x.name, hasDefault: false }));
+            const symbol = createFunctionSymbol('(lambda)', params, ty, []);
+            a.symbolTable.set(node, symbol);
+
+            // the local variables are captured by value, so they are read-only in the body
+            const captured = new Map<VariableSymbol, VariableSymbol>();
+            const enclosingEnv = a.env;
+            a.env = a.env.createInnerEnv(a.globalDepth, x => {
+                if (x.kind != 'VariableSymbol') {
+                    return x;
+                }
+                const capturedSymbol = createVariableSymbol(x.ty, x.isDefined, 'capture');
+                captured.set(capturedSymbol, x);
+                return capturedSymbol;
+            });
+            analyzeFunctionBody(node, symbol, a);
+            a.env = enclosingEnv;
+
+            // the variable used in the lambda is also used in the enclosing function
+            for (const [capturedSymbol, original] of captured) {
+                if (a.usedVars.has(capturedSymbol)) {
+                    a.usedVars.add(original);
+                }
+            }
+            return ty;
         }
     }
     throw new UguisuError('unexpected node');
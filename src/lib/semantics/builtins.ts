@@ -1,30 +1,64 @@
+import { getDefaultProjectInfo } from '../project-file.js';
+import { AstNode } from '../syntax/tools.js';
 import {
+    AnalysisEnv,
     AnalyzeContext,
     anyType,
     arrayType,
+    badType,
+    boolType,
     charType,
+    compareType,
     createFunctionType,
+    createNamedType,
+    floatType,
     createNativeFnSymbol,
+    createOptionalType,
     createStructSymbol,
     createVariableSymbol,
+    dispatchTypeError,
+    getTypeString,
+    isValidType,
+    NativeFnRules,
     numberType,
     stringType,
     Symbol,
+    Type,
     ValidType,
     voidType
 } from './tools.js';
 
-function setDecl(name: string, paramsTy: ValidType[], returnTy: ValidType, a: AnalyzeContext) {
+export type BuiltinDeclaration = {
+    name: string,
+    paramTypes: Type[],
+    returnType: Type,
+};
+
+function setDecl(name: string, paramsTy: ValidType[], returnTy: ValidType, a: AnalyzeContext, rules?: NativeFnRules) {
     const params = Array(paramsTy.length).map(() => ({ name: 'x' }));
     const ty = createFunctionType(paramsTy, returnTy);
-    a.env.set(name, createNativeFnSymbol(params, ty));
+    a.env.set(name, createNativeFnSymbol(params, ty, rules));
+}
+
+/**
+ * The check of the builtins that accept only the optional (`isSome` and `unwrap`).
+*/
+function checkOptionalArg(name: string, argTy: Type, arg: AstNode, a: AnalyzeContext): Type | undefined {
+    if (!isValidType(argTy)) {
+        return badType;
+    }
+    if (argTy.kind != 'OptionalType' && argTy.kind != 'AnyType') {
+        a.dispatchError(`\`${name}\` requires an optional value, found \`${getTypeString(argTy)}\`.`, arg);
+        return badType;
+    }
+    return undefined;
 }
 
 function group(name: string, a: AnalyzeContext, handler: (setItem: (name: string, paramsTy: ValidType[], returnTy: ValidType) => void) => void) {
     const fields: Map<string, Symbol> = new Map();
     function setItem(name: string, paramsTy: ValidType[], returnTy: ValidType) {
         const ty = createFunctionType(paramsTy, returnTy);
-        const symbol = createVariableSymbol(ty, true);
+        const symbol = createVariableSymbol(ty, true, 'var');
         fields.set(name, symbol);
     }
     handler(setItem);
@@ -36,7 +70,7 @@ export function setDeclarations(a: AnalyzeContext) {
         setItem(
             'parse',
             [stringType],
-            numberType
+            createOptionalType(numberType)
         );
         setItem(
             'toString',
@@ -48,6 +82,67 @@ export function setDeclarations(a: AnalyzeContext) {
             [numberType, numberType],
             voidType
         );
+        setItem(
+            'assertNear',
+            [numberType, numberType, numberType],
+            voidType
+        );
+        setItem(
+            'random',
+            [numberType, numberType],
+            numberType
+        );
+        setItem(
+            'abs',
+            [numberType],
+            numberType
+        );
+        setItem(
+            'min',
+            [numberType, numberType],
+            numberType
+        );
+        setItem(
+            'max',
+            [numberType, numberType],
+            numberType
+        );
+        setItem(
+            'pow',
+            [numberType, numberType],
+            numberType
+        );
+    });
+
+    group('float', a, setItem => {
+        setItem(
+            'fromNumber',
+            [numberType],
+            floatType
+        );
+        setItem(
+            'toNumber',
+            [floatType],
+            numberType
+        );
+        setItem(
+            'toString',
+            [floatType],
+            stringType
+        );
+        setItem(
+            'assertNear',
+            [floatType, floatType, floatType],
+            voidType
+        );
+    });
+
+    group('bool', a, setItem => {
+        setItem(
+            'toString',
+            [boolType],
+            stringType
+        );
     });
 
     group('char', a, setItem => {
@@ -84,6 +179,16 @@ export function setDeclarations(a: AnalyzeContext) {
             [stringType],
             arrayType
         );
+        setItem(
+            'length',
+            [stringType],
+            numberType
+        );
+        setItem(
+            'substring',
+            [stringType, numberType, numberType],
+            stringType
+        );
         setItem(
             'assertEq',
             [stringType, stringType],
@@ -125,6 +230,16 @@ export function setDeclarations(a: AnalyzeContext) {
             [numberType],
             voidType
         );
+        setItem(
+            'writeFloat',
+            [floatType],
+            voidType
+        );
+        setItem(
+            'writeBool',
+            [boolType],
+            voidType
+        );
         setItem(
             'read',
             [],
@@ -132,10 +247,187 @@ export function setDeclarations(a: AnalyzeContext) {
         );
     });
 
+    // the argument is a printable value
+    setDecl(
+        'print',
+        [anyType],
+        voidType,
+        a,
+        {
+            check(argTypes, args, a) {
+                const argTy = argTypes[0];
+                const printableTypes = [numberType, floatType, boolType, charType, stringType];
+                if (isValidType(argTy) && argTy.kind != 'VoidType' && !printableTypes.some(x => compareType(argTy, x) == 'compatible')) {
+                    a.dispatchError(`\`print\` requires a number, a float, a bool, a char or a string, found \`${getTypeString(argTy)}\`.`, args[0]);
+                }
+                return undefined;
+            },
+        }
+    );
+
     setDecl(
         'getUnixtime',
         [],
         numberType,
         a
     );
+
+    setDecl(
+        'getEnv',
+        [stringType],
+        stringType,
+        a
+    );
+
+    // the analyzer treats the call as the end of the function, like the return statement
+    setDecl(
+        'panic',
+        [stringType],
+        voidType,
+        a,
+        { diverges: true }
+    );
+
+    setDecl(
+        'assert',
+        [boolType],
+        voidType,
+        a
+    );
+
+    // both arguments have the same type
+    setDecl(
+        'assertEq',
+        [anyType, anyType],
+        voidType,
+        a,
+        {
+            check(argTypes, args, a) {
+                if (compareType(argTypes[1], argTypes[0]) == 'incompatible') {
+                    dispatchTypeError(argTypes[1], argTypes[0], args[1], a);
+                }
+                return undefined;
+            },
+        }
+    );
+
+    // the return type is the type of the argument
+    setDecl(
+        'share',
+        [anyType],
+        anyType,
+        a,
+        {
+            check(argTypes) {
+                return argTypes[0];
+            },
+        }
+    );
+
+    // the return type is the optional of the argument type
+    setDecl(
+        'some',
+        [anyType],
+        anyType,
+        a,
+        {
+            check(argTypes) {
+                if (!isValidType(argTypes[0]) || argTypes[0].kind == 'VoidType') {
+                    return badType;
+                }
+                return createOptionalType(argTypes[0]);
+            },
+        }
+    );
+
+    // the argument is the optional
+    setDecl(
+        'isSome',
+        [anyType],
+        boolType,
+        a,
+        {
+            check(argTypes, args, a) {
+                return checkOptionalArg('isSome', argTypes[0], args[0], a);
+            },
+        }
+    );
+
+    // the return type is the inner type of the optional
+    setDecl(
+        'unwrap',
+        [anyType],
+        anyType,
+        a,
+        {
+            check(argTypes, args, a) {
+                const argTy = argTypes[0];
+                const errorTy = checkOptionalArg('unwrap', argTy, args[0], a);
+                if (errorTy != null) {
+                    return errorTy;
+                }
+                return (argTy.kind == 'OptionalType') ? argTy.innerType : anyType;
+            },
+        }
+    );
+}
+
+/**
+ * Get the type from the type name of the function signature given by the host.
+*/
+export function getHostType(name: string): ValidType | undefined {
+    switch (name) {
+        case 'void': {
+            return voidType;
+        }
+        case 'number':
+        case 'float':
+        case 'bool':
+        case 'char':
+        case 'string': {
+            return createNamedType(name);
+        }
+        case 'array': {
+            return arrayType;
+        }
+    }
+    return undefined;
+}
+
+/**
+ * Declare the function implemented by the host.
+*/
+export function setHostDeclaration(env: AnalysisEnv, name: string, paramsTy: ValidType[], returnTy: ValidType) {
+    const params = paramsTy.map(() => ({ name: 'x' }));
+    const ty = createFunctionType(paramsTy, returnTy);
+    env.set(name, createNativeFnSymbol(params, ty));
+}
+
+/**
+ * Get the names and signatures of all builtin functions.
+*/
+export function getDeclarations(): BuiltinDeclaration[] {
+    const a = new AnalyzeContext(new AnalysisEnv(), new Map(), getDefaultProjectInfo());
+    setDeclarations(a);
+
+    const declarations: BuiltinDeclaration[] = [];
+    for (const [name, symbol] of a.env.entries()) {
+        switch (symbol.kind) {
+            case 'NativeFnSymbol': {
+                if (symbol.ty.kind == 'FunctionType') {
+                    declarations.push({ name, paramTypes: symbol.ty.paramTypes, returnType: symbol.ty.returnType });
+                }
+                break;
+            }
+            case 'StructSymbol': {
+                for (const [fieldName, field] of symbol.fields) {
+                    if (field.kind == 'VariableSymbol' && field.ty.kind == 'FunctionType') {
+                        declarations.push({ name: `${name}.${fieldName}`, paramTypes: field.ty.paramTypes, returnType: field.ty.returnType });
+                    }
+                }
+                break;
+            }
+        }
+    }
+    return declarations;
 }
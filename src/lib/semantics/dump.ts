@@ -0,0 +1,83 @@
+import { AstNode, getChildNodes, SourceFile } from '../syntax/tools.js';
+import { getTypeString, Symbol } from './tools.js';
+
+/**
+ * Dump the analysis result in a canonical form.
+ * The nodes are numbered in the order of the AST traversal, and the symbols are numbered in the order of first
+ * appearance. The dump does not depend on the order in which the analyzer registers the symbols, nor on the
+ * source positions, so structurally identical programs produce the same dump.
+*/
+export function dumpSymbolTable(source: SourceFile, symbolTable: Map<AstNode, Symbol>): string {
+    const lines: string[] = [];
+    const symbolIds = new Map<Symbol, number>();
+    let nodeId = 0;
+
+    function visit(node: AstNode) {
+        const id = nodeId;
+        nodeId++;
+        const symbol = symbolTable.get(node);
+        if (symbol != null) {
+            let symbolId = symbolIds.get(symbol);
+            if (symbolId == null) {
+                symbolId = symbolIds.size;
+                symbolIds.set(symbol, symbolId);
+            }
+            lines.push(`node#${id} ${getNodeLabel(node)} -> symbol#${symbolId} ${getSymbolLabel(symbol)}`);
+        }
+        for (const child of getChildNodes(node)) {
+            visit(child);
+        }
+    }
+    visit(source);
+
+    return lines.join('\n');
+}
+
+function getNodeLabel(node: AstNode): string {
+    switch (node.kind) {
+        case 'FunctionDecl':
+        case 'FnDeclParam':
+        case 'StructDecl':
+        case 'StructDeclField':
+        case 'EnumDecl':
+        case 'EnumDeclVariant':
+        case 'StructExpr':
+        case 'StructExprField':
+        case 'VariableDecl':
+        case 'Identifier':
+        case 'FieldAccess':
+        case 'TyLabel': {
+            return `${node.kind}(${node.name})`;
+        }
+        case 'BinaryOp':
+        case 'UnaryOp': {
+            return `${node.kind}(${node.operator})`;
+        }
+        default: {
+            return node.kind;
+        }
+    }
+}
+
+function getSymbolLabel(symbol: Symbol): string {
+    switch (symbol.kind) {
+        case 'FnSymbol': {
+            return `fn ${symbol.name}: ${getTypeString(symbol.ty)}`;
+        }
+        case 'NativeFnSymbol': {
+            return `native fn: ${getTypeString(symbol.ty)}`;
+        }
+        case 'StructSymbol': {
+            return `struct ${symbol.name}`;
+        }
+        case 'EnumSymbol': {
+            return `enum ${symbol.name}`;
+        }
+        case 'VariableSymbol': {
+            return `${symbol.declKind}: ${getTypeString(symbol.ty)}`;
+        }
+        case 'ExprSymbol': {
+            return `expr: ${getTypeString(symbol.ty)}`;
+        }
+    }
+}
@@ -1,46 +1,75 @@
-import { UguisuError } from '../misc/errors.js';
+import { Diagnostic, UguisuError } from '../misc/errors.js';
+import { Trace } from '../misc/trace.js';
 import { ProjectInfo } from '../project-file.js';
-import { AstNode } from '../syntax/tools.js';
+import { AstNode, getSpan, Pos, VariableDecl } from '../syntax/tools.js';
+
+/**
+ * The options of the static analysis.
+*/
+export type AnalyzeOptions = {
+    /** report the loop that never ends. It is off by default, since the loop may be intended (e.g. the main loop of a server). */
+    warnEndlessLoop?: boolean,
+};
 
 export class AnalyzeContext {
     env: AnalysisEnv;
     symbolTable: Map<AstNode, Symbol>;
     projectInfo: ProjectInfo;
-    warn: string[];
-    error: string[];
+    options: AnalyzeOptions;
+    warn: Diagnostic[];
+    error: Diagnostic[];
+    /** the variables assigned for the first time, used to check the definite assignment */
+    assignedVars: VariableSymbol[];
+    /** the number of the layers that hold the global symbols */
+    globalDepth: number;
+    /** the environment of the enclosing function while a nested function is analyzed */
+    enclosingEnv?: AnalysisEnv;
+    /** the labels of the loops enclosing the current statement, undefined for the loop without the label */
+    loopLabels: (string | undefined)[];
+    /** the local variables declared in the function being analyzed, used to check the unused ones */
+    localVars: { node: VariableDecl, symbol: VariableSymbol }[];
+    /** the variables referred at least once */
+    usedVars: Set<VariableSymbol>;
     // flags
     isUsedAnyType: boolean;
+    /** the copy of the block lowered from the field iteration is analyzed */
+    inBlockCopy: boolean;
 
-    constructor(env: AnalysisEnv, symbolTable: Map<AstNode, Symbol>, projectInfo: ProjectInfo) {
+    constructor(env: AnalysisEnv, symbolTable: Map<AstNode, Symbol>, projectInfo: ProjectInfo, options: AnalyzeOptions = {}) {
         this.env = env;
         this.symbolTable = symbolTable;
         this.projectInfo = projectInfo;
+        this.options = options;
         this.warn = [];
         this.error = [];
+        this.assignedVars = [];
+        this.globalDepth = env.depth;
+        this.loopLabels = [];
+        this.localVars = [];
+        this.usedVars = new Set();
         this.isUsedAnyType = false;
+        this.inBlockCopy = false;
     }
 
     dispatchWarn(message: string, node?: AstNode) {
-        if (node != null) {
-            this.warn.push(`${message} (${node.pos[0]}:${node.pos[1]})`);
-        } else {
-            this.warn.push(message);
-        }
+        this.warn.push({ severity: 'warning', message, start: node?.pos, end: getNodeEnd(node) });
     }
 
     dispatchError(message: string, errorNode?: AstNode) {
-        if (errorNode != null) {
-            this.error.push(`${message} (${errorNode.pos[0]}:${errorNode.pos[1]})`);
-        } else {
-            this.error.push(message);
-        }
+        this.error.push({ severity: 'error', message, start: errorNode?.pos, end: getNodeEnd(errorNode) });
     }
 }
 
+function getNodeEnd(node: AstNode | undefined): Pos | undefined {
+    return (node != null) ? getSpan(node).end : undefined;
+}
+
 export class AnalysisEnv {
     private layers: Map<string, Symbol>[];
+    trace?: Trace;
 
-    constructor(baseEnv?: AnalysisEnv) {
+    constructor(baseEnv?: AnalysisEnv, trace?: Trace) {
+        this.trace = trace ?? baseEnv?.trace;
         if (baseEnv != null) {
             this.layers = [...baseEnv.layers];
         } else {
@@ -49,6 +78,7 @@ export class AnalysisEnv {
     }
 
     set(name: string, symbol: Symbol) {
+        this.trace?.log(`set symbol: ${name} ${symbol.kind}`);
         this.layers[0].set(name, symbol);
     }
 
@@ -62,11 +92,48 @@ export class AnalysisEnv {
         return undefined;
     }
 
+    /**
+     * Enumerate the symbols of the current layer.
+    */
+    entries(): IterableIterator<[string, Symbol]> {
+        return this.layers[0].entries();
+    }
+
+    get depth(): number {
+        return this.layers.length;
+    }
+
+    /**
+     * Create the environment for the function declared inside a function.
+     * The global layers are kept, and the local layers are merged into one layer through `mapLocal`.
+     * The local symbol is hidden when `mapLocal` returns undefined.
+    */
+    createInnerEnv(globalDepth: number, mapLocal: (symbol: Symbol) => Symbol | undefined): AnalysisEnv {
+        const env = new AnalysisEnv(undefined, this.trace);
+        const localDepth = this.layers.length - globalDepth;
+        const locals = new Map<string, Symbol>();
+        // the inner layers take precedence
+        for (const layer of this.layers.slice(0, localDepth).reverse()) {
+            for (const [name, symbol] of layer) {
+                const mapped = mapLocal(symbol);
+                if (mapped != null) {
+                    locals.set(name, mapped);
+                } else {
+                    locals.delete(name);
+                }
+            }
+        }
+        env.layers = [locals, ...this.layers.slice(localDepth)];
+        return env;
+    }
+
     enter() {
+        this.trace?.log(`enter scope`);
         this.layers.unshift(new Map());
     }
 
     leave() {
+        this.trace?.log(`leave scope`);
         if (this.layers.length <= 1) {
             throw new UguisuError('Left the root layer.');
         }
@@ -74,18 +141,24 @@ export class AnalysisEnv {
     }
 }
 
-export type Symbol = FnSymbol | NativeFnSymbol | StructSymbol | VariableSymbol | ExprSymbol;
+export type Symbol = FnSymbol | NativeFnSymbol | StructSymbol | EnumSymbol | VariableSymbol | ExprSymbol;
 
 export type FnSymbol = {
     kind: 'FnSymbol',
-    params: { name: string }[],
+    name: string,
+    params: FnParam[],
     ty: FunctionType | PendingType | BadType,
     /** for wasm */
     vars: FnVar[],
 };
 
-export function createFunctionSymbol(params: { name: string }[], ty: FunctionType | PendingType | BadType, vars: FnVar[]): FnSymbol {
-    return { kind: 'FnSymbol', params, ty, vars };
+/**
+ * The parameter that has the default value can be omitted in the call.
+*/
+export type FnParam = { name: string, hasDefault: boolean };
+
+export function createFunctionSymbol(name: string, params: FnParam[], ty: FunctionType | PendingType | BadType, vars: FnVar[]): FnSymbol {
+    return { kind: 'FnSymbol', name, params, ty, vars };
 }
 
 export type FnVar = { name: string, isParam: boolean, ty: Type };
@@ -94,10 +167,23 @@ export type NativeFnSymbol = {
     kind: 'NativeFnSymbol',
     params: { name: string }[],
     ty: FunctionType | PendingType | BadType,
+} & NativeFnRules;
+
+/**
+ * The rules of the builtin that cannot be written as the signature.
+*/
+export type NativeFnRules = {
+    /**
+     * Check the arguments of the call, which have been checked against the signature.
+     * Returns the type of the call, or undefined to use the return type of the signature.
+    */
+    check?: (argTypes: Type[], args: AstNode[], a: AnalyzeContext) => Type | undefined,
+    /** the call never returns, like the return statement (e.g. `panic`) */
+    diverges?: boolean,
 };
 
-export function createNativeFnSymbol(params: { name: string }[], ty: FunctionType | PendingType | BadType): NativeFnSymbol {
-    return { kind: 'NativeFnSymbol', params, ty };
+export function createNativeFnSymbol(params: { name: string }[], ty: FunctionType | PendingType | BadType, rules?: NativeFnRules): NativeFnSymbol {
+    return { kind: 'NativeFnSymbol', params, ty, ...rules };
 }
 
 export type StructSymbol = {
@@ -110,14 +196,29 @@ export function createStructSymbol(name: string, fields: Map<string, Symbol>): S
     return { kind: 'StructSymbol', name, fields };
 }
 
+export type EnumSymbol = {
+    kind: 'EnumSymbol',
+    name: string,
+    /** the variant names in the order of declaration */
+    variants: string[],
+};
+
+export function createEnumSymbol(name: string, variants: string[]): EnumSymbol {
+    return { kind: 'EnumSymbol', name, variants };
+}
+
 export type VariableSymbol = {
     kind: 'VariableSymbol',
     ty: Type,
     isDefined: boolean,
+    /** the constants, the function parameters and the captured variables cannot be reassigned */
+    declKind: VariableDeclKind,
 };
 
-export function createVariableSymbol(ty: Type, isDefined: boolean): VariableSymbol {
-    return { kind: 'VariableSymbol', ty, isDefined };
+export type VariableDeclKind = 'var' | 'const' | 'param' | 'capture';
+
+export function createVariableSymbol(ty: Type, isDefined: boolean, declKind: VariableDeclKind): VariableSymbol {
+    return { kind: 'VariableSymbol', ty, isDefined, declKind };
 }
 
 export type ExprSymbol = {
@@ -133,7 +234,7 @@ export function createExprSymbol(ty: Type): ExprSymbol {
 
 export type Type = ValidType | BadType | PendingType;
 
-export type ValidType = AnyType | VoidType | NamedType | FunctionType | GenericType;
+export type ValidType = AnyType | VoidType | NamedType | FunctionType | GenericType | TupleType | OptionalType;
 
 export function isValidType(ty: Type): ty is ValidType {
     return !isBadType(ty) && !isPendingType(ty);
@@ -192,16 +293,57 @@ export function createGenericType(name: string, innerTypes: Type[]): GenericType
     return { kind: 'GenericType', name, innerTypes };
 }
 
+export type TupleType = {
+    kind: 'TupleType',
+    elementTypes: Type[],
+};
+
+export function createTupleType(elementTypes: Type[]): TupleType {
+    return { kind: 'TupleType', elementTypes };
+}
+
+/**
+ * The type that may have no value (e.g. `number?`).
+ * The inner type of `none` is `any`, so that it is compatible with any optional type.
+*/
+export type OptionalType = {
+    kind: 'OptionalType',
+    innerType: Type,
+};
+
+export function createOptionalType(innerType: Type): OptionalType {
+    return { kind: 'OptionalType', innerType };
+}
+
 // builtin types
 export const badType = { kind: 'BadType' } as BadType;
 export const pendingType = { kind: 'PendingType' } as PendingType;
 export const anyType = { kind: 'AnyType' } as AnyType;
 export const voidType = { kind: 'VoidType' } as VoidType;
 export const numberType = createNamedType('number');
+export const floatType = createNamedType('float');
 export const boolType = createNamedType('bool');
 export const charType = createNamedType('char');
 export const stringType = createNamedType('string');
-export const arrayType = createNamedType('array');
+/** the array of any items, written as `array` */
+export const arrayType = createArrayType(anyType);
+
+/**
+ * The array type holds the type of the items. The item type is inferred from the array literal.
+*/
+export function createArrayType(itemType: Type): GenericType {
+    return createGenericType('array', [itemType]);
+}
+
+/**
+ * Get the item type of the array type. Returns undefined if the type is not an array.
+*/
+export function getArrayItemType(ty: Type): Type | undefined {
+    if (ty.kind == 'GenericType' && ty.name == 'array') {
+        return ty.innerTypes[0];
+    }
+    return undefined;
+}
 
 export type CompareTypeResult = 'unknown' | 'compatible' | 'incompatible';
 
@@ -287,6 +429,29 @@ export function compareType(x: Type, y: Type): CompareTypeResult {
             }
             return 'compatible';
         }
+        case 'TupleType': {
+            y = y as TupleType;
+            for (const ty of [...x.elementTypes, ...y.elementTypes]) {
+                if (isBadType(ty)) {
+                    return 'unknown';
+                }
+                if (isPendingType(ty)) {
+                    return 'incompatible';
+                }
+            }
+            if (x.elementTypes.length != y.elementTypes.length) {
+                return 'incompatible';
+            }
+            for (let i = 0; i < x.elementTypes.length; i++) {
+                if (compareType(x.elementTypes[i], y.elementTypes[i]) == 'incompatible') {
+                    return 'incompatible';
+                }
+            }
+            return 'compatible';
+        }
+        case 'OptionalType': {
+            return compareType(x.innerType, (y as OptionalType).innerType);
+        }
     }
 }
 
@@ -315,8 +480,21 @@ export function getTypeString(ty: Type): string {
             return `(${params}) => ${returnType}`;
         }
         case 'GenericType': {
+            // the array of any items is written as `array`
+            if (ty.name == 'array' && ty.innerTypes[0].kind == 'AnyType') {
+                return 'array';
+            }
             const inner = ty.innerTypes.map(x => getTypeString(x)).join(', ');
             return `${ty.name}<${inner}>`;
         }
+        case 'TupleType': {
+            const elements = ty.elementTypes.map(x => getTypeString(x)).join(', ');
+            return `(${elements})`;
+        }
+        case 'OptionalType': {
+            // the function type is enclosed in the parentheses, since `?` would apply to the return type
+            const inner = getTypeString(ty.innerType);
+            return (ty.innerType.kind == 'FunctionType') ? `(${inner})?` : `${inner}?`;
+        }
     }
 }
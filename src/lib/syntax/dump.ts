@@ -0,0 +1,74 @@
+import { AstNode, getChildNodes } from './tools.js';
+
+/**
+ * Dump the AST as an indented tree. Each line shows a node with its position (line:column).
+*/
+export function dumpAst(node: AstNode): string {
+    const lines: string[] = [];
+
+    function visit(node: AstNode, depth: number) {
+        lines.push(`${'  '.repeat(depth)}${getNodeLabel(node)} (${node.pos[0]}:${node.pos[1]})`);
+        for (const child of getChildNodes(node)) {
+            visit(child, depth + 1);
+        }
+    }
+    visit(node, 0);
+
+    return lines.join('\n');
+}
+
+/**
+ * Serialize the AST as JSON. Each node has its kind and its position (`pos`, and `end` if available).
+ * The parsed JSON has the same structure as the AST, so it can be analyzed and run again.
+*/
+export function dumpAstJson(node: AstNode, space?: number): string {
+    // the lowered blocks are generated by the analyzer, not a part of the source code
+    return JSON.stringify(node, (key, value) => (key == 'lowered') ? undefined : value, space);
+}
+
+function getNodeLabel(node: AstNode): string {
+    switch (node.kind) {
+        case 'SourceFile': {
+            return `${node.kind}(${node.filename})`;
+        }
+        case 'ImportDecl': {
+            return `${node.kind}(${JSON.stringify(node.path)})`;
+        }
+        case 'FunctionDecl':
+        case 'FnDeclParam':
+        case 'StructDecl':
+        case 'StructDeclField':
+        case 'EnumDecl':
+        case 'EnumDeclVariant':
+        case 'StructExpr':
+        case 'StructExprField':
+        case 'VariableDecl':
+        case 'Identifier':
+        case 'FieldAccess':
+        case 'TyLabel': {
+            return `${node.kind}(${node.name})`;
+        }
+        case 'ForEachFieldStatement': {
+            return `${node.kind}(${node.nameVar}, ${node.valueVar})`;
+        }
+        case 'AssignStatement': {
+            return `${node.kind}(${node.mode})`;
+        }
+        case 'BinaryOp':
+        case 'UnaryOp': {
+            return `${node.kind}(${node.operator})`;
+        }
+        case 'NumberLiteral':
+        case 'FloatLiteral':
+        case 'BoolLiteral': {
+            return `${node.kind}(${node.value})`;
+        }
+        case 'CharLiteral':
+        case 'StringLiteral': {
+            return `${node.kind}(${JSON.stringify(node.value)})`;
+        }
+        default: {
+            return node.kind;
+        }
+    }
+}
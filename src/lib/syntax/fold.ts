@@ -0,0 +1,320 @@
+import { UguisuError } from '../misc/errors.js';
+import { powNumber } from '../running/tools.js';
+import {
+    AstNode,
+    BinaryOp,
+    createBoolLiteral,
+    createFloatLiteral,
+    createNumberLiteral,
+    createStringLiteral,
+    ExprNode,
+    isExprNode,
+    SourceFile,
+    StatementNode,
+    UnaryOp
+} from './tools.js';
+
+/**
+ * Fold the operations whose operands are literals into the literals (e.g. `2 * 3 + 4` into `10`).
+ * The source file is expected to be analyzed without errors. The nodes are replaced in place.
+ * @throws UguisuError (The operation fails when evaluated, e.g. division by zero)
+*/
+export function foldConstants(source: SourceFile): SourceFile {
+    foldNode(source);
+    return source;
+}
+
+function foldNode(node: AstNode) {
+    switch (node.kind) {
+        case 'SourceFile': {
+            for (const decl of node.decls) {
+                foldNode(decl);
+            }
+            break;
+        }
+        case 'FunctionDecl':
+        case 'LambdaExpr': {
+            for (const param of node.params) {
+                if (param.defaultValue != null) {
+                    param.defaultValue = foldExpr(param.defaultValue);
+                }
+            }
+            node.body = foldBlock(node.body);
+            break;
+        }
+        case 'VariableDecl': {
+            if (node.body != null) {
+                node.body = foldExpr(node.body);
+            }
+            break;
+        }
+        case 'AssignStatement': {
+            // the target is kept as the reference to the variable
+            foldNode(node.target);
+            node.body = foldExpr(node.body);
+            break;
+        }
+        case 'IfStatement':
+        case 'IfExpr': {
+            node.cond = foldExpr(node.cond);
+            node.thenBlock = foldBlock(node.thenBlock);
+            node.elseBlock = foldBlock(node.elseBlock);
+            break;
+        }
+        case 'ConditionalExpr': {
+            node.cond = foldExpr(node.cond);
+            node.thenExpr = foldExpr(node.thenExpr);
+            node.elseExpr = foldExpr(node.elseExpr);
+            break;
+        }
+        case 'MatchExpr': {
+            node.target = foldExpr(node.target);
+            for (const arm of node.arms) {
+                arm.body = foldExpr(arm.body);
+            }
+            break;
+        }
+        case 'LoopStatement': {
+            node.block = foldBlock(node.block);
+            break;
+        }
+        case 'RepeatStatement': {
+            node.count = foldExpr(node.count);
+            node.block = foldBlock(node.block);
+            break;
+        }
+        case 'WhileStatement': {
+            node.cond = foldExpr(node.cond);
+            node.block = foldBlock(node.block);
+            break;
+        }
+        case 'ForStatement': {
+            if (node.init != null) {
+                if (isExprNode(node.init)) {
+                    node.init = foldExpr(node.init);
+                } else {
+                    foldNode(node.init);
+                }
+            }
+            if (node.cond != null) {
+                node.cond = foldExpr(node.cond);
+            }
+            if (node.step != null) {
+                if (isExprNode(node.step)) {
+                    node.step = foldExpr(node.step);
+                } else {
+                    foldNode(node.step);
+                }
+            }
+            node.block = foldBlock(node.block);
+            break;
+        }
+        case 'ForEachFieldStatement': {
+            node.target = foldExpr(node.target);
+            node.block = foldBlock(node.block);
+            if (node.lowered != null) {
+                node.lowered = node.lowered.map(x => foldBlock(x));
+            }
+            break;
+        }
+        case 'ReturnStatement': {
+            if (node.expr != null) {
+                node.expr = foldExpr(node.expr);
+            }
+            break;
+        }
+        case 'TailExpr': {
+            node.expr = foldExpr(node.expr);
+            break;
+        }
+        case 'BinaryOp': {
+            node.left = foldExpr(node.left);
+            node.right = foldExpr(node.right);
+            break;
+        }
+        case 'UnaryOp': {
+            node.expr = foldExpr(node.expr);
+            break;
+        }
+        case 'Call': {
+            node.callee = foldExpr(node.callee);
+            node.args = node.args.map(x => foldExpr(x));
+            break;
+        }
+        case 'StructExpr': {
+            for (const field of node.fields) {
+                field.body = foldExpr(field.body);
+            }
+            break;
+        }
+        case 'FieldAccess': {
+            node.target = foldExpr(node.target);
+            break;
+        }
+        case 'ArrayNode':
+        case 'TupleNode': {
+            node.items = node.items.map(x => foldExpr(x));
+            break;
+        }
+        case 'IndexAccess': {
+            node.target = foldExpr(node.target);
+            node.index = foldExpr(node.index);
+            break;
+        }
+    }
+}
+
+function foldBlock(block: StatementNode[]): StatementNode[] {
+    return block.map(statement => {
+        if (isExprNode(statement)) {
+            return foldExpr(statement);
+        }
+        foldNode(statement);
+        return statement;
+    });
+}
+
+function foldExpr(expr: ExprNode): ExprNode {
+    foldNode(expr);
+    let folded: ExprNode | undefined;
+    if (expr.kind == 'BinaryOp') {
+        folded = foldBinaryOp(expr);
+    } else if (expr.kind == 'UnaryOp') {
+        folded = foldUnaryOp(expr);
+    }
+    if (folded == null) {
+        return expr;
+    }
+    folded.end = expr.end;
+    return folded;
+}
+
+/**
+ * The operations are calculated in the same way as the runtime, including their errors.
+*/
+function foldBinaryOp(node: BinaryOp): ExprNode | undefined {
+    const { left, right } = node;
+    if (left.kind == 'NumberLiteral' && right.kind == 'NumberLiteral') {
+        const x = left.value;
+        const y = right.value;
+        switch (node.operator) {
+            case '+': {
+                return createNumberLiteral(left.pos, x + y);
+            }
+            case '-': {
+                return createNumberLiteral(left.pos, x - y);
+            }
+            case '*': {
+                return createNumberLiteral(left.pos, x * y);
+            }
+            case '/': {
+                if (y == 0) {
+                    throw new UguisuError('division by zero');
+                }
+                return createNumberLiteral(left.pos, x / y);
+            }
+            case '%': {
+                if (y == 0) {
+                    throw new UguisuError('modulo by zero');
+                }
+                return createNumberLiteral(left.pos, x % y);
+            }
+            case '**': {
+                // the same errors as the runtime
+                return createNumberLiteral(left.pos, powNumber(x, y));
+            }
+        }
+        return foldComparison(node, x, y);
+    }
+    if (left.kind == 'FloatLiteral' && right.kind == 'FloatLiteral') {
+        const x = left.value;
+        const y = right.value;
+        switch (node.operator) {
+            case '+': {
+                return createFloatLiteral(left.pos, x + y);
+            }
+            case '-': {
+                return createFloatLiteral(left.pos, x - y);
+            }
+            case '*': {
+                return createFloatLiteral(left.pos, x * y);
+            }
+            case '/': {
+                return createFloatLiteral(left.pos, x / y);
+            }
+            case '%': {
+                return createFloatLiteral(left.pos, x % y);
+            }
+        }
+        return foldComparison(node, x, y);
+    }
+    if (left.kind == 'StringLiteral' && right.kind == 'StringLiteral') {
+        if (node.operator == '+') {
+            return createStringLiteral(left.pos, left.value + right.value);
+        }
+        return foldComparison(node, left.value, right.value);
+    }
+    if (left.kind == 'BoolLiteral' && right.kind == 'BoolLiteral') {
+        switch (node.operator) {
+            case '&&': {
+                return createBoolLiteral(left.pos, left.value && right.value);
+            }
+            case '||': {
+                return createBoolLiteral(left.pos, left.value || right.value);
+            }
+            case '==': {
+                return createBoolLiteral(left.pos, left.value == right.value);
+            }
+            case '!=': {
+                return createBoolLiteral(left.pos, left.value != right.value);
+            }
+        }
+    }
+    return undefined;
+}
+
+function foldComparison<T extends number | string>(node: BinaryOp, x: T, y: T): ExprNode | undefined {
+    switch (node.operator) {
+        case '==': {
+            return createBoolLiteral(node.left.pos, x == y);
+        }
+        case '!=': {
+            return createBoolLiteral(node.left.pos, x != y);
+        }
+        case '<': {
+            return createBoolLiteral(node.left.pos, x < y);
+        }
+        case '<=': {
+            return createBoolLiteral(node.left.pos, x <= y);
+        }
+        case '>': {
+            return createBoolLiteral(node.left.pos, x > y);
+        }
+        case '>=': {
+            return createBoolLiteral(node.left.pos, x >= y);
+        }
+    }
+    return undefined;
+}
+
+function foldUnaryOp(node: UnaryOp): ExprNode | undefined {
+    const expr = node.expr;
+    switch (node.operator) {
+        case '-': {
+            if (expr.kind == 'NumberLiteral') {
+                return createNumberLiteral(node.pos, -expr.value);
+            }
+            if (expr.kind == 'FloatLiteral') {
+                return createFloatLiteral(node.pos, -expr.value);
+            }
+            break;
+        }
+        case '!': {
+            if (expr.kind == 'BoolLiteral') {
+                return createBoolLiteral(node.pos, !expr.value);
+            }
+            break;
+        }
+    }
+    return undefined;
+}
@@ -0,0 +1,456 @@
+import { UguisuError } from '../misc/errors.js';
+import {
+    AssignStatement,
+    BinaryOperator,
+    Comment,
+    comparePos,
+    ExprNode,
+    FileNode,
+    FunctionDecl,
+    getSpan,
+    IfExpr,
+    IfStatement,
+    isExprNode,
+    LambdaExpr,
+    LoopNode,
+    MatchExpr,
+    MatchPattern,
+    Pos,
+    SourceFile,
+    Span,
+    StatementNode,
+    TyLabelNode,
+    VariableDecl
+} from './tools.js';
+
+const indentUnit = '    ';
+
+/**
+ * The precedence of the binary operators. It is the same as the operator table of the parser.
+*/
+const precTable: Record<BinaryOperator, number> = {
+    '||': 1,
+    '&&': 2,
+    '==': 3,
+    '!=': 3,
+    '<': 4,
+    '<=': 4,
+    '>': 4,
+    '>=': 4,
+    '+': 5,
+    '-': 5,
+    '*': 6,
+    '/': 6,
+    '%': 6,
+    '**': 7,
+};
+
+/**
+ * The comments are put back in the order of appearance while formatting.
+*/
+class FormatContext {
+    private comments: Comment[];
+    private index: number;
+
+    constructor(comments: Comment[]) {
+        this.comments = comments;
+        this.index = 0;
+    }
+
+    /**
+     * Take the comments before the position.
+    */
+    takeBefore(pos: Pos): Comment[] {
+        const start = this.index;
+        while (this.index < this.comments.length && comparePos(this.comments[this.index].pos, pos) < 0) {
+            this.index++;
+        }
+        return this.comments.slice(start, this.index);
+    }
+
+    /**
+     * Take the comments that start on the line.
+    */
+    takeOnLine(line: number): Comment[] {
+        const start = this.index;
+        while (this.index < this.comments.length && this.comments[this.index].pos[0] == line) {
+            this.index++;
+        }
+        return this.comments.slice(start, this.index);
+    }
+
+    takeRest(): Comment[] {
+        const start = this.index;
+        this.index = this.comments.length;
+        return this.comments.slice(start);
+    }
+}
+
+/**
+ * Print the source file in the canonical layout.
+ * The blocks are indented with 4 spaces, and the declarations are separated by an empty line.
+ * The comments above a line and at the end of a line are kept in their places.
+*/
+export function formatSource(source: SourceFile): string {
+    const f = new FormatContext(source.comments);
+    const chunks: string[] = [];
+    if (source.imports.length > 0) {
+        chunks.push(source.imports.map(x => formatLine(getSpan(x), 0, f, () => `import ${formatString(x.path)};`)).join(''));
+    }
+    for (const decl of source.decls) {
+        chunks.push(formatLine(getSpan(decl), 0, f, () => formatDecl(decl, f)));
+    }
+    const rest = f.takeRest();
+    if (rest.length > 0) {
+        chunks.push(formatComments(rest, 0));
+    }
+    return chunks.join('\n');
+}
+
+/**
+ * Format a line (or lines) with the comments above it and at the end of it.
+ * The comments above it are taken before formatting the inside.
+*/
+function formatLine(span: Span, depth: number, f: FormatContext, format: () => string): string {
+    const leading = formatComments(f.takeBefore(span.start), depth);
+    const text = format();
+    const trailing = f.takeOnLine(span.end[0]).map(x => ` ${x.text}`).join('');
+    return `${leading}${indentUnit.repeat(depth)}${text}${trailing}\n`;
+}
+
+function formatComments(comments: Comment[], depth: number): string {
+    return comments.map(x => `${indentUnit.repeat(depth)}${x.text}\n`).join('');
+}
+
+function formatDecl(node: FileNode, f: FormatContext): string {
+    switch (node.kind) {
+        case 'FunctionDecl': {
+            return formatFunction(node, 0, f);
+        }
+        case 'StructDecl': {
+            const exported = node.exported ? 'export ' : '';
+            const fields = node.fields.map(x => formatLine(getSpan(x), 1, f, () => `${x.name}: ${formatTyLabel(x.ty)},`));
+            const rest = formatComments(f.takeBefore(getSpan(node).end), 1);
+            return `${exported}struct ${node.name} {\n${fields.join('')}${rest}}`;
+        }
+        case 'EnumDecl': {
+            const exported = node.exported ? 'export ' : '';
+            const variants = node.variants.map(x => formatLine(getSpan(x), 1, f, () => `${x.name},`));
+            const rest = formatComments(f.takeBefore(getSpan(node).end), 1);
+            return `${exported}enum ${node.name} {\n${variants.join('')}${rest}}`;
+        }
+        case 'VariableDecl': {
+            return `${formatVariableDecl(node, 0, f)};`;
+        }
+    }
+}
+
+function formatFunction(node: FunctionDecl | LambdaExpr, depth: number, f: FormatContext): string {
+    const head = (node.kind == 'FunctionDecl') ? `${node.exported ? 'export ' : ''}fn ${node.name}` : 'fn';
+    const params = node.params.map(x => {
+        const ty = (x.ty != null) ? `: ${formatTyLabel(x.ty)}` : '';
+        const defaultValue = (x.defaultValue != null) ? ` = ${formatExpr(x.defaultValue, depth, f)}` : '';
+        return `${x.name}${ty}${defaultValue}`;
+    });
+    const returnTy = (node.returnTy != null) ? `: ${formatTyLabel(node.returnTy)}` : '';
+    return `${head}(${params.join(', ')})${returnTy} ${formatBlock(node.body, depth, f, getSpan(node).end)}`;
+}
+
+/**
+ * The comments before the end position are put at the end of the block.
+*/
+function formatBlock(block: StatementNode[], depth: number, f: FormatContext, end?: Pos): string {
+    const lines = block.map(x => formatLine(getSpan(x), depth + 1, f, () => formatStatement(x, depth + 1, f)));
+    const rest = (end != null) ? formatComments(f.takeBefore(end), depth + 1) : '';
+    return `{\n${lines.join('')}${rest}${indentUnit.repeat(depth)}}`;
+}
+
+function formatStatement(node: StatementNode, depth: number, f: FormatContext): string {
+    if (isExprNode(node)) {
+        return `${formatExpr(node, depth, f)};`;
+    }
+    switch (node.kind) {
+        case 'FunctionDecl': {
+            return formatFunction(node, depth, f);
+        }
+        case 'VariableDecl': {
+            return `${formatVariableDecl(node, depth, f)};`;
+        }
+        case 'AssignStatement': {
+            return `${formatAssign(node, depth, f)};`;
+        }
+        case 'IfStatement': {
+            return formatIf(node, depth, f);
+        }
+        case 'LoopStatement': {
+            return `${formatLabel(node)}loop ${formatBlock(node.block, depth, f, getSpan(node).end)}`;
+        }
+        case 'RepeatStatement': {
+            return `${formatLabel(node)}repeat ${formatExpr(node.count, depth, f)} ${formatBlock(node.block, depth, f, getSpan(node).end)}`;
+        }
+        case 'WhileStatement': {
+            return `${formatLabel(node)}while ${formatExpr(node.cond, depth, f)} ${formatBlock(node.block, depth, f, getSpan(node).end)}`;
+        }
+        case 'ForStatement': {
+            let init = '';
+            if (node.init != null) {
+                if (isExprNode(node.init)) {
+                    init = formatExpr(node.init, depth, f);
+                } else if (node.init.kind == 'VariableDecl') {
+                    init = formatVariableDecl(node.init, depth, f);
+                } else {
+                    init = formatAssign(node.init, depth, f);
+                }
+            }
+            const cond = (node.cond != null) ? ` ${formatExpr(node.cond, depth, f)}` : '';
+            let step = '';
+            if (node.step != null) {
+                step = isExprNode(node.step) ? ` ${formatExpr(node.step, depth, f)}` : ` ${formatAssign(node.step, depth, f)}`;
+            }
+            return `${formatLabel(node)}for (${init};${cond};${step}) ${formatBlock(node.block, depth, f, getSpan(node).end)}`;
+        }
+        case 'ForEachFieldStatement': {
+            return `${formatLabel(node)}for ${node.nameVar}, ${node.valueVar} in ${formatExpr(node.target, depth, f)} ${formatBlock(node.block, depth, f, getSpan(node).end)}`;
+        }
+        case 'ReturnStatement': {
+            return (node.expr != null) ? `return ${formatExpr(node.expr, depth, f)};` : 'return;';
+        }
+        case 'BreakStatement': {
+            return (node.label != null) ? `break ${node.label};` : 'break;';
+        }
+        case 'ContinueStatement': {
+            return (node.label != null) ? `continue ${node.label};` : 'continue;';
+        }
+        case 'TailExpr': {
+            return formatExpr(node.expr, depth, f);
+        }
+    }
+}
+
+function formatLabel(node: LoopNode): string {
+    return (node.label != null) ? `${node.label}: ` : '';
+}
+
+function formatVariableDecl(node: VariableDecl, depth: number, f: FormatContext): string {
+    let text = `${node.isConst ? 'const' : 'var'} ${node.name}`;
+    if (node.ty != null) {
+        text += `: ${formatTyLabel(node.ty)}`;
+    }
+    if (node.body != null) {
+        text += ` = ${formatExpr(node.body, depth, f)}`;
+    }
+    return text;
+}
+
+function formatAssign(node: AssignStatement, depth: number, f: FormatContext): string {
+    return `${formatExpr(node.target, depth, f)} ${node.mode} ${formatExpr(node.body, depth, f)}`;
+}
+
+/**
+ * The `else if` chain is printed as is, instead of the nested block.
+*/
+function formatIf(node: IfStatement | IfExpr, depth: number, f: FormatContext): string {
+    const end = getSpan(node).end;
+    // the comments before `else` are put in the else block
+    const thenEnd = (node.elseBlock.length == 0) ? end : undefined;
+    let text = `if ${formatExpr(node.cond, depth, f)} ${formatBlock(node.thenBlock, depth, f, thenEnd)}`;
+    if (node.elseBlock.length == 1) {
+        const elseNode = node.elseBlock[0];
+        if (elseNode.kind == 'IfStatement') {
+            return `${text} else ${formatIf(elseNode, depth, f)}`;
+        }
+        if (elseNode.kind == 'TailExpr' && elseNode.expr.kind == 'IfExpr') {
+            return `${text} else ${formatIf(elseNode.expr, depth, f)}`;
+        }
+    }
+    if (node.kind == 'IfExpr' || node.elseBlock.length > 0) {
+        text += ` else ${formatBlock(node.elseBlock, depth, f, end)}`;
+    }
+    return text;
+}
+
+function formatExpr(node: ExprNode, depth: number, f: FormatContext): string {
+    switch (node.kind) {
+        case 'NumberLiteral':
+        case 'BoolLiteral': {
+            return node.value.toString();
+        }
+        case 'NoneLiteral': {
+            return 'none';
+        }
+        case 'FloatLiteral': {
+            const text = node.value.toString();
+            return Number.isInteger(node.value) ? `${text}.0` : text;
+        }
+        case 'CharLiteral': {
+            return `'${escapeChars(node.value, '\'')}'`;
+        }
+        case 'StringLiteral': {
+            return formatString(node.value);
+        }
+        case 'Identifier': {
+            return node.name;
+        }
+        case 'BinaryOp': {
+            const prec = precTable[node.operator];
+            // the operators are left associative except `**`
+            const leftPrec = (node.operator == '**') ? prec + 1 : prec;
+            const rightPrec = (node.operator == '**') ? prec : prec + 1;
+            let left = formatExpr(node.left, depth, f);
+            if ((node.left.kind == 'BinaryOp' && precTable[node.left.operator] < leftPrec) || node.left.kind == 'ConditionalExpr') {
+                left = `(${left})`;
+            }
+            let right = formatExpr(node.right, depth, f);
+            if ((node.right.kind == 'BinaryOp' && precTable[node.right.operator] < rightPrec) || node.right.kind == 'ConditionalExpr') {
+                right = `(${right})`;
+            }
+            return `${left} ${node.operator} ${right}`;
+        }
+        case 'UnaryOp': {
+            let expr = formatExpr(node.expr, depth, f);
+            if (node.expr.kind == 'BinaryOp' || node.expr.kind == 'ConditionalExpr' || isNegativeLiteral(node.expr)) {
+                expr = `(${expr})`;
+            }
+            return `${node.operator}${expr}`;
+        }
+        case 'Call': {
+            const args = node.args.map(x => formatExpr(x, depth, f));
+            return `${formatOperand(node.callee, depth, f)}(${args.join(', ')})`;
+        }
+        case 'StructExpr': {
+            if (node.fields.length == 0) {
+                return `new ${node.name} {}`;
+            }
+            const fields = node.fields.map(x => {
+                // the shorthand of the field initialized with the variable of the same name
+                if (x.body.kind == 'Identifier' && x.body.name == x.name) {
+                    return x.name;
+                }
+                return `${x.name}: ${formatExpr(x.body, depth, f)}`;
+            });
+            return `new ${node.name} { ${fields.join(', ')} }`;
+        }
+        case 'FieldAccess': {
+            return `${formatOperand(node.target, depth, f)}.${node.name}`;
+        }
+        case 'ArrayNode': {
+            return `[${node.items.map(x => formatExpr(x, depth, f)).join(', ')}]`;
+        }
+        case 'IndexAccess': {
+            return `${formatOperand(node.target, depth, f)}[${formatExpr(node.index, depth, f)}]`;
+        }
+        case 'TupleNode': {
+            return `(${node.items.map(x => formatExpr(x, depth, f)).join(', ')})`;
+        }
+        case 'IfExpr': {
+            return formatIf(node, depth, f);
+        }
+        case 'MatchExpr': {
+            return formatMatch(node, depth, f);
+        }
+        case 'LambdaExpr': {
+            return formatFunction(node, depth, f);
+        }
+        case 'ConditionalExpr': {
+            // the condition is enclosed in parentheses when it is also a conditional operator
+            let cond = formatExpr(node.cond, depth, f);
+            if (node.cond.kind == 'ConditionalExpr') {
+                cond = `(${cond})`;
+            }
+            return `${cond} ? ${formatExpr(node.thenExpr, depth, f)} : ${formatExpr(node.elseExpr, depth, f)}`;
+        }
+    }
+    throw new UguisuError('unexpected node');
+}
+
+function formatMatch(node: MatchExpr, depth: number, f: FormatContext): string {
+    const arms = node.arms.map(x => formatLine(getSpan(x), depth + 1, f, () => `${formatPattern(x.pattern)} => ${formatExpr(x.body, depth + 1, f)},`));
+    const rest = formatComments(f.takeBefore(getSpan(node).end), depth + 1);
+    return `match ${formatExpr(node.target, depth, f)} {\n${arms.join('')}${rest}${indentUnit.repeat(depth)}}`;
+}
+
+function formatPattern(pattern: MatchPattern): string {
+    switch (pattern.kind) {
+        case 'variant': {
+            return pattern.name;
+        }
+        case 'number': {
+            return pattern.value.toString();
+        }
+        case 'wildcard': {
+            return '_';
+        }
+    }
+}
+
+/**
+ * Format the operand of the unary operator or the target of the suffix (call, field access and index access).
+ * The operations are enclosed in parentheses so that they are parsed in the same order.
+*/
+function formatOperand(node: ExprNode, depth: number, f: FormatContext): string {
+    const text = formatExpr(node, depth, f);
+    if (node.kind == 'BinaryOp' || node.kind == 'UnaryOp' || node.kind == 'IfExpr' || node.kind == 'MatchExpr' || node.kind == 'ConditionalExpr' || isNegativeLiteral(node)) {
+        return `(${text})`;
+    }
+    return text;
+}
+
+/**
+ * The negative literal is produced by the constant folding.
+*/
+function isNegativeLiteral(node: ExprNode): boolean {
+    return (node.kind == 'NumberLiteral' || node.kind == 'FloatLiteral') && node.value < 0;
+}
+
+function formatTyLabel(node: TyLabelNode): string {
+    if (node.kind == 'FunctionTyLabel') {
+        return `(${node.params.map(x => formatTyLabel(x)).join(', ')}) => ${formatTyLabel(node.returnTy)}`;
+    }
+    if (node.kind == 'TupleTyLabel') {
+        return `(${node.items.map(x => formatTyLabel(x)).join(', ')})`;
+    }
+    if (node.kind == 'OptionalTyLabel') {
+        return `${formatTyLabel(node.innerTy)}?`;
+    }
+    return node.name;
+}
+
+function formatString(value: string): string {
+    return `"${escapeChars(value, '"')}"`;
+}
+
+function escapeChars(value: string, quote: string): string {
+    let text = '';
+    for (const c of value) {
+        switch (c) {
+            case '\\': {
+                text += '\\\\';
+                break;
+            }
+            case '\r': {
+                text += '\\r';
+                break;
+            }
+            case '\n': {
+                text += '\\n';
+                break;
+            }
+            case '\t': {
+                text += '\\t';
+                break;
+            }
+            case '\0': {
+                text += '\\0';
+                break;
+            }
+            case quote: {
+                text += `\\${quote}`;
+                break;
+            }
+            default: {
+                text += c;
+            }
+        }
+    }
+    return text;
+}
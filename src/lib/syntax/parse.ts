@@ -1,11 +1,14 @@
-import { UguisuError } from '../misc/errors.js';
+import { Diagnostic, formatDiagnostic, UguisuError } from '../misc/errors.js';
 import { Trace } from '../misc/trace.js';
 import { ProjectInfo } from '../project-file.js';
-import { LiteralValue, Scanner, Token } from './scan.js';
+import { describeToken, LiteralValue, Scanner, Token } from './scan.js';
 import {
     AssignMode,
+    AstNode,
     BinaryOperator,
     BreakStatement,
+    Comment,
+    ContinueStatement,
     createArrayNode,
     createAssignStatement,
     createBinaryOp,
@@ -13,66 +16,166 @@ import {
     createBreakStatement,
     createCall,
     createCharLiteral,
+    createConditionalExpr,
+    createContinueStatement,
+    createEnumDecl,
+    createEnumDeclVariant,
+    createFloatLiteral,
     createFieldAccess,
     createFnDeclParam,
+    createForEachFieldStatement,
     createFunctionDecl,
+    createFunctionTyLabel,
+    createIfExpr,
     createIdentifier,
     createIfStatement,
     createIndexAccess,
+    createLambdaExpr,
     createLoopStatement,
+    createMatchArm,
+    createMatchExpr,
+    createNoneLiteral,
+    createOptionalTyLabel,
+    createRepeatStatement,
+    createWhileStatement,
+    createForStatement,
     createNumberLiteral,
     createReturnStatement,
+    createImportDecl,
     createSourceFile,
     createStringLiteral,
     createStructDecl,
     createStructDeclField,
     createStructExpr,
     createStructExprField,
+    createTailExpr,
+    createTupleNode,
+    createTupleTyLabel,
     createTyLabel,
     createUnaryOp,
     createVariableDecl,
+    EnumDecl,
+    EnumDeclVariant,
     ExprNode,
     FileNode,
     FnDeclParam,
+    ForEachFieldStatement,
     FunctionDecl,
+    Identifier,
+    IfExpr,
     IfStatement,
+    ImportDecl,
+    LambdaExpr,
+    LoopNode,
     LoopStatement,
+    MatchArm,
+    MatchExpr,
+    MatchPattern,
+    Pos,
+    RepeatStatement,
+    WhileStatement,
+    ForStatement,
+    AssignStatement,
     ReturnStatement,
     SourceFile,
     StatementNode,
     StructDecl,
     StructDeclField,
     StructExprField,
-    TyLabel,
+    TyLabelNode,
     VariableDecl
 } from './tools.js';
 
 const trace = Trace.getDefault().createChild(false);
 
+/**
+ * Parse the source code.
+ * The parser recovers from the syntax error and continues parsing, so all syntax errors are reported at once.
+ * @throws UguisuError (Syntax error. The message contains all the errors on separate lines)
+*/
 export function parse(sourceCode: string, filename: string, projectInfo: ProjectInfo): SourceFile {
     const p = new ParseContext(new Scanner(), projectInfo);
-    p.setup(sourceCode);
-    return parseSourceFile(p, filename);
+    let source: SourceFile | undefined;
+    try {
+        p.setup(sourceCode);
+        source = parseSourceFile(p, filename);
+    } catch (err) {
+        if (!(err instanceof UguisuError)) {
+            throw err;
+        }
+        p.dispatchError(err.message);
+    }
+    if (source == null || p.errors.length > 0) {
+        throw new UguisuError(p.errors.map(x => formatDiagnostic(x)).join('\n'), p.errors);
+    }
+    return source;
+}
+
+/**
+ * Parse the source code that consists of a single expression.
+ * @throws UguisuError (Syntax error)
+*/
+export function parseExpression(sourceCode: string, projectInfo: ProjectInfo): ExprNode {
+    const p = new ParseContext(new Scanner(), projectInfo);
+    let expr: ExprNode | undefined;
+    try {
+        p.setup(sourceCode);
+        expr = parseExpr(p);
+        p.expect(Token.EOF);
+    } catch (err) {
+        if (!(err instanceof UguisuError)) {
+            throw err;
+        }
+        p.dispatchError(err.message);
+    }
+    if (expr == null || p.errors.length > 0) {
+        throw new UguisuError(p.errors.map(x => formatDiagnostic(x)).join('\n'), p.errors);
+    }
+    return expr;
 }
 
 class ParseContext {
     s: Scanner;
     projectInfo: ProjectInfo;
+    errors: Diagnostic[];
+    /** set when the scanner failed. The parser cannot recover from it. */
+    isAborted: boolean;
 
     constructor(s: Scanner, projectInfo: ProjectInfo) {
         this.s = s;
         this.projectInfo = projectInfo;
+        this.errors = [];
+        this.isAborted = false;
     }
 
     setup(sourceCode: string) {
         this.s.setup(sourceCode);
-        this.s.next();
+        this.scan();
+    }
+
+    dispatchError(message: string) {
+        this.errors.push({ severity: 'error', message, start: this.getPos() });
+    }
+
+    /**
+     * Check whether the parser can recover from the error.
+    */
+    isRecoverable(err: unknown): err is UguisuError {
+        return (err instanceof UguisuError && !this.isAborted);
     }
 
     getPos(): [number, number] {
         return this.s.getPos();
     }
 
+    /**
+     * Set the end position of the node to the end of the last consumed token.
+    */
+    end<T extends AstNode>(node: T): T {
+        node.end = this.s.getLastTokenEndPos();
+        return node;
+    }
+
     getToken(): Token {
         return this.s.getToken();
     }
@@ -85,12 +188,25 @@ class ParseContext {
         return this.s.getLiteralValue();
     }
 
+    getComments(): Comment[] {
+        return this.s.getComments();
+    }
+
     /**
      * Move to the next token.
     */
     next() {
         trace.log(`[parse] next`);
-        this.s.next();
+        this.scan();
+    }
+
+    private scan() {
+        try {
+            this.s.next();
+        } catch (err) {
+            this.isAborted = true;
+            throw err;
+        }
     }
 
     tokenIs(token: Token): boolean {
@@ -98,12 +214,22 @@ class ParseContext {
         return (this.getToken() == token);
     }
 
+    /**
+     * Create the error that the current token is none of the expected ones.
+     * The expected item is the token or the description of the syntax (e.g. `expression`).
+    */
+    unexpected(expected: (Token | string)[]): UguisuError {
+        const items = expected.map(x => (typeof x == 'string') ? x : describeToken(x));
+        const expectedText = (items.length == 1) ? items[0] : `one of ${items.join(', ')}`;
+        return new UguisuError(`expected ${expectedText}, found ${describeToken(this.getToken())}.`);
+    }
+
     /**
      * Expect the current token.
     */
     expect(token: Token) {
         if (!this.tokenIs(token)) {
-            throw new UguisuError(`unexpected token: ${Token[this.getToken()]}`);
+            throw this.unexpected([token]);
         }
     }
 
@@ -120,7 +246,8 @@ class ParseContext {
 
 /**
  * ```text
- * <Block> = "{" <Statement>* "}"
+ * <Block> = "{" <Statement>* <TailExpr>? "}"
+ * <TailExpr> = <Expr>
  * ```
 */
 function parseBlock(p: ParseContext): StatementNode[] {
@@ -128,8 +255,16 @@ function parseBlock(p: ParseContext): StatementNode[] {
 
     p.expectAndNext(Token.BeginBrace);
     const statements: StatementNode[] = [];
-    while (!p.tokenIs(Token.EndBrace)) {
-        statements.push(parseStatement(p));
+    while (!p.tokenIs(Token.EndBrace) && !p.tokenIs(Token.EOF)) {
+        try {
+            statements.push(parseStatement(p));
+        } catch (err) {
+            if (!p.isRecoverable(err)) {
+                throw err;
+            }
+            p.dispatchError(err.message);
+            skipStatement(p);
+        }
     }
     p.expectAndNext(Token.EndBrace);
 
@@ -139,20 +274,128 @@ function parseBlock(p: ParseContext): StatementNode[] {
 
 /**
  * ```text
- * <TyLabel> = ":" <identifier>
+ * <TyLabel> = ":" <Ty>
  * ```
 */
-function parseTyLabel(p: ParseContext): TyLabel {
+function parseTyLabel(p: ParseContext): TyLabelNode {
     trace.enter('[parse] parseTyLabel');
 
     p.expectAndNext(Token.Colon);
+    const ty = parseTy(p);
+
+    trace.leave();
+    return ty;
+}
+
+/**
+ * ```text
+ * <Ty> = <TyInner> "?"?
+ * ```
+*/
+function parseTy(p: ParseContext): TyLabelNode {
     const pos = p.getPos();
+    const ty = parseTyInner(p);
+
+    // optional type
+    if (p.tokenIs(Token.Question)) {
+        p.next();
+        return p.end(createOptionalTyLabel(pos, ty));
+    }
+
+    return ty;
+}
+
+/**
+ * ```text
+ * <TyInner> = <identifier> / "(" (<Ty> ("," <Ty>)* ","?)? ")" "=>" <Ty> / "(" <Ty> ("," <Ty>)+ ","? ")"
+ * ```
+*/
+function parseTyInner(p: ParseContext): TyLabelNode {
+    const pos = p.getPos();
+
+    // function type or tuple type
+    if (p.tokenIs(Token.BeginParen)) {
+        p.next();
+        const params: TyLabelNode[] = [];
+        if (!p.tokenIs(Token.EndParen)) {
+            params.push(parseTy(p));
+            while (p.tokenIs(Token.Comma)) {
+                p.next();
+                if (p.tokenIs(Token.EndParen)) {
+                    break;
+                }
+                params.push(parseTy(p));
+            }
+        }
+        p.expectAndNext(Token.EndParen);
+        if (!p.tokenIs(Token.Arrow)) {
+            if (params.length < 2) {
+                throw new UguisuError('a tuple type requires two or more elements.');
+            }
+            return p.end(createTupleTyLabel(pos, params));
+        }
+        p.next();
+        const returnTy = parseTy(p);
+        return p.end(createFunctionTyLabel(pos, params, returnTy));
+    }
+
     p.expect(Token.Ident);
     const name = p.getIdentValue();
     p.next();
+    return p.end(createTyLabel(pos, name));
+}
 
-    trace.leave();
-    return createTyLabel(pos, name);
+/**
+ * Skip the tokens until the end of the statement for the error recovery.
+ * Stops after the semicolon or the block that ends the statement, or before the end of the enclosing block.
+*/
+function skipStatement(p: ParseContext) {
+    let depth = 0;
+    while (!p.tokenIs(Token.EOF)) {
+        if (p.tokenIs(Token.BeginBrace)) {
+            depth++;
+        } else if (p.tokenIs(Token.EndBrace)) {
+            if (depth == 0) {
+                return;
+            }
+            depth--;
+            if (depth == 0) {
+                p.next();
+                return;
+            }
+        } else if (p.tokenIs(Token.Semi) && depth == 0) {
+            p.next();
+            return;
+        }
+        p.next();
+    }
+}
+
+/**
+ * Skip the tokens until the beginning of the next declaration for the error recovery.
+*/
+function skipDeclaration(p: ParseContext) {
+    let depth = 0;
+    while (!p.tokenIs(Token.EOF)) {
+        if (p.tokenIs(Token.BeginBrace)) {
+            depth++;
+        } else if (p.tokenIs(Token.EndBrace)) {
+            depth = Math.max(depth - 1, 0);
+        } else if (depth == 0) {
+            switch (p.getToken()) {
+                case Token.Import:
+                case Token.Export:
+                case Token.Fn:
+                case Token.Struct:
+                case Token.Enum:
+                case Token.Var:
+                case Token.Const: {
+                    return;
+                }
+            }
+        }
+        p.next();
+    }
 }
 
 //#endregion General
@@ -161,10 +404,11 @@ function parseTyLabel(p: ParseContext): TyLabel {
 
 /**
  * ```text
- * <SourceFile> = (<FunctionDecl>)*
+ * <SourceFile> = (<ImportDecl> / <FunctionDecl> / <StructDecl> / <EnumDecl> / <VariableDecl>)*
  * ```
 */
 function parseSourceFile(p: ParseContext, filename: string): SourceFile {
+    let imports: ImportDecl[] = [];
     let decls: FileNode[] = [];
     trace.enter('[parse] parseSourceFile');
 
@@ -174,35 +418,85 @@ function parseSourceFile(p: ParseContext, filename: string): SourceFile {
             trace.leave();
             break;
         }
-        let exported = false;
-        if (p.getToken() == Token.Export) {
-            p.next();
-            exported = true;
-        }
-        switch (p.getToken()) {
-            case Token.Fn: {
-                decls.push(parseFunctionDecl(p, exported));
-                break;
+        const pos = p.getPos();
+        try {
+            if (p.tokenIs(Token.Import)) {
+                imports.push(parseImportDecl(p));
+            } else {
+                decls.push(parseDeclaration(p));
             }
-            case Token.Struct: {
-                decls.push(parseStructDecl(p, exported));
-                break;
+        } catch (err) {
+            if (!p.isRecoverable(err)) {
+                throw err;
             }
-            default: {
-                throw new UguisuError(`unexpected token: ${Token[p.getToken()]}`);
+            p.dispatchError(err.message);
+            // skip the token where the declaration starts, so that the parser always makes progress
+            const currentPos = p.getPos();
+            if (currentPos[0] == pos[0] && currentPos[1] == pos[1]) {
+                p.next();
             }
+            skipDeclaration(p);
         }
         trace.leave();
     }
 
     trace.leave();
-    return createSourceFile([1, 1], filename, decls);
+    return p.end(createSourceFile([1, 1], filename, imports, decls, p.getComments()));
+}
+
+function parseDeclaration(p: ParseContext): FileNode {
+    let exported = false;
+    if (p.getToken() == Token.Export) {
+        p.next();
+        exported = true;
+    }
+    switch (p.getToken()) {
+        case Token.Fn: {
+            return parseFunctionDecl(p, exported);
+        }
+        case Token.Struct: {
+            return parseStructDecl(p, exported);
+        }
+        case Token.Enum: {
+            return parseEnumDecl(p, exported);
+        }
+        case Token.Var:
+        case Token.Const: {
+            if (exported) {
+                throw new UguisuError('exported variable is not supported yet.');
+            }
+            return parseVariableDecl(p);
+        }
+        default: {
+            throw p.unexpected([Token.Fn, Token.Struct, Token.Enum, Token.Var, Token.Const]);
+        }
+    }
+}
+
+/**
+ * ```text
+ * <ImportDecl> = "import" <string literal> ";"
+ * ```
+*/
+function parseImportDecl(p: ParseContext): ImportDecl {
+    const pos = p.getPos();
+    p.next();
+
+    p.expect(Token.Literal);
+    const literal = p.getLiteralValue();
+    if (literal.kind != 'string') {
+        throw p.unexpected(['string literal']);
+    }
+    p.next();
+    p.expectAndNext(Token.Semi);
+
+    return p.end(createImportDecl(pos, literal.value));
 }
 
 /**
  * ```text
  * <FunctionDecl> = "fn" <identifier> "(" <FnDeclParams>? ")" <TyLabel>? <Block>
- * <FnDeclParams> = <FnDeclParam> ("," <FnDeclParam>)*
+ * <FnDeclParams> = <FnDeclParam> ("," <FnDeclParam>)* ","?
  * ```
 */
 function parseFunctionDecl(p: ParseContext, exported: boolean): FunctionDecl {
@@ -213,8 +507,44 @@ function parseFunctionDecl(p: ParseContext, exported: boolean): FunctionDecl {
     p.expect(Token.Ident);
     const name = p.getIdentValue();
     p.next();
+    const params = parseFnDeclParams(p);
+    let returnTy;
+    if (p.tokenIs(Token.Colon)) {
+        returnTy = parseTyLabel(p);
+    }
+    const body = parseBlock(p);
+
+    trace.leave();
+    return p.end(createFunctionDecl(pos, name, params, body, returnTy, exported));
+}
+
+/**
+ * ```text
+ * <LambdaExpr> = "fn" "(" <FnDeclParams>? ")" <TyLabel>? <Block>
+ * ```
+*/
+function parseLambdaExpr(p: ParseContext): LambdaExpr {
+    trace.enter('[parse] parseLambdaExpr');
+
+    const pos = p.getPos();
+    p.next();
+    const params = parseFnDeclParams(p);
+    let returnTy;
+    if (p.tokenIs(Token.Colon)) {
+        returnTy = parseTyLabel(p);
+    }
+    const body = parseBlock(p);
+
+    trace.leave();
+    return p.end(createLambdaExpr(pos, params, body, returnTy));
+}
+
+/**
+ * Parse the parameter list enclosed in the parentheses.
+*/
+function parseFnDeclParams(p: ParseContext): FnDeclParam[] {
     p.expectAndNext(Token.BeginParen);
-    let params: FnDeclParam[] = [];
+    const params: FnDeclParam[] = [];
     if (!p.tokenIs(Token.EndParen)) {
         params.push(parseFnDeclParam(p));
         while (p.tokenIs(Token.Comma)) {
@@ -226,19 +556,12 @@ function parseFunctionDecl(p: ParseContext, exported: boolean): FunctionDecl {
         }
     }
     p.expectAndNext(Token.EndParen);
-    let returnTy;
-    if (p.tokenIs(Token.Colon)) {
-        returnTy = parseTyLabel(p);
-    }
-    const body = parseBlock(p);
-
-    trace.leave();
-    return createFunctionDecl(pos, name, params, body, returnTy, exported);
+    return params;
 }
 
 /**
  * ```text
- * <FnDeclParam> = <identifier> <TyLabel>?
+ * <FnDeclParam> = <identifier> <TyLabel>? ("=" <Expr>)?
  * ```
 */
 function parseFnDeclParam(p: ParseContext): FnDeclParam {
@@ -254,13 +577,19 @@ function parseFnDeclParam(p: ParseContext): FnDeclParam {
         ty = parseTyLabel(p);
     }
 
+    let defaultValue;
+    if (p.tokenIs(Token.Assign)) {
+        p.next();
+        defaultValue = parseExpr(p);
+    }
+
     trace.leave();
-    return createFnDeclParam(pos, name, ty);
+    return p.end(createFnDeclParam(pos, name, ty, defaultValue));
 }
 
 /**
  * <StructDecl> = "struct" <identifier> "{" <StructDeclFields>? "}"
- * <StructDeclFields> = <StructDeclField> ("," <StructDeclField>)*
+ * <StructDeclFields> = <StructDeclField> ("," <StructDeclField>)* ","?
 */
 function parseStructDecl(p: ParseContext, exported: boolean): StructDecl {
     const pos = p.getPos();
@@ -284,7 +613,7 @@ function parseStructDecl(p: ParseContext, exported: boolean): StructDecl {
     }
     p.expectAndNext(Token.EndBrace);
 
-    return createStructDecl(pos, name, fields, exported);
+    return p.end(createStructDecl(pos, name, fields, exported));
 }
 
 /**
@@ -299,7 +628,47 @@ function parseStructDeclField(p: ParseContext): StructDeclField {
 
     const ty = parseTyLabel(p);
 
-    return createStructDeclField(pos, name, ty);
+    return p.end(createStructDeclField(pos, name, ty));
+}
+
+/**
+ * <EnumDecl> = "enum" <identifier> "{" <EnumDeclVariants>? "}"
+ * <EnumDeclVariants> = <EnumDeclVariant> ("," <EnumDeclVariant>)* ","?
+ * <EnumDeclVariant> = <identifier>
+*/
+function parseEnumDecl(p: ParseContext, exported: boolean): EnumDecl {
+    const pos = p.getPos();
+    p.next();
+
+    p.expect(Token.Ident);
+    const name = p.getIdentValue();
+    p.next();
+
+    p.expectAndNext(Token.BeginBrace);
+    let variants: EnumDeclVariant[] = [];
+    if (!p.tokenIs(Token.EndBrace)) {
+        variants.push(parseEnumDeclVariant(p));
+        while (p.tokenIs(Token.Comma)) {
+            p.next();
+            if (p.tokenIs(Token.EndBrace)) {
+                break;
+            }
+            variants.push(parseEnumDeclVariant(p));
+        }
+    }
+    p.expectAndNext(Token.EndBrace);
+
+    return p.end(createEnumDecl(pos, name, variants, exported));
+}
+
+function parseEnumDeclVariant(p: ParseContext): EnumDeclVariant {
+    const pos = p.getPos();
+
+    p.expect(Token.Ident);
+    const name = p.getIdentValue();
+    p.next();
+
+    return p.end(createEnumDeclVariant(pos, name));
 }
 
 //#endregion SourceFile
@@ -308,12 +677,17 @@ function parseStructDeclField(p: ParseContext): StructDeclField {
 
 /**
  * ```text
- * <Statement> = <VariableDecl> / <AssignStatement> / <IfStatement> / <LoopStatement> / <ReturnStatement> / <BreakStatement> / <ExprNode>
+ * <Statement> = <FunctionDecl> / <VariableDecl> / <AssignStatement> / <IfStatement> / <LabeledStatement> / <LoopStatement> / <RepeatStatement> / <WhileStatement> / <ForStatement> / <ForEachFieldStatement> / <ReturnStatement> / <BreakStatement> / <ContinueStatement> / <ExprNode>
  * ```
 */
 function parseStatement(p: ParseContext): StatementNode {
     switch (p.getToken()) {
-        case Token.Var: {
+        case Token.Fn: {
+            // the nested function cannot be exported
+            return parseFunctionDecl(p, false);
+        }
+        case Token.Var:
+        case Token.Const: {
             return parseVariableDecl(p);
         }
         case Token.If: {
@@ -322,12 +696,24 @@ function parseStatement(p: ParseContext): StatementNode {
         case Token.Loop: {
             return parseLoopStatement(p);
         }
+        case Token.Repeat: {
+            return parseRepeatStatement(p);
+        }
+        case Token.While: {
+            return parseWhileStatement(p);
+        }
+        case Token.For: {
+            return parseStatementStartWithFor(p);
+        }
         case Token.Return: {
             return parseReturnStatement(p);
         }
         case Token.Break: {
             return parseBreakStatement(p);
         }
+        case Token.Continue: {
+            return parseContinueStatement(p);
+        }
         default: {
             return parseStatementStartWithExpr(p);
         }
@@ -337,13 +723,52 @@ function parseStatement(p: ParseContext): StatementNode {
 /**
  * ```text
  * <StatementStartWithExpr>
- *   = <Expr> ("=" / "+=" / "-=" / "*=" / "/=" / "%=") <Expr> ";"
+ *   = <LabeledStatement>
+ *   / <AssignStatement> ";"
  *   / <Expr> ";"
+ *   / <Expr> &"}"
  * ```
 */
 function parseStatementStartWithExpr(p: ParseContext): StatementNode {
     trace.enter('[parse] parseStatementStartWithExpr');
 
+    const statement = parseExprOrAssign(p);
+    if (statement.kind == 'Identifier' && p.tokenIs(Token.Colon)) {
+        // the identifier followed by the colon is the label of the loop
+        p.next();
+        trace.leave();
+        return parseLabeledStatement(p, statement);
+    }
+    if (statement.kind == 'AssignStatement') {
+        p.expectAndNext(Token.Semi);
+        trace.leave();
+        return statement;
+    }
+    switch (p.getToken()) {
+        case Token.Semi: {
+            p.next();
+            trace.leave();
+            return statement;
+        }
+        case Token.EndBrace: {
+            // the end of the block
+            trace.leave();
+            return p.end(createTailExpr(statement.pos, statement));
+        }
+        default: {
+            throw p.unexpected([Token.Semi, Token.EndBrace]);
+        }
+    }
+}
+
+/**
+ * ```text
+ * <AssignStatement> = <Expr> ("=" / "+=" / "-=" / "*=" / "/=" / "%=" / "**=") <Expr>
+ * ```
+*/
+function parseExprOrAssign(p: ParseContext): AssignStatement | ExprNode {
+    trace.enter('[parse] parseExprOrAssign');
+
     const expr = parseExpr(p);
     switch (p.getToken()) {
         case Token.Assign:
@@ -351,7 +776,8 @@ function parseStatementStartWithExpr(p: ParseContext): StatementNode {
         case Token.SubAssign:
         case Token.MultAssign:
         case Token.DivAssign:
-        case Token.ModAssign: {
+        case Token.ModAssign:
+        case Token.PowAssign: {
             const modeToken = p.getToken();
             p.next();
             let mode: AssignMode;
@@ -380,34 +806,35 @@ function parseStatementStartWithExpr(p: ParseContext): StatementNode {
                     mode = '%=';
                     break;
                 }
+                case Token.PowAssign: {
+                    mode = '**=';
+                    break;
+                }
                 default: {
                     throw new UguisuError(`unexpected token: ${Token[p.getToken()]}`);
                 }
             }
             const body = parseExpr(p);
-            p.expectAndNext(Token.Semi);
             trace.leave();
-            return createAssignStatement(expr.pos, expr, body, mode);
+            return p.end(createAssignStatement(expr.pos, expr, body, mode));
         }
-        case Token.Semi: {
-            p.next();
+        default: {
             trace.leave();
             return expr;
         }
-        default: {
-            throw new UguisuError(`unexpected token: ${Token[p.getToken()]}`);
-        }
     }
 }
 
 /**
  * ```text
  * <VariableDecl> = "var" <identifier> <TyLabel>? ("=" <Expr>)? ";"
+ *   / "const" <identifier> <TyLabel>? "=" <Expr> ";"
  * ```
 */
 function parseVariableDecl(p: ParseContext): VariableDecl {
     trace.enter('[parse] parseVariableDecl');
 
+    const isConst = p.tokenIs(Token.Const);
     p.next();
     const pos = p.getPos();
     p.expect(Token.Ident);
@@ -423,16 +850,56 @@ function parseVariableDecl(p: ParseContext): VariableDecl {
     if (p.tokenIs(Token.Assign)) {
         p.next();
         body = parseExpr(p);
+    } else if (isConst) {
+        throw new UguisuError('const declaration requires an initializer.');
     }
     p.expectAndNext(Token.Semi);
 
     trace.leave();
-    return createVariableDecl(pos, name, ty, body);
+    return p.end(createVariableDecl(pos, name, isConst, ty, body));
+}
+
+/**
+ * ```text
+ * <LabeledStatement> = <identifier> ":" (<LoopStatement> / <RepeatStatement> / <WhileStatement> / <ForStatement> / <ForEachFieldStatement>)
+ * ```
+*/
+function parseLabeledStatement(p: ParseContext, label: Identifier): LoopNode {
+    trace.enter('[parse] parseLabeledStatement');
+
+    let statement: LoopNode;
+    switch (p.getToken()) {
+        case Token.Loop: {
+            statement = parseLoopStatement(p);
+            break;
+        }
+        case Token.Repeat: {
+            statement = parseRepeatStatement(p);
+            break;
+        }
+        case Token.While: {
+            statement = parseWhileStatement(p);
+            break;
+        }
+        case Token.For: {
+            statement = parseStatementStartWithFor(p);
+            break;
+        }
+        default: {
+            throw p.unexpected([Token.Loop, Token.Repeat, Token.While, Token.For]);
+        }
+    }
+    // the labeled statement starts at the label
+    statement.label = label.name;
+    statement.pos = label.pos;
+
+    trace.leave();
+    return statement;
 }
 
 /**
  * ```text
- * <BreakStatement> = "break" ";"
+ * <BreakStatement> = "break" <identifier>? ";"
  * ```
 */
 function parseBreakStatement(p: ParseContext): BreakStatement {
@@ -440,10 +907,36 @@ function parseBreakStatement(p: ParseContext): BreakStatement {
 
     const pos = p.getPos();
     p.expectAndNext(Token.Break);
+    let label;
+    if (p.tokenIs(Token.Ident)) {
+        label = p.getIdentValue();
+        p.next();
+    }
+    p.expectAndNext(Token.Semi);
+
+    trace.leave();
+    return p.end(createBreakStatement(pos, label));
+}
+
+/**
+ * ```text
+ * <ContinueStatement> = "continue" <identifier>? ";"
+ * ```
+*/
+function parseContinueStatement(p: ParseContext): ContinueStatement {
+    trace.enter('[parse] parseContinueStatement');
+
+    const pos = p.getPos();
+    p.expectAndNext(Token.Continue);
+    let label;
+    if (p.tokenIs(Token.Ident)) {
+        label = p.getIdentValue();
+        p.next();
+    }
     p.expectAndNext(Token.Semi);
 
     trace.leave();
-    return createBreakStatement(pos);
+    return p.end(createContinueStatement(pos, label));
 }
 
 /**
@@ -463,7 +956,7 @@ function parseReturnStatement(p: ParseContext): ReturnStatement {
     p.expectAndNext(Token.Semi);
 
     trace.leave();
-    return createReturnStatement(pos, expr);
+    return p.end(createReturnStatement(pos, expr));
 }
 
 /**
@@ -491,7 +984,91 @@ function parseIfStatement(p: ParseContext): IfStatement {
     }
 
     trace.leave();
-    return createIfStatement(pos, cond, thenBlock, elseBlock);
+    return p.end(createIfStatement(pos, cond, thenBlock, elseBlock));
+}
+
+/**
+ * ```text
+ * <IfExpr> = "if" <Expr> <Block> "else" (<IfExpr> / <Block>)
+ * ```
+*/
+function parseIfExpr(p: ParseContext): IfExpr {
+    trace.enter('[parse] parseIfExpr');
+
+    const pos = p.getPos();
+    p.next();
+    const cond = parseExpr(p);
+    const thenBlock = parseBlock(p);
+    if (!p.tokenIs(Token.Else)) {
+        throw new UguisuError('if expression requires the else branch');
+    }
+    p.next();
+    let elseBlock: StatementNode[];
+    if (p.tokenIs(Token.If)) {
+        const elseIf = parseIfExpr(p);
+        elseBlock = [createTailExpr(elseIf.pos, elseIf)];
+    } else {
+        elseBlock = parseBlock(p);
+    }
+
+    trace.leave();
+    return p.end(createIfExpr(pos, cond, thenBlock, elseBlock));
+}
+
+/**
+ * ```text
+ * <MatchExpr> = "match" <Expr> "{" <MatchArm> ("," <MatchArm>)* ","? "}"
+ * <MatchArm> = <MatchPattern> "=>" <Expr>
+ * <MatchPattern> = <identifier> / "-"? <NumberLiteral>
+ * ```
+ * The identifier `_` is the wildcard pattern.
+*/
+function parseMatchExpr(p: ParseContext): MatchExpr {
+    trace.enter('[parse] parseMatchExpr');
+
+    const pos = p.getPos();
+    p.next();
+    const target = parseExpr(p);
+    p.expectAndNext(Token.BeginBrace);
+    const arms: MatchArm[] = [];
+    arms.push(parseMatchArm(p));
+    while (p.tokenIs(Token.Comma)) {
+        p.next();
+        if (p.tokenIs(Token.EndBrace)) {
+            break;
+        }
+        arms.push(parseMatchArm(p));
+    }
+    p.expectAndNext(Token.EndBrace);
+
+    trace.leave();
+    return p.end(createMatchExpr(pos, target, arms));
+}
+
+function parseMatchArm(p: ParseContext): MatchArm {
+    const pos = p.getPos();
+    let pattern: MatchPattern;
+    if (p.tokenIs(Token.Ident)) {
+        const name = p.getIdentValue();
+        pattern = (name == '_') ? { kind: 'wildcard' } : { kind: 'variant', name };
+        p.next();
+    } else {
+        let sign = 1;
+        if (p.tokenIs(Token.Minus)) {
+            sign = -1;
+            p.next();
+        }
+        p.expect(Token.Literal);
+        const literal = p.getLiteralValue();
+        if (literal.kind != 'number') {
+            throw p.unexpected(['number literal']);
+        }
+        pattern = { kind: 'number', value: sign * getNumberValue(literal) };
+        p.next();
+    }
+    p.expectAndNext(Token.Arrow);
+    const body = parseExpr(p);
+    return p.end(createMatchArm(pos, pattern, body));
 }
 
 /**
@@ -507,15 +1084,145 @@ function parseLoopStatement(p: ParseContext): LoopStatement {
     const block = parseBlock(p);
 
     trace.leave();
-    return createLoopStatement(pos, block);
+    return p.end(createLoopStatement(pos, block));
+}
+
+/**
+ * ```text
+ * <RepeatStatement> = "repeat" <Expr> <Block>
+ * ```
+*/
+function parseRepeatStatement(p: ParseContext): RepeatStatement {
+    trace.enter('[parse] parseRepeatStatement');
+
+    const pos = p.getPos();
+    p.expectAndNext(Token.Repeat);
+    const count = parseExpr(p);
+    const block = parseBlock(p);
+
+    trace.leave();
+    return p.end(createRepeatStatement(pos, count, block));
+}
+
+/**
+ * ```text
+ * <WhileStatement> = "while" <Expr> <Block>
+ * ```
+*/
+function parseWhileStatement(p: ParseContext): WhileStatement {
+    trace.enter('[parse] parseWhileStatement');
+
+    const pos = p.getPos();
+    p.expectAndNext(Token.While);
+    const cond = parseExpr(p);
+    const block = parseBlock(p);
+
+    trace.leave();
+    return p.end(createWhileStatement(pos, cond, block));
+}
+
+/**
+ * ```text
+ * <StatementStartWithFor> = <ForStatement> / <ForEachFieldStatement>
+ * ```
+*/
+function parseStatementStartWithFor(p: ParseContext): ForStatement | ForEachFieldStatement {
+    trace.enter('[parse] parseStatementStartWithFor');
+
+    const pos = p.getPos();
+    p.expectAndNext(Token.For);
+    let statement;
+    if (p.tokenIs(Token.BeginParen)) {
+        statement = parseForStatement(p, pos);
+    } else {
+        statement = parseForEachFieldStatement(p, pos);
+    }
+
+    trace.leave();
+    return statement;
+}
+
+/**
+ * ```text
+ * <ForStatement> = "for" "(" (<VariableDecl> / <AssignStatement>? ";" / <Expr> ";") <Expr>? ";" (<AssignStatement> / <Expr>)? ")" <Block>
+ * ```
+*/
+function parseForStatement(p: ParseContext, pos: Pos): ForStatement {
+    trace.enter('[parse] parseForStatement');
+
+    p.expectAndNext(Token.BeginParen);
+
+    let init;
+    if (p.tokenIs(Token.Var)) {
+        init = parseVariableDecl(p);
+    } else if (p.tokenIs(Token.Semi)) {
+        p.next();
+    } else {
+        init = parseExprOrAssign(p);
+        p.expectAndNext(Token.Semi);
+    }
+
+    let cond;
+    if (!p.tokenIs(Token.Semi)) {
+        cond = parseExpr(p);
+    }
+    p.expectAndNext(Token.Semi);
+
+    let step;
+    if (!p.tokenIs(Token.EndParen)) {
+        step = parseExprOrAssign(p);
+    }
+    p.expectAndNext(Token.EndParen);
+
+    const block = parseBlock(p);
+
+    trace.leave();
+    return p.end(createForStatement(pos, init, cond, step, block));
+}
+
+/**
+ * ```text
+ * <ForEachFieldStatement> = "for" <identifier> "," <identifier> "in" <Expr> <Block>
+ * ```
+*/
+function parseForEachFieldStatement(p: ParseContext, pos: Pos): ForEachFieldStatement {
+    trace.enter('[parse] parseForEachFieldStatement');
+
+    p.expect(Token.Ident);
+    const nameVar = p.getIdentValue();
+    p.next();
+    p.expectAndNext(Token.Comma);
+    p.expect(Token.Ident);
+    const valueVar = p.getIdentValue();
+    p.next();
+    p.expectAndNext(Token.In);
+    const target = parseExpr(p);
+    const block = parseBlock(p);
+
+    trace.leave();
+    return p.end(createForEachFieldStatement(pos, nameVar, valueVar, target, block));
 }
 
 //#endregion Statements
 
 //#region Expressions
 
+/**
+ * ```text
+ * <Expr> = <Infix> ("?" <Expr> ":" <Expr>)?
+ * ```
+ * The conditional operator has the lowest precedence and is right associative.
+*/
 function parseExpr(p: ParseContext): ExprNode {
-    return parseInfix(p, 0);
+    const expr = parseInfix(p, 0);
+    if (!p.tokenIs(Token.Question)) {
+        return expr;
+    }
+    p.next();
+    const thenExpr = parseExpr(p);
+    p.expectAndNext(Token.Colon);
+    const elseExpr = parseExpr(p);
+    return p.end(createConditionalExpr(expr.pos, expr, thenExpr, elseExpr));
 }
 
 type OpInfo = { prec: number, assoc: 'left' | 'right', op: BinaryOperator };
@@ -540,6 +1247,8 @@ const opTable: Map<Token, OpInfo> = new Map([
     [Token.Asterisk, { prec: 6, assoc: 'left', op: '*' }],
     [Token.Slash, { prec: 6, assoc: 'left', op: '/' }],
     [Token.Percent, { prec: 6, assoc: 'left', op: '%' }],
+    // 7
+    [Token.Asterisk2, { prec: 7, assoc: 'right', op: '**' }],
 ]);
 
 function parseInfix(p: ParseContext, minPrec: number): ExprNode {
@@ -561,7 +1270,7 @@ function parseInfix(p: ParseContext, minPrec: number): ExprNode {
         }
         p.next();
         const rightExpr = parseInfix(p, nextMinPrec);
-        expr = createBinaryOp(pos, info.op, expr, rightExpr);
+        expr = p.end(createBinaryOp(pos, info.op, expr, rightExpr));
     }
     return expr;
 }
@@ -597,22 +1306,28 @@ function parseSuffixChain(p: ParseContext, target: ExprNode): ExprNode {
                 }
             }
             p.expectAndNext(Token.EndParen);
-            return parseSuffixChain(p, createCall(pos, target, args));
+            return parseSuffixChain(p, p.end(createCall(pos, target, args)));
         }
         case Token.Dot: { // field access
             p.next();
             const pos = p.getPos();
-            p.expect(Token.Ident);
-            const name = p.getIdentValue();
+            let name;
+            if (p.tokenIs(Token.Literal) && p.getLiteralValue().kind == 'number') {
+                // the item of the tuple (e.g. `x.0`)
+                name = p.getLiteralValue().value;
+            } else {
+                p.expect(Token.Ident);
+                name = p.getIdentValue();
+            }
             p.next();
-            return parseSuffixChain(p, createFieldAccess(pos, name, target));
+            return parseSuffixChain(p, p.end(createFieldAccess(pos, name, target)));
         }
         case Token.BeginBracket: { // index access
             const pos = p.getPos();
             p.next();
             const index = parseExpr(p);
             p.expectAndNext(Token.EndBracket);
-            return parseSuffixChain(p, createIndexAccess(pos, target, index));
+            return parseSuffixChain(p, p.end(createIndexAccess(pos, target, index)));
         }
         default: {
             return target;
@@ -622,7 +1337,8 @@ function parseSuffixChain(p: ParseContext, target: ExprNode): ExprNode {
 
 /**
  * ```text
- * <AtomInner> = <NumberLiteral> / <BoolLiteral> / <StringLiteral> / <StructExpr> / <Array> / <Identifier> / <Prefix> <Atom> / "(" <Expr> ")"
+ * <AtomInner> = <NumberLiteral> / <FloatLiteral> / <BoolLiteral> / <StringLiteral> / "none" / <StructExpr> / <Array> / <IfExpr> / <MatchExpr> / <LambdaExpr> / <Identifier> / <Prefix> <Atom> / "(" <Expr> ")" / <Tuple>
+ * <Tuple> = "(" <Expr> ("," <Expr>)+ ","? ")"
  * ```
 */
 function parseAtomInner(p: ParseContext): ExprNode {
@@ -630,25 +1346,44 @@ function parseAtomInner(p: ParseContext): ExprNode {
     switch (p.getToken()) {
         case Token.Literal: {
             const literal = p.getLiteralValue();
-            p.next();
             if (literal.kind == 'number') {
-                return createNumberLiteral(pos, parseInt(literal.value));
+                // the error points at the literal
+                const value = getNumberValue(literal);
+                p.next();
+                return p.end(createNumberLiteral(pos, value));
+            }
+            p.next();
+            if (literal.kind == 'float') {
+                return p.end(createFloatLiteral(pos, parseFloat(literal.value)));
             }
             if (literal.kind == 'bool') {
-                return createBoolLiteral(pos, (literal.value == 'true'));
+                return p.end(createBoolLiteral(pos, (literal.value == 'true')));
             }
             if (literal.kind == 'char') {
-                return createCharLiteral(pos, literal.value);
+                return p.end(createCharLiteral(pos, literal.value));
             }
             if (literal.kind == 'string') {
-                return createStringLiteral(pos, literal.value);
+                return p.end(createStringLiteral(pos, literal.value));
             }
             throw new UguisuError('not implemented yet');
         }
         case Token.Ident: {
             const name = p.getIdentValue();
             p.next();
-            return createIdentifier(pos, name);
+            return p.end(createIdentifier(pos, name));
+        }
+        case Token.None: {
+            p.next();
+            return p.end(createNoneLiteral(pos));
+        }
+        case Token.If: {
+            return parseIfExpr(p);
+        }
+        case Token.Match: {
+            return parseMatchExpr(p);
+        }
+        case Token.Fn: {
+            return parseLambdaExpr(p);
         }
         case Token.New: {
             p.next();
@@ -668,7 +1403,7 @@ function parseAtomInner(p: ParseContext): ExprNode {
                 }
             }
             p.expectAndNext(Token.EndBrace);
-            return createStructExpr(pos, name, fields);
+            return p.end(createStructExpr(pos, name, fields));
         }
         case Token.BeginBracket: {
             p.next();
@@ -682,38 +1417,76 @@ function parseAtomInner(p: ParseContext): ExprNode {
                 }
             }
             p.expectAndNext(Token.EndBracket);
-            return createArrayNode(pos, items);
+            return p.end(createArrayNode(pos, items));
         }
         case Token.Not: {
             p.next();
             const expr = parseAtom(p);
-            return createUnaryOp(pos, '!', expr);
+            return p.end(createUnaryOp(pos, '!', expr));
+        }
+        case Token.Minus: {
+            p.next();
+            const expr = parseAtom(p);
+            return p.end(createUnaryOp(pos, '-', expr));
         }
         case Token.BeginParen: {
             p.next();
             const expr = parseExpr(p);
+            if (p.tokenIs(Token.Comma)) {
+                const items = [expr];
+                while (p.tokenIs(Token.Comma)) {
+                    p.next();
+                    if (p.tokenIs(Token.EndParen)) {
+                        break;
+                    }
+                    items.push(parseExpr(p));
+                }
+                p.expectAndNext(Token.EndParen);
+                if (items.length < 2) {
+                    throw new UguisuError('a tuple requires two or more items.');
+                }
+                return p.end(createTupleNode(pos, items));
+            }
             p.expectAndNext(Token.EndParen);
             return expr;
         }
         default: {
-            throw new UguisuError(`unexpected token: ${Token[p.getToken()]}`);
+            throw p.unexpected(['expression']);
         }
     }
 }
 
+/**
+ * Get the value of the number literal. The prefix of the base (e.g. `0x`) is decoded as well.
+ * @throws UguisuError (The value cannot be represented exactly as a number)
+*/
+function getNumberValue(literal: LiteralValue): number {
+    const value = Number(literal.value);
+    if (!Number.isSafeInteger(value)) {
+        throw new UguisuError('integer literal out of range for `number`.');
+    }
+    return value;
+}
+
 /**
  * ```text
- * <StructExprField> = <identifier> ":" <Expr>
+ * <StructExprField> = <identifier> (":" <Expr>)?
  * ```
+ * If the body is omitted, the field is initialized with the variable of the same name.
 */
 function parseStructExprField(p: ParseContext): StructExprField {
     const pos = p.getPos();
     p.expect(Token.Ident);
     const name = p.getIdentValue();
     p.next();
-    p.expectAndNext(Token.Colon);
-    const body = parseExpr(p);
-    return createStructExprField(pos, name, body);
+    let body: ExprNode;
+    if (p.tokenIs(Token.Colon)) {
+        p.next();
+        body = parseExpr(p);
+    } else {
+        body = p.end(createIdentifier(pos, name));
+    }
+    return p.end(createStructExprField(pos, name, body));
 }
 
 //#endregion Expressions
@@ -0,0 +1,53 @@
+import path from 'path';
+import { UguisuError } from '../misc/errors.js';
+import { ProjectInfo } from '../project-file.js';
+import { parse } from './parse.js';
+import { createSourceFile, FileNode, SourceFile } from './tools.js';
+
+/**
+ * Load the imported files and merge their declarations into the source file.
+ * The import path is resolved relative to the importing file.
+ * Each file is loaded only once, and the declarations of the imported files come first.
+*/
+export function resolveImports(source: SourceFile, projectInfo: ProjectInfo, readFile: (filePath: string) => string): SourceFile {
+    const loaded = new Set<string>();
+    const decls: FileNode[] = [];
+    resolveFile(source, [path.resolve(source.filename)], loaded, decls, projectInfo, readFile);
+    return createSourceFile(source.pos, source.filename, source.imports, decls, source.comments);
+}
+
+function resolveFile(
+    source: SourceFile,
+    stack: string[],
+    loaded: Set<string>,
+    decls: FileNode[],
+    projectInfo: ProjectInfo,
+    readFile: (filePath: string) => string,
+) {
+    loaded.add(stack[stack.length - 1]);
+
+    for (const node of source.imports) {
+        const filePath = path.resolve(path.dirname(source.filename), node.path);
+
+        // check for import cycle
+        if (stack.includes(filePath)) {
+            const cycle = [...stack, filePath].map(x => path.basename(x)).join(' -> ');
+            throw new UguisuError(`import cycle detected: ${cycle}`);
+        }
+
+        if (loaded.has(filePath)) {
+            continue;
+        }
+
+        let sourceCode;
+        try {
+            sourceCode = readFile(filePath);
+        } catch (err) {
+            throw new UguisuError(`Failed to load the imported file: ${node.path}`);
+        }
+        const imported = parse(sourceCode, filePath, projectInfo);
+        resolveFile(imported, [...stack, filePath], loaded, decls, projectInfo, readFile);
+    }
+
+    decls.push(...source.decls);
+}
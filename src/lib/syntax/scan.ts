@@ -1,20 +1,34 @@
 import { UguisuError } from '../misc/errors.js';
 import { Trace } from '../misc/trace.js';
+import { Comment } from './tools.js';
 
 const trace = Trace.getDefault().createChild(false);
 
 const space = [' ', '\t', '\r', '\n'];
 const digit = /^[0-9]$/;
 const wordChar = /^[A-Za-z0-9_]$/;
+
+/**
+ * The prefix character (e.g. `x` of `0x`) and the digits of the integer literal with the base.
+*/
+const radixTable = new Map<string, { name: string, digit: RegExp }>([
+    ['x', { name: 'hexadecimal', digit: /^[0-9A-Fa-f]$/ }],
+    ['o', { name: 'octal', digit: /^[0-7]$/ }],
+    ['b', { name: 'binary', digit: /^[01]$/ }],
+]);
 const spCharTable = new Map([
     ['r', '\r'],
     ['n', '\n'],
     ['t', '\t'],
+    ['0', '\0'],
+    ['\'', '\''],
+    ['"', '"'],
+    ['\\', '\\'],
 ]);
 
 export type LiteralValue = { kind: LiteralKind, value: string };
 
-export type LiteralKind = 'none' | 'number' | 'char' | 'string' | 'bool';
+export type LiteralKind = 'none' | 'number' | 'float' | 'char' | 'string' | 'bool';
 
 export enum Token {
     EOF,
@@ -27,6 +41,8 @@ export enum Token {
     Minus,
     /** "*" */
     Asterisk,
+    /** "**" */
+    Asterisk2,
     /** "/" */
     Slash,
     /** "%" */
@@ -49,6 +65,8 @@ export enum Token {
     Comma,
     /** ":" */
     Colon,
+    /** "?" */
+    Question,
     /** ";" */
     Semi,
     /** "=" */
@@ -63,8 +81,12 @@ export enum Token {
     DivAssign,
     /** "%=" */
     ModAssign,
+    /** "**=" */
+    PowAssign,
     /** "==" */
     Eq,
+    /** "=>" */
+    Arrow,
     /** ">" */
     GreaterThan,
     /** ">=" */
@@ -90,8 +112,12 @@ export enum Token {
     Fn,
     /** "var" */
     Var,
+    /** "const" */
+    Const,
     /** "struct" */
     Struct,
+    /** "enum" */
+    Enum,
     /** "new" */
     New,
     /** "return" */
@@ -100,16 +126,112 @@ export enum Token {
     If,
     /** "else" */
     Else,
+    /** "match" */
+    Match,
     /** "loop" */
     Loop,
+    /** "repeat" */
+    Repeat,
+    /** "while" */
+    While,
     /** "break" */
     Break,
+    /** "continue" */
+    Continue,
+    /** "for" */
+    For,
+    /** "in" */
+    In,
+    /** "none" */
+    None,
     /** "import" */
     Import,
     /** "export" */
     Export,
 }
 
+/**
+ * The text of the tokens for the error messages.
+*/
+const tokenTexts = new Map<Token, string>([
+    [Token.Plus, '+'],
+    [Token.Minus, '-'],
+    [Token.Asterisk, '*'],
+    [Token.Asterisk2, '**'],
+    [Token.Slash, '/'],
+    [Token.Percent, '%'],
+    [Token.BeginBrace, '{'],
+    [Token.EndBrace, '}'],
+    [Token.BeginParen, '('],
+    [Token.EndParen, ')'],
+    [Token.BeginBracket, '['],
+    [Token.EndBracket, ']'],
+    [Token.Dot, '.'],
+    [Token.Comma, ','],
+    [Token.Colon, ':'],
+    [Token.Question, '?'],
+    [Token.Semi, ';'],
+    [Token.Assign, '='],
+    [Token.AddAssign, '+='],
+    [Token.SubAssign, '-='],
+    [Token.MultAssign, '*='],
+    [Token.DivAssign, '/='],
+    [Token.ModAssign, '%='],
+    [Token.PowAssign, '**='],
+    [Token.Eq, '=='],
+    [Token.Arrow, '=>'],
+    [Token.GreaterThan, '>'],
+    [Token.GreaterThanEq, '>='],
+    [Token.LessThan, '<'],
+    [Token.LessThanEq, '<='],
+    [Token.Not, '!'],
+    [Token.NotEq, '!='],
+    [Token.Or, '|'],
+    [Token.And, '&'],
+    [Token.Or2, '||'],
+    [Token.And2, '&&'],
+    [Token.Fn, 'fn'],
+    [Token.Var, 'var'],
+    [Token.Const, 'const'],
+    [Token.Struct, 'struct'],
+    [Token.Enum, 'enum'],
+    [Token.New, 'new'],
+    [Token.Return, 'return'],
+    [Token.If, 'if'],
+    [Token.Else, 'else'],
+    [Token.Match, 'match'],
+    [Token.Loop, 'loop'],
+    [Token.Repeat, 'repeat'],
+    [Token.While, 'while'],
+    [Token.Break, 'break'],
+    [Token.Continue, 'continue'],
+    [Token.For, 'for'],
+    [Token.In, 'in'],
+    [Token.None, 'none'],
+    [Token.Import, 'import'],
+    [Token.Export, 'export'],
+]);
+
+/**
+ * Describe the token for the error messages (e.g. `` `(` ``, `identifier`).
+*/
+export function describeToken(token: Token): string {
+    switch (token) {
+        case Token.EOF: {
+            return 'end of file';
+        }
+        case Token.Ident: {
+            return 'identifier';
+        }
+        case Token.Literal: {
+            return 'literal';
+        }
+        default: {
+            return `\`${tokenTexts.get(token)}\``;
+        }
+    }
+}
+
 export class Scanner {
     private sourceCode: string;
     private index: number;
@@ -117,10 +239,13 @@ export class Scanner {
     private column: number;
     private tokenLine: number;
     private tokenColumn: number;
+    private lastTokenEndLine: number;
+    private lastTokenEndColumn: number;
     private ch: string | null;
     private token: Token;
     private tokenValue: string;
     private literalKind: LiteralKind;
+    private comments: Comment[];
 
     constructor() {
         this.sourceCode = '';
@@ -129,10 +254,13 @@ export class Scanner {
         this.column = 0;
         this.tokenLine = 0;
         this.tokenColumn = 0;
+        this.lastTokenEndLine = 0;
+        this.lastTokenEndColumn = 0;
         this.ch = null;
         this.token = Token.EOF;
         this.tokenValue = '';
         this.literalKind = 'none';
+        this.comments = [];
     }
 
     setup(sourceCode: string) {
@@ -142,6 +270,9 @@ export class Scanner {
         this.column = 0;
         this.tokenLine = 0;
         this.tokenColumn = 0;
+        this.lastTokenEndLine = 0;
+        this.lastTokenEndColumn = 0;
+        this.comments = [];
         if (this.isEof()) {
             return;
         }
@@ -152,6 +283,13 @@ export class Scanner {
         return [this.tokenLine + 1, this.tokenColumn + 1];
     }
 
+    /**
+     * Get the position just after the token before the current token.
+    */
+    getLastTokenEndPos(): [number, number] {
+        return [this.lastTokenEndLine + 1, this.lastTokenEndColumn + 1];
+    }
+
     getToken() {
         return this.token;
     }
@@ -164,6 +302,13 @@ export class Scanner {
         return this.tokenValue;
     }
 
+    /**
+     * Get the comments read so far in the order of appearance.
+    */
+    getComments(): Comment[] {
+        return this.comments;
+    }
+
     private isEof(): boolean {
         return this.index >= this.sourceCode.length;
     }
@@ -202,6 +347,9 @@ export class Scanner {
     */
     next() {
         trace.enter(`[scan] read`);
+        // the current position is just after the token
+        this.lastTokenEndLine = this.line;
+        this.lastTokenEndColumn = this.column;
         while (true) {
             if (this.ch == null) {
                 this.token = Token.EOF;
@@ -254,6 +402,15 @@ export class Scanner {
                     if (this.ch == '=') {
                         this.nextChar();
                         this.token = Token.MultAssign;
+                    } else if (this.ch == '*') {
+                        this.nextChar();
+                        // @ts-ignore
+                        if (this.ch == '=') {
+                            this.nextChar();
+                            this.token = Token.PowAssign;
+                        } else {
+                            this.token = Token.Asterisk2;
+                        }
                     } else {
                         this.token = Token.Asterisk;
                     }
@@ -267,11 +424,11 @@ export class Scanner {
                         this.token = Token.DivAssign;
                     } else if (this.ch == '/') {
                         this.nextChar();
-                        this.skipCommentLine();
+                        this.readCommentLine();
                         continue;
                     } else if (this.ch == '*') {
                         this.nextChar();
-                        this.skipCommentRange();
+                        this.readCommentRange();
                         continue;
                     } else {
                         this.token = Token.Slash;
@@ -334,6 +491,11 @@ export class Scanner {
                     this.nextChar();
                     break;
                 }
+                case '?': {
+                    this.token = Token.Question;
+                    this.nextChar();
+                    break;
+                }
                 case ';': {
                     this.token = Token.Semi;
                     this.nextChar();
@@ -344,6 +506,10 @@ export class Scanner {
                     if (this.ch == '=') {
                         this.token = Token.Eq;
                         this.nextChar();
+                    // @ts-ignore
+                    } else if (this.ch == '>') {
+                        this.token = Token.Arrow;
+                        this.nextChar();
                     } else {
                         this.token = Token.Assign;
                     }
@@ -420,19 +586,99 @@ export class Scanner {
     }
 
     private readDigits() {
+        // the integer with the base prefix
+        const prefixCh = this.sourceCode[this.index + 1];
+        if (this.ch == '0' && prefixCh != null && radixTable.has(prefixCh)) {
+            this.readRadixDigits();
+            return;
+        }
         let buf = '';
         while (true) {
+            if (this.skipDigitSeparator(buf, digit)) {
+                continue;
+            }
             if (this.ch == null || !digit.test(this.ch)) {
                 break;
             }
             buf += this.ch;
             this.nextChar();
         }
+        // fraction part
+        // not read just after the dot, so that `x.0.1` is read as the accesses to the tuple items
+        const nextCh = this.sourceCode[this.index + 1];
+        const isAfterDot = (this.token == Token.Dot);
+        if (!isAfterDot && this.ch == '.' && nextCh != null && digit.test(nextCh)) {
+            buf += this.ch;
+            this.nextChar();
+            while (true) {
+                if (this.skipDigitSeparator(buf, digit)) {
+                    continue;
+                }
+                if (this.ch == null || !digit.test(this.ch)) {
+                    break;
+                }
+                buf += this.ch;
+                this.nextChar();
+            }
+            this.token = Token.Literal;
+            this.tokenValue = buf;
+            this.literalKind = 'float';
+            return;
+        }
         this.token = Token.Literal;
         this.tokenValue = buf;
         this.literalKind = 'number';
     }
 
+    /**
+     * Skip the digit separator `_` (e.g. `1_000`). The separator is not included in the token value.
+     * @returns whether the separator is skipped
+    */
+    private skipDigitSeparator(digits: string, isDigit: RegExp): boolean {
+        if (this.ch != '_') {
+            return false;
+        }
+        // the separator must be placed between the digits
+        const nextCh = this.sourceCode[this.index + 1];
+        if (digits.length == 0 || nextCh == null || !isDigit.test(nextCh)) {
+            throw new UguisuError('the digit separator `_` must be placed between digits.');
+        }
+        this.nextChar();
+        return true;
+    }
+
+    /**
+     * Read the integer literal with the base prefix (`0x`, `0o` or `0b`). The token value keeps the prefix.
+    */
+    private readRadixDigits() {
+        let buf = this.ch!;
+        this.nextChar();
+        const prefixCh = this.ch!;
+        const radix = radixTable.get(prefixCh)!;
+        buf += prefixCh;
+        this.nextChar();
+        let digits = '';
+        while (true) {
+            if (this.skipDigitSeparator(digits, radix.digit)) {
+                continue;
+            }
+            if (this.ch == null || !wordChar.test(this.ch)) {
+                break;
+            }
+            if (!radix.digit.test(this.ch)) {
+                throw new UguisuError(`invalid digit \`${this.ch}\` in the ${radix.name} literal.`);
+            }
+            digits += this.ch;
+            this.nextChar();
+        }
+        if (digits.length == 0) {
+            throw new UguisuError(`the ${radix.name} literal has no digits.`);
+        }
+        this.token = Token.Literal;
+        this.tokenValue = buf + digits;
+        this.literalKind = 'number';
+    }
+
     private readWord() {
         let buf = '';
         while (true) {
@@ -447,6 +693,10 @@ export class Scanner {
                 this.token = Token.Var;
                 break;
             }
+            case 'const': {
+                this.token = Token.Const;
+                break;
+            }
             case 'fn': {
                 this.token = Token.Fn;
                 break;
@@ -455,6 +705,10 @@ export class Scanner {
                 this.token = Token.Struct;
                 break;
             }
+            case 'enum': {
+                this.token = Token.Enum;
+                break;
+            }
             case 'new': {
                 this.token = Token.New;
                 break;
@@ -471,14 +725,42 @@ export class Scanner {
                 this.token = Token.Else;
                 break;
             }
+            case 'match': {
+                this.token = Token.Match;
+                break;
+            }
             case 'loop': {
                 this.token = Token.Loop;
                 break;
             }
+            case 'repeat': {
+                this.token = Token.Repeat;
+                break;
+            }
+            case 'while': {
+                this.token = Token.While;
+                break;
+            }
             case 'break': {
                 this.token = Token.Break;
                 break;
             }
+            case 'continue': {
+                this.token = Token.Continue;
+                break;
+            }
+            case 'for': {
+                this.token = Token.For;
+                break;
+            }
+            case 'in': {
+                this.token = Token.In;
+                break;
+            }
+            case 'none': {
+                this.token = Token.None;
+                break;
+            }
             case 'true': {
                 this.token = Token.Literal;
                 this.literalKind = 'bool';
@@ -526,9 +808,13 @@ export class Scanner {
                     throw new UguisuError('unexpected EOF');
                 }
                 this.nextChar();
+                if (c == 'u') {
+                    buf += this.readUnicodeEscape();
+                    continue;
+                }
                 const sc = spCharTable.get(c);
                 if (sc == null) {
-                    throw new UguisuError('invalid special character');
+                    throw new UguisuError(`unknown escape sequence \`\\${c}\`.`);
                 }
                 buf += sc;
                 continue;
@@ -541,20 +827,57 @@ export class Scanner {
         this.literalKind = kind;
     }
 
-    private skipCommentLine() {
+    /**
+     * Read the unicode escape sequence (e.g. `\u{3042}`) after `\u`.
+    */
+    private readUnicodeEscape(): string {
+        if (this.ch != '{') {
+            throw new UguisuError('unknown escape sequence `\\u`.');
+        }
+        this.nextChar();
+        let digits = '';
+        while (this.ch != null && this.ch != '}') {
+            digits += this.ch;
+            this.nextChar();
+        }
+        if (this.ch == null) {
+            throw new UguisuError('unexpected EOF');
+        }
+        this.nextChar();
+        const code = /^[0-9a-fA-F]{1,6}$/.test(digits) ? parseInt(digits, 16) : NaN;
+        if (isNaN(code) || code > 0x10FFFF || (code >= 0xD800 && code <= 0xDFFF)) {
+            throw new UguisuError(`invalid unicode escape sequence \`\\u{${digits}}\`.`);
+        }
+        return String.fromCodePoint(code);
+    }
+
+    /**
+     * Read the line comment after `//`. The comment is kept for the formatter.
+    */
+    private readCommentLine() {
+        const start = this.index - 2;
+        let end = this.index;
         while (true) {
             if (this.ch == null) {
+                end = this.index;
                 break;
             }
             if (this.ch == '\n') {
+                end = this.index;
                 this.nextChar();
                 break;
             }
             this.nextChar();
         }
+        const text = this.sourceCode.slice(start, end).replace(/\r$/, '');
+        this.comments.push({ pos: this.getPos(), text });
     }
 
-    private skipCommentRange() {
+    /**
+     * Read the range comment after `/*`. The comment is kept for the formatter.
+    */
+    private readCommentRange() {
+        const start = this.index - 2;
         while (true) {
             if (this.ch == null) {
                 break;
@@ -570,5 +893,6 @@ export class Scanner {
             }
             this.nextChar();
         }
+        this.comments.push({ pos: this.getPos(), text: this.sourceCode.slice(start, this.index) });
     }
 }
@@ -2,22 +2,32 @@ export type Pos = [number, number];
 
 export type AstNode
     = SourceFile
+    | ImportDecl
     | FileNode
     | StatementNode
-    | TyLabel
+    | TyLabelNode
     | FnDeclParam
     | StructDeclField
-    | StructExprField;
+    | StructExprField
+    | EnumDeclVariant
+    | MatchArm;
 
-export type FileNode = FunctionDecl | StructDecl;
+export type FileNode = FunctionDecl | StructDecl | EnumDecl | VariableDecl;
 
 export type StatementCoreNode
-    = VariableDecl
+    = FunctionDecl
+    | VariableDecl
     | AssignStatement
     | IfStatement
     | LoopStatement
+    | RepeatStatement
+    | WhileStatement
+    | ForStatement
+    | ForEachFieldStatement
     | ReturnStatement
-    | BreakStatement;
+    | BreakStatement
+    | ContinueStatement
+    | TailExpr;
 
 export type StatementNode
     = StatementCoreNode
@@ -25,9 +35,11 @@ export type StatementNode
 
 export type ExprNode
     = NumberLiteral
+    | FloatLiteral
     | BoolLiteral
     | CharLiteral
     | StringLiteral
+    | NoneLiteral
     | BinaryOp
     | UnaryOp
     | Identifier
@@ -35,7 +47,12 @@ export type ExprNode
     | StructExpr
     | FieldAccess
     | ArrayNode
-    | IndexAccess;
+    | IndexAccess
+    | TupleNode
+    | IfExpr
+    | MatchExpr
+    | ConditionalExpr
+    | LambdaExpr;
 
 export type ReferenceExpr
     = Identifier
@@ -44,36 +61,56 @@ export type ReferenceExpr
 
 export type NodeOf<T extends AstNode['kind']>
     = T extends 'SourceFile' ? SourceFile
+    : T extends 'ImportDecl' ? ImportDecl
     : T extends 'FunctionDecl' ? FunctionDecl
     : T extends 'FnDeclParam' ? FnDeclParam
     : T extends 'IfStatement' ? IfStatement
+    : T extends 'IfExpr' ? IfExpr
+    : T extends 'MatchExpr' ? MatchExpr
+    : T extends 'ConditionalExpr' ? ConditionalExpr
+    : T extends 'LambdaExpr' ? LambdaExpr
+    : T extends 'MatchArm' ? MatchArm
     : T extends 'Identifier' ? Identifier
     : T extends 'NumberLiteral' ? NumberLiteral
+    : T extends 'FloatLiteral' ? FloatLiteral
     : T extends 'BoolLiteral' ? BoolLiteral
     : T extends 'CharLiteral' ? CharLiteral
     : T extends 'StringLiteral' ? StringLiteral
+    : T extends 'NoneLiteral' ? NoneLiteral
     : T extends 'UnaryOp' ? UnaryOp
     : T extends 'BinaryOp' ? BinaryOp
     : T extends 'Call' ? Call
     : T extends 'TyLabel' ? TyLabel
+    : T extends 'FunctionTyLabel' ? FunctionTyLabel
+    : T extends 'TupleTyLabel' ? TupleTyLabel
+    : T extends 'OptionalTyLabel' ? OptionalTyLabel
     : T extends 'BreakStatement' ? BreakStatement
     : T extends 'ContinueStatement' ? ContinueStatement
     : T extends 'ReturnStatement' ? ReturnStatement
+    : T extends 'TailExpr' ? TailExpr
     : T extends 'LoopStatement' ? LoopStatement
+    : T extends 'RepeatStatement' ? RepeatStatement
+    : T extends 'WhileStatement' ? WhileStatement
+    : T extends 'ForStatement' ? ForStatement
+    : T extends 'ForEachFieldStatement' ? ForEachFieldStatement
     : T extends 'AssignStatement' ? AssignStatement
     : T extends 'VariableDecl' ? VariableDecl
     : T extends 'StructDeclField' ? StructDeclField
     : T extends 'StructExprField' ? StructExprField
     : T extends 'StructDecl' ? StructDecl
     : T extends 'StructExpr' ? StructExpr
+    : T extends 'EnumDecl' ? EnumDecl
+    : T extends 'EnumDeclVariant' ? EnumDeclVariant
     : T extends 'FieldAccess' ? FieldAccess
     : T extends 'ArrayNode' ? ArrayNode
     : T extends 'IndexAccess' ? IndexAccess
+    : T extends 'TupleNode' ? TupleNode
     : never;
 
 const exprNodeKind: AstNode['kind'][] = [
-    'NumberLiteral', 'BoolLiteral', 'CharLiteral', 'StringLiteral', 'BinaryOp', 'UnaryOp', 'Identifier', 'Call', 'StructExpr',
-    'FieldAccess', 'ArrayNode', 'IndexAccess',
+    'NumberLiteral', 'FloatLiteral', 'BoolLiteral', 'CharLiteral', 'StringLiteral', 'BinaryOp', 'UnaryOp', 'Identifier', 'Call', 'StructExpr',
+    'FieldAccess', 'ArrayNode', 'IndexAccess', 'TupleNode', 'IfExpr', 'MatchExpr',
+    'ConditionalExpr', 'NoneLiteral', 'LambdaExpr',
 ];
 export function isExprNode(node: AstNode): node is ExprNode {
     return exprNodeKind.includes(node.kind);
@@ -82,20 +119,44 @@ export function isExprNode(node: AstNode): node is ExprNode {
 export type SourceFile = {
     kind: 'SourceFile',
     pos: Pos;
+    end?: Pos;
     filename: string;
+    imports: ImportDecl[],
     decls: FileNode[],
+    /** the comments in the order of appearance */
+    comments: Comment[],
+};
+export function createSourceFile(pos: Pos, filename: string, imports: ImportDecl[], decls: FileNode[], comments: Comment[]): SourceFile {
+    return { kind: 'SourceFile', pos, filename, imports, decls, comments };
+}
+
+/**
+ * The comment in the source code. It is not a node of the AST, and is kept for the formatter.
+*/
+export type Comment = {
+    pos: Pos,
+    /** the text including the delimiters (e.g. `// comment`) */
+    text: string,
+};
+
+export type ImportDecl = {
+    kind: 'ImportDecl',
+    pos: Pos;
+    end?: Pos;
+    path: string;
 };
-export function createSourceFile(pos: Pos, filename: string, decls: FileNode[]): SourceFile {
-    return { kind: 'SourceFile', pos, filename, decls };
+export function createImportDecl(pos: Pos, path: string): ImportDecl {
+    return { kind: 'ImportDecl', pos, path };
 }
 
 export type FunctionDecl = {
     kind: 'FunctionDecl',
     pos: Pos;
+    end?: Pos;
     name: string,
     params: FnDeclParam[],
     body: StatementNode[],
-    returnTy?: TyLabel,
+    returnTy?: TyLabelNode,
     exported: boolean,
 };
 export function createFunctionDecl(
@@ -103,25 +164,50 @@ export function createFunctionDecl(
     name: string,
     params: FnDeclParam[],
     body: StatementNode[],
-    returnTy: TyLabel | undefined,
+    returnTy: TyLabelNode | undefined,
     exported: boolean,
 ): FunctionDecl {
     return { kind: 'FunctionDecl', pos, name, params, body, returnTy, exported };
 }
 
+/**
+ * The function written as an expression (e.g. `fn(x: number): number { x + offset }`).
+ * The local variables used in the body are captured by value when the expression is evaluated.
+*/
+export type LambdaExpr = {
+    kind: 'LambdaExpr',
+    pos: Pos;
+    end?: Pos;
+    params: FnDeclParam[],
+    body: StatementNode[],
+    returnTy?: TyLabelNode,
+};
+export function createLambdaExpr(
+    pos: Pos,
+    params: FnDeclParam[],
+    body: StatementNode[],
+    returnTy: TyLabelNode | undefined,
+): LambdaExpr {
+    return { kind: 'LambdaExpr', pos, params, body, returnTy };
+}
+
 export type FnDeclParam = {
     kind: 'FnDeclParam',
     pos: Pos;
+    end?: Pos;
     name: string;
-    ty?: TyLabel;
+    ty?: TyLabelNode;
+    /** the value used when the argument is omitted */
+    defaultValue?: ExprNode;
 };
-export function createFnDeclParam(pos: Pos, name: string, ty?: TyLabel): FnDeclParam {
-    return { kind: 'FnDeclParam', pos, name, ty };
+export function createFnDeclParam(pos: Pos, name: string, ty?: TyLabelNode, defaultValue?: ExprNode): FnDeclParam {
+    return { kind: 'FnDeclParam', pos, name, ty, defaultValue };
 }
 
 export type IfStatement = {
     kind: 'IfStatement',
     pos: Pos;
+    end?: Pos;
     cond: ExprNode;
     thenBlock: StatementNode[];
     elseBlock: StatementNode[];
@@ -135,9 +221,78 @@ export function createIfStatement(
     return { kind: 'IfStatement', pos, cond, thenBlock, elseBlock };
 }
 
+/**
+ * The if used as an expression. The else branch is required.
+*/
+export type IfExpr = {
+    kind: 'IfExpr',
+    pos: Pos;
+    end?: Pos;
+    cond: ExprNode;
+    thenBlock: StatementNode[];
+    elseBlock: StatementNode[];
+};
+export function createIfExpr(
+    pos: Pos,
+    cond: ExprNode,
+    thenBlock: StatementNode[],
+    elseBlock: StatementNode[],
+): IfExpr {
+    return { kind: 'IfExpr', pos, cond, thenBlock, elseBlock };
+}
+
+/**
+ * The conditional operator (e.g. `x > 0 ? x : -x`). Only the selected branch is evaluated.
+*/
+export type ConditionalExpr = {
+    kind: 'ConditionalExpr',
+    pos: Pos;
+    end?: Pos;
+    cond: ExprNode;
+    thenExpr: ExprNode;
+    elseExpr: ExprNode;
+};
+export function createConditionalExpr(pos: Pos, cond: ExprNode, thenExpr: ExprNode, elseExpr: ExprNode): ConditionalExpr {
+    return { kind: 'ConditionalExpr', pos, cond, thenExpr, elseExpr };
+}
+
+/**
+ * The match expression selects the arm whose pattern matches the target value.
+*/
+export type MatchExpr = {
+    kind: 'MatchExpr',
+    pos: Pos;
+    end?: Pos;
+    target: ExprNode;
+    arms: MatchArm[];
+};
+export function createMatchExpr(pos: Pos, target: ExprNode, arms: MatchArm[]): MatchExpr {
+    return { kind: 'MatchExpr', pos, target, arms };
+}
+
+export type MatchArm = {
+    kind: 'MatchArm',
+    pos: Pos;
+    end?: Pos;
+    pattern: MatchPattern;
+    body: ExprNode;
+};
+export function createMatchArm(pos: Pos, pattern: MatchPattern, body: ExprNode): MatchArm {
+    return { kind: 'MatchArm', pos, pattern, body };
+}
+
+/**
+ * The variant name of the enum (e.g. `Red`), the number (e.g. `1`) or the wildcard `_` that matches any value.
+*/
+export type MatchPattern
+    = { kind: 'variant', name: string }
+    | { kind: 'number', value: number }
+    | { kind: 'wildcard' };
+
 export type Identifier = {
     kind: 'Identifier',
     pos: Pos;
+    end?: Pos;
     name: string,
 };
 export function createIdentifier(pos: Pos, name: string): Identifier {
@@ -147,24 +302,49 @@ export function createIdentifier(pos: Pos, name: string): Identifier {
 export type NumberLiteral = {
     kind: 'NumberLiteral',
     pos: Pos;
+    end?: Pos;
     value: number,
 };
 export function createNumberLiteral(pos: Pos, value: number): NumberLiteral {
     return { kind: 'NumberLiteral', pos, value };
 }
 
+export type FloatLiteral = {
+    kind: 'FloatLiteral',
+    pos: Pos;
+    end?: Pos;
+    value: number,
+};
+export function createFloatLiteral(pos: Pos, value: number): FloatLiteral {
+    return { kind: 'FloatLiteral', pos, value };
+}
+
 export type BoolLiteral = {
     kind: 'BoolLiteral',
     pos: Pos;
+    end?: Pos;
     value: boolean,
 };
 export function createBoolLiteral(pos: Pos, value: boolean): BoolLiteral {
     return { kind: 'BoolLiteral', pos, value };
 }
 
+/**
+ * The `none` literal, the optional value that has no value.
+*/
+export type NoneLiteral = {
+    kind: 'NoneLiteral',
+    pos: Pos;
+    end?: Pos;
+};
+export function createNoneLiteral(pos: Pos): NoneLiteral {
+    return { kind: 'NoneLiteral', pos };
+}
+
 export type CharLiteral = {
     kind: 'CharLiteral',
     pos: Pos;
+    end?: Pos;
     value: string,
 };
 export function createCharLiteral(pos: Pos, value: string): CharLiteral {
@@ -174,6 +354,7 @@ export function createCharLiteral(pos: Pos, value: string): CharLiteral {
 export type StringLiteral = {
     kind: 'StringLiteral',
     pos: Pos;
+    end?: Pos;
     value: string,
 };
 export function createStringLiteral(pos: Pos, value: string): StringLiteral {
@@ -183,18 +364,25 @@ export function createStringLiteral(pos: Pos, value: string): StringLiteral {
 export type UnaryOp = {
     kind: 'UnaryOp',
     pos: Pos;
+    end?: Pos;
     operator: UnaryOperator,
     expr: ExprNode,
 };
 export function createUnaryOp(pos: Pos, operator: UnaryOperator, expr: ExprNode): UnaryOp {
     return { kind: 'UnaryOp', pos, operator, expr };
 }
-export type UnaryOperator = LogicalUnaryOperator;
+export type UnaryOperator = LogicalUnaryOperator | ArithmeticUnaryOperator;
 export type LogicalUnaryOperator = '!';
+export type ArithmeticUnaryOperator = '-';
+
+export function isArithmeticUnaryOperator(x: UnaryOperator): x is ArithmeticUnaryOperator {
+    return (x == '-');
+}
 
 export type BinaryOp = {
     kind: 'BinaryOp',
     pos: Pos;
+    end?: Pos;
     operator: BinaryOperator,
     left: ExprNode,
     right: ExprNode,
@@ -232,8 +420,8 @@ export function isOrderingOperator(x: BinaryOperator): x is OrderingOperator {
     return orderingOperators.includes(x);
 }
 
-export type ArithmeticOperator = '+' | '-' | '*' | '/' | '%';
-const arithmeticOperators: BinaryOperator[] = ['+', '-', '*', '/', '%'];
+export type ArithmeticOperator = '+' | '-' | '*' | '/' | '%' | '**';
+const arithmeticOperators: BinaryOperator[] = ['+', '-', '*', '/', '%', '**'];
 
 export function isArithmeticOperator(x: BinaryOperator): x is ArithmeticOperator {
     return arithmeticOperators.includes(x);
@@ -242,6 +430,7 @@ export function isArithmeticOperator(x: BinaryOperator): x is ArithmeticOperator
 export type Call = {
     kind: 'Call',
     pos: Pos;
+    end?: Pos;
     callee: ExprNode,
     args: ExprNode[],
 };
@@ -252,51 +441,197 @@ export function createCall(pos: Pos, callee: ExprNode, args: ExprNode[]): Call {
 export type TyLabel = {
     kind: 'TyLabel',
     pos: Pos;
+    end?: Pos;
     name: string,
 };
 export function createTyLabel(pos: Pos, name: string): TyLabel {
     return { kind: 'TyLabel', pos, name };
 }
 
+/**
+ * The type label of the function (e.g. `(number, number) => bool`).
+*/
+export type FunctionTyLabel = {
+    kind: 'FunctionTyLabel',
+    pos: Pos;
+    end?: Pos;
+    params: TyLabelNode[],
+    returnTy: TyLabelNode,
+};
+export function createFunctionTyLabel(pos: Pos, params: TyLabelNode[], returnTy: TyLabelNode): FunctionTyLabel {
+    return { kind: 'FunctionTyLabel', pos, params, returnTy };
+}
+
+/**
+ * The type label of the tuple (e.g. `(number, bool)`).
+*/
+export type TupleTyLabel = {
+    kind: 'TupleTyLabel',
+    pos: Pos;
+    end?: Pos;
+    items: TyLabelNode[],
+};
+export function createTupleTyLabel(pos: Pos, items: TyLabelNode[]): TupleTyLabel {
+    return { kind: 'TupleTyLabel', pos, items };
+}
+
+/**
+ * The type label of the optional (e.g. `number?`).
+*/
+export type OptionalTyLabel = {
+    kind: 'OptionalTyLabel',
+    pos: Pos;
+    end?: Pos;
+    innerTy: TyLabelNode,
+};
+export function createOptionalTyLabel(pos: Pos, innerTy: TyLabelNode): OptionalTyLabel {
+    return { kind: 'OptionalTyLabel', pos, innerTy };
+}
+
+export type TyLabelNode = TyLabel | FunctionTyLabel | TupleTyLabel | OptionalTyLabel;
+
+/**
+ * The label specifies the loop to exit. Without the label, the innermost loop is exited.
+*/
 export type BreakStatement = {
     kind: 'BreakStatement',
     pos: Pos,
+    end?: Pos,
+    label?: string,
 };
-export function createBreakStatement(pos: Pos): BreakStatement {
-    return { kind: 'BreakStatement', pos };
+export function createBreakStatement(pos: Pos, label?: string): BreakStatement {
+    return { kind: 'BreakStatement', pos, label };
 }
 
+/**
+ * The label specifies the loop to continue. Without the label, the innermost loop is continued.
+*/
 export type ContinueStatement = {
     kind: 'ContinueStatement',
     pos: Pos,
+    end?: Pos,
+    label?: string,
 };
-export function createContinueStatement(pos: Pos): ContinueStatement {
-    return { kind: 'ContinueStatement', pos };
+export function createContinueStatement(pos: Pos, label?: string): ContinueStatement {
+    return { kind: 'ContinueStatement', pos, label };
 }
 
 export type ReturnStatement = {
     kind: 'ReturnStatement',
     pos: Pos,
+    end?: Pos,
     expr?: ExprNode,
 };
 export function createReturnStatement(pos: Pos, expr?: ExprNode): ReturnStatement {
     return { kind: 'ReturnStatement', pos, expr };
 }
 
+/**
+ * The expression at the end of a block without a semicolon. The block yields the value of the expression.
+*/
+export type TailExpr = {
+    kind: 'TailExpr',
+    pos: Pos,
+    end?: Pos,
+    expr: ExprNode,
+};
+export function createTailExpr(pos: Pos, expr: ExprNode): TailExpr {
+    return { kind: 'TailExpr', pos, expr };
+}
+
+/**
+ * The statements that can be exited by break. They can have the label (e.g. `outer: loop { }`).
+*/
+export type LoopNode = LoopStatement | RepeatStatement | WhileStatement | ForStatement | ForEachFieldStatement;
+
 export type LoopStatement = {
     kind: 'LoopStatement',
     pos: Pos,
+    end?: Pos,
+    label?: string,
     block: StatementNode[],
 };
 export function createLoopStatement(pos: Pos, block: StatementNode[]): LoopStatement {
     return { kind: 'LoopStatement', pos, block };
 }
 
-export type AssignMode = '=' | '+=' | '-=' | '*=' | '/=' | '%=';
+export type RepeatStatement = {
+    kind: 'RepeatStatement',
+    pos: Pos,
+    end?: Pos,
+    label?: string,
+    count: ExprNode,
+    block: StatementNode[],
+};
+export function createRepeatStatement(pos: Pos, count: ExprNode, block: StatementNode[]): RepeatStatement {
+    return { kind: 'RepeatStatement', pos, count, block };
+}
+
+export type WhileStatement = {
+    kind: 'WhileStatement',
+    pos: Pos,
+    end?: Pos,
+    label?: string,
+    cond: ExprNode,
+    block: StatementNode[],
+};
+export function createWhileStatement(pos: Pos, cond: ExprNode, block: StatementNode[]): WhileStatement {
+    return { kind: 'WhileStatement', pos, cond, block };
+}
+
+export type ForStatement = {
+    kind: 'ForStatement',
+    pos: Pos,
+    end?: Pos,
+    label?: string,
+    init?: VariableDecl | AssignStatement | ExprNode,
+    cond?: ExprNode,
+    step?: AssignStatement | ExprNode,
+    block: StatementNode[],
+};
+export function createForStatement(
+    pos: Pos,
+    init: VariableDecl | AssignStatement | ExprNode | undefined,
+    cond: ExprNode | undefined,
+    step: AssignStatement | ExprNode | undefined,
+    block: StatementNode[],
+): ForStatement {
+    return { kind: 'ForStatement', pos, init, cond, step, block };
+}
+
+export type ForEachFieldStatement = {
+    kind: 'ForEachFieldStatement',
+    pos: Pos,
+    end?: Pos,
+    label?: string,
+    nameVar: string,
+    valueVar: string,
+    target: ExprNode,
+    block: StatementNode[],
+    /**
+     * The block for each field, lowered by the analyzer.
+     * It declares the name and the value of the field, which is accessed through `fieldIterationTarget`.
+    */
+    lowered?: StatementNode[][],
+};
+/** the name of the hidden variable that holds the target of the field iteration */
+export const fieldIterationTarget = '@target';
+export function createForEachFieldStatement(
+    pos: Pos,
+    nameVar: string,
+    valueVar: string,
+    target: ExprNode,
+    block: StatementNode[],
+): ForEachFieldStatement {
+    return { kind: 'ForEachFieldStatement', pos, nameVar, valueVar, target, block };
+}
+
+export type AssignMode = '=' | '+=' | '-=' | '*=' | '/=' | '%=' | '**=';
 
 export type AssignStatement = {
     kind: 'AssignStatement',
     pos: Pos,
+    end?: Pos,
     target: ExprNode,
     body: ExprNode,
     mode: AssignMode,
@@ -313,12 +648,15 @@ export function createAssignStatement(
 export type VariableDecl = {
     kind: 'VariableDecl',
     pos: Pos,
+    end?: Pos,
     name: string,
-    ty?: TyLabel,
+    ty?: TyLabelNode,
     body?: ExprNode,
+    /** declared with `const` */
+    isConst: boolean,
 };
-export function createVariableDecl(pos: Pos, name: string, ty?: TyLabel, body?: ExprNode): VariableDecl {
-    return { kind: 'VariableDecl', pos, name, ty, body };
+export function createVariableDecl(pos: Pos, name: string, isConst: boolean, ty?: TyLabelNode, body?: ExprNode): VariableDecl {
+    return { kind: 'VariableDecl', pos, name, ty, body, isConst };
 }
 
 // struct
@@ -326,6 +664,7 @@ export function createVariableDecl(pos: Pos, name: string, ty?: TyLabel, body?:
 export type StructDecl = {
     kind: 'StructDecl',
     pos: Pos,
+    end?: Pos,
     name: string,
     fields: StructDeclField[],
     exported: boolean,
@@ -337,16 +676,42 @@ export function createStructDecl(pos: Pos, name: string, fields: StructDeclField
 export type StructDeclField = {
     kind: 'StructDeclField',
     pos: Pos,
+    end?: Pos,
     name: string,
-    ty: TyLabel,
+    ty: TyLabelNode,
 };
-export function createStructDeclField(pos: Pos, name: string, ty: TyLabel): StructDeclField {
+export function createStructDeclField(pos: Pos, name: string, ty: TyLabelNode): StructDeclField {
     return { kind: 'StructDeclField', pos, name, ty };
 }
 
+// enum
+
+export type EnumDecl = {
+    kind: 'EnumDecl',
+    pos: Pos,
+    end?: Pos,
+    name: string,
+    variants: EnumDeclVariant[],
+    exported: boolean,
+};
+export function createEnumDecl(pos: Pos, name: string, variants: EnumDeclVariant[], exported: boolean): EnumDecl {
+    return { kind: 'EnumDecl', pos, name, variants, exported };
+}
+
+export type EnumDeclVariant = {
+    kind: 'EnumDeclVariant',
+    pos: Pos,
+    end?: Pos,
+    name: string,
+};
+export function createEnumDeclVariant(pos: Pos, name: string): EnumDeclVariant {
+    return { kind: 'EnumDeclVariant', pos, name };
+}
+
 export type StructExpr = {
     kind: 'StructExpr',
     pos: Pos,
+    end?: Pos,
     name: string,
     fields: StructExprField[],
 };
@@ -357,6 +722,7 @@ export function createStructExpr(pos: Pos, name: string, fields: StructExprField
 export type StructExprField = {
     kind: 'StructExprField',
     pos: Pos,
+    end?: Pos,
     name: string,
     body: ExprNode,
 };
@@ -367,6 +733,7 @@ export function createStructExprField(pos: Pos, name: string, body: ExprNode): S
 export type FieldAccess = {
     kind: 'FieldAccess',
     pos: Pos,
+    end?: Pos,
     name: string,
     target: ExprNode,
 };
@@ -379,6 +746,7 @@ export function createFieldAccess(pos: Pos, name: string, target: ExprNode): Fie
 export type ArrayNode = {
     kind: 'ArrayNode',
     pos: Pos,
+    end?: Pos,
     items: ExprNode[],
 };
 export function createArrayNode(pos: Pos, items: ExprNode[]): ArrayNode {
@@ -388,9 +756,206 @@ export function createArrayNode(pos: Pos, items: ExprNode[]): ArrayNode {
 export type IndexAccess = {
     kind: 'IndexAccess',
     pos: Pos,
+    end?: Pos,
     target: ExprNode,
     index: ExprNode,
 };
 export function createIndexAccess(pos: Pos, target: ExprNode, index: ExprNode): IndexAccess {
     return { kind: 'IndexAccess', pos, target, index };
 }
+
+// tuple
+
+/**
+ * The tuple has two or more items. The item is accessed by the field access with its index (e.g. `x.0`).
+*/
+export type TupleNode = {
+    kind: 'TupleNode',
+    pos: Pos,
+    end?: Pos,
+    items: ExprNode[],
+};
+export function createTupleNode(pos: Pos, items: ExprNode[]): TupleNode {
+    return { kind: 'TupleNode', pos, items };
+}
+
+/**
+ * Get the child nodes in the order of appearance in the source code.
+*/
+export function getChildNodes(node: AstNode): AstNode[] {
+    switch (node.kind) {
+        case 'SourceFile': {
+            return [...node.imports, ...node.decls];
+        }
+        case 'ImportDecl': {
+            return [];
+        }
+        case 'FunctionDecl':
+        case 'LambdaExpr': {
+            const children: AstNode[] = [...node.params];
+            if (node.returnTy != null) {
+                children.push(node.returnTy);
+            }
+            children.push(...node.body);
+            return children;
+        }
+        case 'FnDeclParam': {
+            const children: AstNode[] = [];
+            if (node.ty != null) {
+                children.push(node.ty);
+            }
+            if (node.defaultValue != null) {
+                children.push(node.defaultValue);
+            }
+            return children;
+        }
+        case 'StructDecl': {
+            return [...node.fields];
+        }
+        case 'StructDeclField': {
+            return [node.ty];
+        }
+        case 'EnumDecl': {
+            return [...node.variants];
+        }
+        case 'VariableDecl': {
+            const children: AstNode[] = [];
+            if (node.ty != null) {
+                children.push(node.ty);
+            }
+            if (node.body != null) {
+                children.push(node.body);
+            }
+            return children;
+        }
+        case 'AssignStatement': {
+            return [node.target, node.body];
+        }
+        case 'IfStatement':
+        case 'IfExpr': {
+            return [node.cond, ...node.thenBlock, ...node.elseBlock];
+        }
+        case 'ConditionalExpr': {
+            return [node.cond, node.thenExpr, node.elseExpr];
+        }
+        case 'MatchExpr': {
+            return [node.target, ...node.arms];
+        }
+        case 'MatchArm': {
+            return [node.body];
+        }
+        case 'LoopStatement': {
+            return [...node.block];
+        }
+        case 'RepeatStatement': {
+            return [node.count, ...node.block];
+        }
+        case 'WhileStatement': {
+            return [node.cond, ...node.block];
+        }
+        case 'ForStatement': {
+            const children: AstNode[] = [];
+            if (node.init != null) {
+                children.push(node.init);
+            }
+            if (node.cond != null) {
+                children.push(node.cond);
+            }
+            if (node.step != null) {
+                children.push(node.step);
+            }
+            return [...children, ...node.block];
+        }
+        case 'ForEachFieldStatement': {
+            return [node.target, ...node.block];
+        }
+        case 'ReturnStatement': {
+            return (node.expr != null) ? [node.expr] : [];
+        }
+        case 'TailExpr': {
+            return [node.expr];
+        }
+        case 'BreakStatement':
+        case 'ContinueStatement':
+        case 'EnumDeclVariant':
+        case 'TyLabel':
+        case 'NumberLiteral':
+        case 'FloatLiteral':
+        case 'BoolLiteral':
+        case 'CharLiteral':
+        case 'StringLiteral':
+        case 'NoneLiteral':
+        case 'Identifier': {
+            return [];
+        }
+        case 'FunctionTyLabel': {
+            return [...node.params, node.returnTy];
+        }
+        case 'TupleTyLabel': {
+            return [...node.items];
+        }
+        case 'OptionalTyLabel': {
+            return [node.innerTy];
+        }
+        case 'BinaryOp': {
+            return [node.left, node.right];
+        }
+        case 'UnaryOp': {
+            return [node.expr];
+        }
+        case 'Call': {
+            return [node.callee, ...node.args];
+        }
+        case 'StructExpr': {
+            return [...node.fields];
+        }
+        case 'StructExprField': {
+            return [node.body];
+        }
+        case 'FieldAccess': {
+            return [node.target];
+        }
+        case 'ArrayNode':
+        case 'TupleNode': {
+            return [...node.items];
+        }
+        case 'IndexAccess': {
+            return [node.target, node.index];
+        }
+    }
+}
+
+/**
+ * The range of the source code. The end position is exclusive.
+*/
+export type Span = { start: Pos, end: Pos };
+
+/**
+ * Get the range of the source code that the node and its children cover.
+ * The position of some nodes does not point to the beginning (e.g. the operator of the binary operation),
+ * so the start is the earliest position among the node and its children.
+*/
+export function getSpan(node: AstNode): Span {
+    let start = node.pos;
+    let end = node.end ?? node.pos;
+    for (const child of getChildNodes(node)) {
+        const span = getSpan(child);
+        if (comparePos(span.start, start) < 0) {
+            start = span.start;
+        }
+        if (comparePos(span.end, end) > 0) {
+            end = span.end;
+        }
+    }
+    return { start, end };
+}
+
+/**
+ * Compare the positions. Returns a negative number if x is before y, and a positive number if x is after y.
+*/
+export function comparePos(x: Pos, y: Pos): number {
+    if (x[0] != y[0]) {
+        return x[0] - y[0];
+    }
+    return x[1] - y[1];
+}
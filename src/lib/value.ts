@@ -0,0 +1,153 @@
+import { UguisuError } from './misc/errors.js';
+import {
+    ArrayValue,
+    BoolValue,
+    CharValue,
+    EnumValue,
+    FloatValue,
+    NoneValue,
+    NumberValue,
+    OptionalValue,
+    StringValue,
+    StructValue,
+    Symbol,
+    TupleValue,
+    Value
+} from './running/tools.js';
+
+/**
+ * The value of the program exposed to the host.
+ * The struct, the array and the tuple are copied, so modifying them does not affect the program.
+ * The optional value has no `value` if it is `none`.
+ *
+ * ```js
+ * const value = uguisu.runSourceForValue('fn main(): number { return 42; }');
+ * if (value.kind == 'number') {
+ *     console.log(value.value); // 42
+ * }
+ * ```
+*/
+export type UguisuValue =
+    | { kind: 'void' }
+    | { kind: 'number', value: number }
+    | { kind: 'float', value: number }
+    | { kind: 'bool', value: boolean }
+    | { kind: 'char', value: string }
+    | { kind: 'string', value: string }
+    | { kind: 'struct', fields: Record<string, UguisuValue> }
+    | { kind: 'array', items: UguisuValue[] }
+    | { kind: 'tuple', items: UguisuValue[] }
+    | { kind: 'enum', name: string, variant: string }
+    | { kind: 'optional', value?: UguisuValue }
+    | { kind: 'function' };
+
+/**
+ * Convert the value of the runtime to the value exposed to the host.
+*/
+export function toUguisuValue(value: Value): UguisuValue {
+    switch (value.kind) {
+        case 'NoneValue': {
+            return { kind: 'void' };
+        }
+        case 'NumberValue': {
+            return { kind: 'number', value: value.getValue() };
+        }
+        case 'FloatValue': {
+            return { kind: 'float', value: value.getValue() };
+        }
+        case 'BoolValue': {
+            return { kind: 'bool', value: value.getValue() };
+        }
+        case 'CharValue': {
+            return { kind: 'char', value: value.getValue() };
+        }
+        case 'StringValue': {
+            return { kind: 'string', value: value.getValue() };
+        }
+        case 'StructValue': {
+            const fields: Record<string, UguisuValue> = {};
+            for (const name of value.getFieldNames()) {
+                const field = value.lookupField(name)!;
+                if (field.value != null) {
+                    fields[name] = toUguisuValue(field.value);
+                }
+            }
+            return { kind: 'struct', fields };
+        }
+        case 'ArrayValue': {
+            const items: UguisuValue[] = [];
+            for (let i = 0; i < value.count(); i++) {
+                const item = value.at(i)!;
+                items.push(item.value != null ? toUguisuValue(item.value) : { kind: 'void' });
+            }
+            return { kind: 'array', items };
+        }
+        case 'TupleValue': {
+            const items: UguisuValue[] = [];
+            for (let i = 0; i < value.count(); i++) {
+                const item = value.at(i)!;
+                items.push(item.value != null ? toUguisuValue(item.value) : { kind: 'void' });
+            }
+            return { kind: 'tuple', items };
+        }
+        case 'EnumValue': {
+            return { kind: 'enum', name: value.getEnumName(), variant: value.getVariant() };
+        }
+        case 'OptionalValue': {
+            const inner = value.getValue();
+            return (inner != null) ? { kind: 'optional', value: toUguisuValue(inner) } : { kind: 'optional' };
+        }
+        case 'FunctionValue': {
+            return { kind: 'function' };
+        }
+    }
+}
+
+/**
+ * Convert the value given by the host to the value of the runtime.
+ * @throws UguisuError (The function cannot be passed from the host)
+*/
+export function fromUguisuValue(value: UguisuValue): Value {
+    switch (value.kind) {
+        case 'void': {
+            return new NoneValue();
+        }
+        case 'number': {
+            return new NumberValue(value.value);
+        }
+        case 'float': {
+            return new FloatValue(value.value);
+        }
+        case 'bool': {
+            return new BoolValue(value.value);
+        }
+        case 'char': {
+            return new CharValue(value.value);
+        }
+        case 'string': {
+            return new StringValue(value.value);
+        }
+        case 'struct': {
+            const fields = new Map<string, Symbol>();
+            for (const [name, field] of Object.entries(value.fields)) {
+                fields.set(name, new Symbol(fromUguisuValue(field)));
+            }
+            return new StructValue(fields);
+        }
+        case 'array': {
+            return new ArrayValue(value.items.map(x => new Symbol(fromUguisuValue(x))));
+        }
+        case 'tuple': {
+            return new TupleValue(value.items.map(x => new Symbol(fromUguisuValue(x))));
+        }
+        case 'enum': {
+            return new EnumValue(value.name, value.variant);
+        }
+        case 'optional': {
+            return new OptionalValue(value.value != null ? fromUguisuValue(value.value) : undefined);
+        }
+        case 'function': {
+            throw new UguisuError('a function cannot be passed from the host.');
+        }
+    }
+}
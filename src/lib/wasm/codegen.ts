@@ -164,6 +164,34 @@ function translateStatements(ctx: Context, nodes: StatementNode[], funcInfo: Fun
                 body.push(ctx.mod.loop('L'+label, ctx.mod.block('B'+label, refs)));
                 break;
             }
+            case 'WhileStatement': {
+                const label = ctx.labelCount;
+                ctx.labelCount++;
+                const cond = translateExpr(ctx, node.cond, funcInfo);
+                const refs = translateStatements(ctx, node.block, funcInfo, label);
+                refs.unshift(ctx.mod.br('B'+label, ctx.mod.i32.eqz(cond)));
+                refs.push(ctx.mod.br('L'+label));
+                body.push(ctx.mod.loop('L'+label, ctx.mod.block('B'+label, refs)));
+                break;
+            }
+            case 'ForStatement': {
+                if (node.init != null) {
+                    body.push(...translateStatements(ctx, [node.init], funcInfo, loopLabel));
+                }
+                const label = ctx.labelCount;
+                ctx.labelCount++;
+                const refs = translateStatements(ctx, node.block, funcInfo, label);
+                if (node.cond != null) {
+                    const cond = translateExpr(ctx, node.cond, funcInfo);
+                    refs.unshift(ctx.mod.br('B'+label, ctx.mod.i32.eqz(cond)));
+                }
+                if (node.step != null) {
+                    refs.push(...translateStatements(ctx, [node.step], funcInfo, loopLabel));
+                }
+                refs.push(ctx.mod.br('L'+label));
+                body.push(ctx.mod.loop('L'+label, ctx.mod.block('B'+label, refs)));
+                break;
+            }
             case 'ReturnStatement': {
                 if (node.expr != null) {
                     const expr = translateExpr(ctx, node.expr, funcInfo);
@@ -177,9 +205,15 @@ function translateStatements(ctx: Context, nodes: StatementNode[], funcInfo: Fun
                 if (loopLabel == null) {
                     throw new UguisuError('invalid break target');
                 }
+                if (node.label != null) {
+                    throw new UguisuError('not impelemented yet');
+                }
                 body.push(ctx.mod.br('B'+loopLabel));
                 break;
             }
+            case 'ContinueStatement': {
+                throw new UguisuError('not impelemented yet');
+            }
             case 'NumberLiteral':
             case 'BoolLiteral':
             case 'StringLiteral':
@@ -252,11 +286,12 @@ function translateExpr(ctx: Context, node: ExprNode, func: FuncInfo): number {
                     case '>=': {
                         return ctx.mod.i32.ge_s(left, right);
                     }
+                    // the right operand is evaluated only if the left operand does not determine the result
                     case '&&': {
-                        return ctx.mod.i32.and(left, right);
+                        return ctx.mod.if(left, right, ctx.mod.i32.const(0));
                     }
                     case '||': {
-                        return ctx.mod.i32.or(left, right);
+                        return ctx.mod.if(left, ctx.mod.i32.const(1), right);
                     }
                     default: {
                         throw new UguisuError('unsupported operation');
@@ -277,6 +312,9 @@ function translateExpr(ctx: Context, node: ExprNode, func: FuncInfo): number {
                 case '!': {
                     return ctx.mod.i32.eq(expr, ctx.mod.i32.const(0));
                 }
+                case '-': {
+                    return ctx.mod.i32.sub(ctx.mod.i32.const(0), expr);
+                }
                 default: {
                     throw new UguisuError('unsupported operation');
                 }
@@ -0,0 +1,725 @@
+import assert from 'assert';
+import { inspectSource, listBuiltins, Uguisu, UguisuError } from '../src/lib/index.js';
+
+test('list builtins', () => {
+    const builtins = listBuiltins();
+    const writeNum = builtins.find(x => x.name == 'console.writeNum');
+    assert.deepStrictEqual(writeNum, { name: 'console.writeNum', params: ['number'], returnType: 'void' });
+    const getUnixtime = builtins.find(x => x.name == 'getUnixtime');
+    assert.deepStrictEqual(getUnixtime, { name: 'getUnixtime', params: [], returnType: 'number' });
+});
+
+test('import a function from another file', () => {
+    const files: Record<string, string> = {
+        '/project/main.ug': `
+import "lib/util.ug";
+fn main() {
+    console.writeNum(double(21));
+}
+`,
+        '/project/lib/util.ug': `
+fn double(x: number): number {
+    return x * 2;
+}
+`,
+    };
+    const output: string[] = [];
+    const uguisu = new Uguisu({
+        stdout(str) { output.push(str); },
+        readFile(filePath) {
+            const source = files[filePath];
+            if (source == null) {
+                throw new Error('not found');
+            }
+            return source;
+        },
+    });
+    uguisu.run('/project');
+    assert.deepStrictEqual(output, ['42']);
+});
+
+test('import cycle', () => {
+    const files: Record<string, string> = {
+        '/project/main.ug': `
+import "a.ug";
+fn main() { }
+`,
+        '/project/a.ug': `
+import "main.ug";
+fn a() { }
+`,
+    };
+    const uguisu = new Uguisu({
+        readFile(filePath) {
+            return files[filePath];
+        },
+    });
+    assert.throws(() => uguisu.run('/project'), UguisuError);
+});
+
+test('run source code', () => {
+    const output: string[] = [];
+    const uguisu = new Uguisu({
+        stdout(str) { output.push(str); },
+    });
+    uguisu.runSource(`
+fn main() {
+    console.writeNum(1 + 2);
+}
+`);
+    assert.deepStrictEqual(output, ['3']);
+});
+
+test('exit code returned by main', () => {
+    const uguisu = new Uguisu({
+        stdout() { },
+    });
+    assert.strictEqual(uguisu.runSource(`
+fn main(): number {
+    return 42;
+}
+`), 42);
+    assert.strictEqual(uguisu.runSource(`
+fn main() { }
+`), undefined);
+});
+
+test('value returned by main', () => {
+    const uguisu = new Uguisu({
+        stdout() { },
+    });
+    const value = uguisu.runSourceForValue(`
+struct Point {
+    x: number,
+    name: string,
+}
+fn main(): Point {
+    return new Point { x: 1, name: "a" };
+}
+`);
+    assert.deepStrictEqual(value, {
+        kind: 'struct',
+        fields: {
+            x: { kind: 'number', value: 1 },
+            name: { kind: 'string', value: 'a' },
+        },
+    });
+    assert.deepStrictEqual(uguisu.runSourceForValue('fn main() { }'), { kind: 'void' });
+});
+
+test('run without main', () => {
+    const output: string[] = [];
+    const uguisu = new Uguisu({
+        stdout(str) { output.push(str); },
+    });
+    const sourceCode = `
+fn add(x: number, y: number): number {
+    return x + y;
+}
+var x = add(1, 2);
+`;
+    assert.throws(() => uguisu.runSource(sourceCode), UguisuError);
+    assert.strictEqual(uguisu.runSource(sourceCode, { skipMain: true }), undefined);
+    assert.deepStrictEqual(uguisu.runSourceForValue(sourceCode, { skipMain: true }), { kind: 'void' });
+});
+
+test('optimize option', () => {
+    const uguisu = new Uguisu({
+        stdout() { },
+    });
+    const sourceCode = `
+fn main(): number {
+    var x = 10;
+    return x + 2 * 3 + 4;
+}
+`;
+    assert.strictEqual(uguisu.runSource(sourceCode, { optimize: true }), 20);
+    assert.strictEqual(uguisu.runSource(sourceCode), 20);
+});
+
+test('time option', () => {
+    const output: string[] = [];
+    const log = console.log;
+    console.log = (str: string) => { output.push(str); };
+    const sourceCode = `
+fn main() {
+    console.write("hello");
+}
+`;
+    try {
+        const uguisu = new Uguisu();
+        uguisu.runSource(sourceCode, { time: true });
+        assert.strictEqual(output.length, 4);
+        assert.ok(output[0].startsWith('[time] parse: '));
+        assert.ok(output[1].startsWith('[time] analyze: '));
+        assert.strictEqual(output[2], 'hello');
+        assert.ok(output[3].startsWith('[time] run: '));
+
+        output.length = 0;
+        uguisu.runSource(sourceCode, { time: true, skipCheck: true });
+        assert.deepStrictEqual(output.map(x => x.split(':')[0]), ['[time] parse', 'hello', '[time] run']);
+    } finally {
+        console.log = log;
+    }
+});
+
+test('evaluate expression', () => {
+    const uguisu = new Uguisu({
+        stdout() { },
+    });
+    assert.deepStrictEqual(uguisu.evalExpression('1 + 2 * 3'), { kind: 'number', value: 7 });
+    assert.deepStrictEqual(uguisu.evalExpression('"a" + "b" == "ab"'), { kind: 'bool', value: true });
+    assert.deepStrictEqual(uguisu.evalExpression('number.max(1, 5)'), { kind: 'number', value: 5 });
+    assert.throws(() => uguisu.evalExpression('1 +'), UguisuError);
+    assert.throws(() => uguisu.evalExpression('1 + true'), UguisuError);
+    assert.throws(() => uguisu.evalExpression('1 2'), UguisuError);
+});
+
+test('interpreter keeps the declarations', () => {
+    const output: string[] = [];
+    const uguisu = new Uguisu({
+        stdout(str) { output.push(str); },
+    });
+    const interpreter = uguisu.createInterpreter();
+    interpreter.feed('fn square(x: number): number { return x * x; }');
+    interpreter.feed(`
+var total = square(3);
+fn report() {
+    console.write(number.toString(total));
+}
+`);
+    assert.deepStrictEqual(interpreter.eval('total + 1'), { kind: 'number', value: 10 });
+    interpreter.eval('report()');
+    assert.deepStrictEqual(output, ['9']);
+    // the declarations with errors are not added
+    assert.throws(() => interpreter.feed('var x = 1; var y: bool = 2;'), UguisuError);
+    assert.throws(() => interpreter.eval('x'), UguisuError);
+    interpreter.feed('var x = 2;');
+    assert.deepStrictEqual(interpreter.eval('square(x)'), { kind: 'number', value: 4 });
+    // already declared
+    assert.throws(() => interpreter.feed('fn square(x: number): number { return x; }'), UguisuError);
+    // the interpreters do not share the declarations
+    assert.throws(() => uguisu.createInterpreter().eval('square(2)'), UguisuError);
+});
+
+test('interpreter does not keep the declarations when the initialization fails', () => {
+    const uguisu = new Uguisu();
+    const interpreter = uguisu.createInterpreter();
+    assert.throws(() => interpreter.feed(`
+fn fail(): number {
+    panic("failed");
+}
+var failed = fail();
+`));
+    assert.throws(() => interpreter.feed('var y = failed + 1;'), UguisuError);
+    assert.throws(() => interpreter.eval('fail()'), UguisuError);
+    // the names can be declared again
+    interpreter.feed('var failed = 1;');
+    assert.deepStrictEqual(interpreter.eval('failed'), { kind: 'number', value: 1 });
+});
+
+test('register host function', () => {
+    const output: string[] = [];
+    const uguisu = new Uguisu({
+        stdout(str) { output.push(str); },
+    });
+    uguisu.registerFunction({ name: 'repeatText', params: ['string', 'number'], returnType: 'string' }, args => {
+        if (args[0].kind != 'string' || args[1].kind != 'number') {
+            throw new Error('invalid arguments');
+        }
+        return { kind: 'string', value: args[0].value.repeat(args[1].value) };
+    });
+    uguisu.runSource(`
+fn main() {
+    console.write(repeatText("ab", 3));
+}
+`);
+    assert.deepStrictEqual(output, ['ababab']);
+    // type checked
+    assert.throws(() => uguisu.runSource('fn main() { repeatText(1, 3); }'), UguisuError);
+    // conflicted with the builtin
+    assert.throws(() => uguisu.registerFunction({ name: 'number', params: [], returnType: 'void' }, () => ({ kind: 'void' })), TypeError);
+});
+
+test('list functions including the registered ones', () => {
+    const uguisu = new Uguisu();
+    uguisu.registerFunction({ name: 'double', params: ['number'], returnType: 'number' }, args => args[0]);
+    const functions = uguisu.listFunctions();
+    assert.deepStrictEqual(functions.find(x => x.name == 'double'), { name: 'double', params: ['number'], returnType: 'number' });
+    assert.ok(functions.some(x => x.name == 'console.writeNum'));
+    assert.strictEqual(functions.length, listBuiltins().length + 1);
+    // the builtins of another instance are not affected
+    assert.ok(!new Uguisu().listFunctions().some(x => x.name == 'double'));
+});
+
+test('output is written to the console by default', () => {
+    const output: string[] = [];
+    const log = console.log;
+    console.log = (str: string) => { output.push(str); };
+    try {
+        const uguisu = new Uguisu();
+        uguisu.runSource(`
+fn main() {
+    console.write("hello");
+}
+`);
+    } finally {
+        console.log = log;
+    }
+    assert.deepStrictEqual(output, ['hello']);
+});
+
+test('dump options', () => {
+    const output: string[] = [];
+    const log = console.log;
+    console.log = (str: string) => { output.push(str); };
+    const sourceCode = `
+fn main() {
+    console.write("hello");
+}
+`;
+    try {
+        const uguisu = new Uguisu();
+        // only parsed
+        uguisu.runSource(sourceCode, { dumpAst: true });
+        assert.strictEqual(output.length, 1);
+        assert.ok(output[0].startsWith('SourceFile('));
+        assert.ok(output[0].includes('\n  FunctionDecl(main) (2:1)\n'));
+
+        // the AST and the analysis result are printed in order before running
+        output.length = 0;
+        uguisu.runSource(sourceCode, { dumpAst: true, dumpSymbolTable: true });
+        assert.strictEqual(output.length, 3);
+        assert.ok(output[0].startsWith('SourceFile'));
+        assert.ok(output[1].startsWith('node#'));
+        assert.strictEqual(output[2], 'hello');
+
+        // the analysis result is printed without checking
+        output.length = 0;
+        uguisu.runSource(`
+fn main() {
+    var x: number = "a";
+    console.write("hello");
+}
+`, { dumpSymbolTable: true, skipCheck: true });
+        assert.strictEqual(output.length, 2);
+        assert.ok(output[0].startsWith('node#'));
+        assert.strictEqual(output[1], 'hello');
+    } finally {
+        console.log = log;
+    }
+});
+
+test('trace option', () => {
+    const output: string[] = [];
+    const log = console.log;
+    console.log = (str: string) => { output.push(str); };
+    const sourceCode = `
+fn main() {
+    console.write("hello");
+}
+`;
+    try {
+        const uguisu = new Uguisu();
+        uguisu.runSource(sourceCode);
+        assert.deepStrictEqual(output, ['hello']);
+
+        output.length = 0;
+        uguisu.runSource(sourceCode, { trace: true });
+        assert.ok(output.some(x => x.includes('analyze statement: Call')));
+        assert.ok(output.some(x => x.includes('call function: main')));
+        assert.ok(output.some(x => x.includes('eval statement: Call')));
+        assert.ok(output.includes('hello'));
+    } finally {
+        console.log = log;
+    }
+});
+
+test('inspect source', () => {
+    const program = inspectSource(`
+fn add(x: number, y: number): number {
+    return x + y;
+}
+fn main() {
+    var flag = add(1, 2) > 2;
+}
+`);
+    assert.ok(program.success);
+    const kinds = [...program.nodes()].map(x => x.kind);
+    assert.strictEqual(kinds[0], 'SourceFile');
+    assert.ok(kinds.includes('BinaryOp'));
+    for (const node of program.nodes()) {
+        if (node.kind == 'FunctionDecl' && node.name == 'add') {
+            assert.strictEqual(program.getTypeString(node), '(number, number) => number');
+            assert.deepStrictEqual(program.getPos(node), [2, 1]);
+        }
+        if (node.kind == 'VariableDecl') {
+            assert.strictEqual(node.name, 'flag');
+            assert.strictEqual(program.getTypeString(node), 'bool');
+        }
+    }
+});
+
+test('references between the nodes', () => {
+    const program = inspectSource(`
+fn add(x: number, y: number): number {
+    return x + y;
+}
+fn main() {
+    var a = add(1, 2);
+    console.writeNum(add(a, 3));
+}
+`);
+    assert.ok(program.success);
+    const nodes = [...program.nodes()];
+    const add = nodes.find(x => x.kind == 'FunctionDecl' && x.name == 'add')!;
+
+    // the call graph
+    const callees = nodes
+        .filter(x => x.kind == 'Call')
+        .map(x => (x.kind == 'Call') ? program.getDeclaration(x.callee) : undefined);
+    assert.deepStrictEqual(callees, [add, undefined, add]);
+
+    const refs = program.getReferences(add);
+    assert.deepStrictEqual(refs.map(x => program.getPos(x)), [[6, 13], [7, 22]]);
+
+    const param = nodes.find(x => x.kind == 'FnDeclParam' && x.name == 'x')!;
+    const a = nodes.find(x => x.kind == 'VariableDecl' && x.name == 'a')!;
+    assert.deepStrictEqual(program.getReferences(param).map(x => program.getPos(x)), [[3, 12]]);
+    assert.strictEqual(program.getDeclaration(program.getReferences(a)[0]), a);
+    assert.strictEqual(program.getTypeString(program.getReferences(a)[0]), 'number');
+});
+
+test('references to the struct field', () => {
+    const program = inspectSource(`
+struct Point {
+    x: number,
+    y: number,
+}
+fn main() {
+    var p = new Point { x: 1, y: 2 };
+    p.x = p.x + p.y;
+    console.writeNum(p.x);
+}
+`);
+    assert.ok(program.success);
+    const nodes = [...program.nodes()];
+    const x = nodes.find(x => x.kind == 'StructDeclField' && x.name == 'x')!;
+    const refs = program.getReferences(x);
+    assert.deepStrictEqual(refs.map(x => x.kind), ['FieldAccess', 'FieldAccess', 'FieldAccess']);
+    assert.deepStrictEqual(refs.map(x => program.getSpan(x).start), [[8, 5], [8, 11], [9, 22]]);
+    assert.strictEqual(program.getDeclaration(refs[0]), x);
+    assert.strictEqual(program.getTypeString(refs[0]), 'number');
+});
+
+test('diagnostics of the analysis', () => {
+    const program = inspectSource(`
+fn main() {
+    var x: number = true;
+}
+`);
+    assert.ok(!program.success);
+    assert.strictEqual(program.diagnostics.length, 1);
+    assert.strictEqual(program.diagnostics[0].severity, 'error');
+    assert.deepStrictEqual(program.diagnostics[0].start, [3, 21]);
+    assert.deepStrictEqual(program.diagnostics[0].end, [3, 25]);
+});
+
+test('diagnostics of the else if condition', () => {
+    const program = inspectSource(`
+fn main() {
+    var x = 1;
+    if x == 0 {
+    } else if x == 1 {
+    } else if x {
+    }
+}
+`);
+    assert.ok(!program.success);
+    assert.strictEqual(program.diagnostics.length, 1);
+    assert.deepStrictEqual(program.diagnostics[0].start, [7, 15]);
+    assert.deepStrictEqual(program.diagnostics[0].end, [7, 16]);
+});
+
+test('diagnostics of the reassignment with another type', () => {
+    const program = inspectSource(`
+fn main() {
+    var x;
+    x = 1;
+    x = "a";
+    number.assertEq(x, 1);
+}
+`);
+    assert.ok(!program.success);
+    assert.strictEqual(program.diagnostics.length, 1);
+    assert.strictEqual(program.diagnostics[0].message, 'type mismatched. expected `number`, found `string`');
+    assert.deepStrictEqual(program.diagnostics[0].start, [5, 9]);
+});
+
+test('diagnostics of the field iteration are reported once', () => {
+    const program = inspectSource(`
+struct Point {
+    x: number,
+    y: number,
+}
+fn main() {
+    var p = new Point { x: 1, y: 2 };
+    for name, value in p {
+        console.write(value);
+    }
+}
+`);
+    assert.ok(!program.success);
+    assert.strictEqual(program.diagnostics.length, 1);
+    assert.strictEqual(program.diagnostics[0].message, 'type mismatched. expected `string`, found `number`');
+    assert.deepStrictEqual(program.diagnostics[0].start, [9, 23]);
+});
+
+test('diagnostics of the operator not defined for string', () => {
+    const program = inspectSource(`
+fn main() {
+    var x = "abc" * 2;
+}
+`);
+    assert.ok(!program.success);
+    assert.strictEqual(program.diagnostics.length, 1);
+    assert.strictEqual(program.diagnostics[0].message, 'operator `*` is not defined for `string`.');
+    assert.deepStrictEqual(program.diagnostics[0].start, [3, 13]);
+    assert.deepStrictEqual(program.diagnostics[0].end, [3, 18]);
+});
+
+test('diagnostics of the duplicate global declaration', () => {
+    const program = inspectSource(`
+var x = 1;
+var x = 2;
+fn main() { }
+`);
+    assert.ok(!program.success);
+    assert.strictEqual(program.diagnostics.length, 1);
+    assert.strictEqual(program.diagnostics[0].message, '`x` is already declared.');
+    assert.deepStrictEqual(program.diagnostics[0].start, [3, 1]);
+    assert.deepStrictEqual(program.diagnostics[0].end, [3, 11]);
+});
+
+test('diagnostics of the void type', () => {
+    const program = inspectSource(`
+struct S {
+    value: void,
+}
+fn f(x: void): void {
+}
+fn main() {
+    var x: void = f(1);
+    var g: () => void = main;
+    var t: (number, void) = (1, 2);
+}
+`);
+    assert.ok(!program.success);
+    const expected: [string, [number, number]][] = [
+        ['fields cannot have type `void`.', [3, 12]],
+        ['return type `void` should be omitted.', [5, 16]],
+        ['parameters cannot have type `void`.', [5, 9]],
+        ['variables cannot have type `void`.', [8, 12]],
+        ['tuple elements cannot have type `void`.', [10, 21]],
+    ];
+    for (const [message, start] of expected) {
+        const diagnostic = program.diagnostics.find(x => x.message == message);
+        assert.deepStrictEqual(diagnostic?.start, start);
+    }
+    // the function type can return void
+    assert.ok(!program.diagnostics.some(x => x.start != null && x.start[0] == 9));
+});
+
+test('diagnostics of the comparison chaining', () => {
+    const program = inspectSource(`
+fn main() {
+    var x = 5;
+    var inRange = 1 < x < 10;
+}
+`);
+    assert.ok(!program.success);
+    assert.strictEqual(program.diagnostics.length, 1);
+    assert.strictEqual(program.diagnostics[0].message, 'comparison chaining is not supported; use `&&`.');
+    assert.deepStrictEqual(program.diagnostics[0].start, [4, 25]);
+});
+
+test('warning of the loop that never ends', () => {
+    const source = `
+fn main() {
+    loop {
+        console.write("tick");
+    }
+}
+fn find(): number {
+    var i = 0;
+    loop {
+        if i == 3 {
+            return i;
+        }
+        i = i + 1;
+    }
+}
+fn count() {
+    loop {
+        while true {
+            break;
+        }
+        if true {
+            break;
+        }
+    }
+}
+fn fail() {
+    loop {
+        panic("failed");
+    }
+}
+`;
+    const program = inspectSource(source, undefined, { warnEndlessLoop: true });
+    assert.ok(program.success);
+    assert.strictEqual(program.diagnostics.length, 1);
+    assert.strictEqual(program.diagnostics[0].severity, 'warning');
+    assert.strictEqual(program.diagnostics[0].message, 'the loop never ends, since it has no `break` or `return`.');
+    assert.deepStrictEqual(program.diagnostics[0].start, [3, 5]);
+    // off by default
+    assert.strictEqual(inspectSource(source).diagnostics.length, 0);
+});
+
+test('nested function cannot use the local variables', () => {
+    const program = inspectSource(`
+fn main() {
+    var x = 1;
+    fn f(): number {
+        return x;
+    }
+    f();
+}
+`);
+    assert.strictEqual(program.success, false);
+    assert.strictEqual(program.diagnostics[0].message, 'cannot use the local variable `x` of the enclosing function.');
+    assert.deepStrictEqual(program.diagnostics[0].start, [5, 16]);
+});
+
+test('diagnostics of the loop labels', () => {
+    const program = inspectSource(`
+fn main() {
+    outer: loop {
+        break inner;
+    }
+}
+`);
+    assert.strictEqual(program.success, false);
+    assert.strictEqual(program.diagnostics[0].message, 'unknown label `inner`.');
+    assert.deepStrictEqual(program.diagnostics[0].start, [4, 9]);
+});
+
+test('warning of the unused variables', () => {
+    const program = inspectSource(`
+fn main() {
+    var used = 1;
+    var unused = 2;
+    var _ignored = 3;
+    var captured = 4;
+    var f = fn(): number { captured };
+    number.assertEq(f(), used);
+    add(1, 2);
+    var written = 5;
+    written = 6;
+}
+fn add(x: number, y: number) {
+}
+`);
+    assert.ok(program.success);
+    assert.deepStrictEqual(program.diagnostics.map(x => [x.severity, x.message, x.start]), [
+        ['warning', 'variable `unused` is never used.', [4, 5]],
+        ['warning', 'variable `written` is never used.', [10, 5]],
+    ]);
+});
+
+test('span of the node', () => {
+    const program = inspectSource(`
+fn main() {
+    var x = (1 + 23) * foo(4, 5);
+}
+`);
+    for (const node of program.nodes()) {
+        if (node.kind == 'VariableDecl') {
+            assert.deepStrictEqual(program.getSpan(node), { start: [3, 5], end: [3, 34] });
+        }
+        if (node.kind == 'BinaryOp' && node.operator == '+') {
+            assert.deepStrictEqual(program.getSpan(node), { start: [3, 14], end: [3, 20] });
+        }
+        if (node.kind == 'Call') {
+            assert.deepStrictEqual(program.getSpan(node), { start: [3, 24], end: [3, 33] });
+        }
+    }
+});
+
+test('diagnostics of the syntax errors', () => {
+    const uguisu = new Uguisu();
+    assert.throws(() => uguisu.runSource(`
+fn main() {
+    var x = ;
+}
+`), (err: unknown) => {
+        return err instanceof UguisuError
+            && err.diagnostics.length == 1
+            && err.diagnostics[0].start != null
+            && err.diagnostics[0].start[0] == 3
+            && err.diagnostics[0].start[1] == 13;
+    });
+});
+
+test('syntax errors show the expected tokens', () => {
+    const uguisu = new Uguisu();
+    assert.throws(() => uguisu.runSource(`
+fn main() {
+    var x = (1 + 2;
+    var y = ;
+}
+struct P { x: number }
+1;
+`), (err: unknown) => {
+        if (!(err instanceof UguisuError)) {
+            return false;
+        }
+        assert.deepStrictEqual(err.diagnostics.map(x => [x.message, x.start]), [
+            ['expected `)`, found `;`.', [3, 19]],
+            ['expected expression, found `;`.', [4, 13]],
+            ['expected one of `fn`, `struct`, `enum`, `var`, `const`, found literal.', [7, 1]],
+        ]);
+        return true;
+    });
+});
+
+test('diagnostics of the integer literal out of range', () => {
+    const uguisu = new Uguisu();
+    assert.throws(() => uguisu.runSource(`
+fn main() {
+    var x = 1 + 99999999999999999999;
+}
+`), (err: unknown) => {
+        return err instanceof UguisuError
+            && err.diagnostics.length == 1
+            && err.diagnostics[0].message == 'integer literal out of range for `number`.'
+            && err.diagnostics[0].start != null
+            && err.diagnostics[0].start[0] == 3
+            && err.diagnostics[0].start[1] == 17;
+    });
+    // the largest integer that the number represents exactly
+    assert.doesNotThrow(() => uguisu.runSource('fn main() { var x = 9007199254740991; }'));
+    assert.doesNotThrow(() => uguisu.runSource('fn main() { var x = 0x1F_FFFF_FFFF_FFFF; }'));
+});
+
+test('unknown field at runtime', () => {
+    const uguisu = new Uguisu();
+    assert.throws(() => uguisu.runSource(`
+struct Point {
+    x: number,
+}
+fn main() {
+    var p = new Point { x: 1 };
+    var y = p.y;
+}
+`, { skipCheck: true }), (err: unknown) => err instanceof UguisuError && err.message == 'unknown field `y`');
+});
@@ -0,0 +1,32 @@
+import assert from 'assert';
+import fs from 'fs';
+import os from 'os';
+import path from 'path';
+import { runStdin } from '../src/bin/uguisu/run.js';
+import { Uguisu } from '../src/lib/index.js';
+
+test('run the program read from stdin', () => {
+    const output: string[] = [];
+    const uguisu = new Uguisu({
+        stdout(str) { output.push(str); },
+    });
+    // the file stands in for the piped input
+    const dirPath = fs.mkdtempSync(path.join(os.tmpdir(), 'uguisu-'));
+    const filePath = path.join(dirPath, 'stdin.ug');
+    fs.writeFileSync(filePath, `
+fn main(): number {
+    console.write("こんにちは");
+    console.writeNum(1 + 2);
+    return 3;
+}
+`);
+    const fd = fs.openSync(filePath, 'r');
+    try {
+        const exitCode = runStdin(uguisu, {}, fd);
+        assert.strictEqual(exitCode, 3);
+        assert.deepStrictEqual(output, ['こんにちは', '3']);
+    } finally {
+        fs.closeSync(fd);
+        fs.rmSync(dirPath, { recursive: true });
+    }
+});
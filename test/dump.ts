@@ -0,0 +1,84 @@
+import assert from 'assert';
+import { parse } from '../src/lib/syntax/parse.js';
+import { analyze } from '../src/lib/semantics/analyze.js';
+import { dumpSymbolTable } from '../src/lib/semantics/dump.js';
+import { AnalysisEnv } from '../src/lib/semantics/tools.js';
+import { run } from '../src/lib/running/run.js';
+import { RunningEnv } from '../src/lib/running/tools.js';
+import { dumpAstJson } from '../src/lib/syntax/dump.js';
+import { SourceFile } from '../src/lib/syntax/tools.js';
+import { defaultVersion, ProjectInfo } from '../src/lib/project-file.js';
+
+function dump(sourceCode: string): string {
+    const projectInfo: ProjectInfo = {
+        filename: 'main.ug',
+        langVersion: defaultVersion,
+    };
+    const sourceFile = parse(sourceCode, projectInfo.filename, projectInfo);
+    const symbolTable = new Map();
+    const result = analyze(sourceFile, new AnalysisEnv(), symbolTable, projectInfo);
+    assert.ok(result.success);
+    return dumpSymbolTable(sourceFile, symbolTable);
+}
+
+test('canonical dump of structurally identical programs', () => {
+    const first = dump(`
+fn add(x: number, y: number): number {
+    return x + y;
+}
+fn main() {
+    var a = add(1, 2);
+    console.writeNum(a);
+}
+`);
+    const second = dump(`
+// the same program with another layout
+fn add(x: number, y: number): number { return x + y; }
+
+fn main() { var a = add(1, 2); console.writeNum(a); }
+`);
+    assert.notStrictEqual(first, '');
+    assert.strictEqual(second, first);
+});
+
+test('canonical dump of different programs', () => {
+    const first = dump(`
+fn main() {
+    var a = 1 + 2;
+}
+`);
+    const second = dump(`
+fn main() {
+    var a = 1 < 2;
+}
+`);
+    assert.notStrictEqual(second, first);
+});
+
+test('round trip of the AST as JSON', () => {
+    const projectInfo: ProjectInfo = {
+        filename: 'main.ug',
+        langVersion: defaultVersion,
+    };
+    const sourceFile = parse(`
+fn add(x: number, y: number): number {
+    return x + y;
+}
+fn main() {
+    var a = add(1, 2);
+    number.assertEq(a, 3);
+}
+`, projectInfo.filename, projectInfo);
+    const json = dumpAstJson(sourceFile);
+    const restored: SourceFile = JSON.parse(json);
+
+    // serialize again
+    assert.strictEqual(dumpAstJson(restored), json);
+    // the positions are included
+    assert.deepStrictEqual(restored.decls[0].pos, [2, 1]);
+
+    // the restored AST can be analyzed and run
+    const result = analyze(restored, new AnalysisEnv(), new Map(), projectInfo);
+    assert.ok(result.success);
+    run(restored, new RunningEnv(), {}, projectInfo);
+});
@@ -0,0 +1,96 @@
+import assert from 'assert';
+import { parse } from '../src/lib/syntax/parse.js';
+import { foldConstants } from '../src/lib/syntax/fold.js';
+import { FunctionDecl, VariableDecl } from '../src/lib/syntax/tools.js';
+import { defaultVersion, ProjectInfo } from '../src/lib/project-file.js';
+
+function getMainBody(sourceCode: string) {
+    const projectInfo: ProjectInfo = {
+        filename: 'main.ug',
+        langVersion: defaultVersion,
+    };
+    const sourceFile = foldConstants(parse(sourceCode, projectInfo.filename, projectInfo));
+    const main = sourceFile.decls.find(x => x.kind == 'FunctionDecl' && x.name == 'main') as FunctionDecl;
+    return main.body;
+}
+
+function getVariableBody(sourceCode: string) {
+    const decl = getMainBody(sourceCode)[0] as VariableDecl;
+    return decl.body;
+}
+
+test('fold arithmetic operations', () => {
+    const body = getVariableBody(`
+fn main() {
+    var x = 2 * 3 + 4 - -1;
+}
+`);
+    assert.ok(body?.kind == 'NumberLiteral');
+    assert.strictEqual(body.value, 11);
+});
+
+test('fold number division', () => {
+    const body = getVariableBody(`
+fn main() {
+    var x = 8 / 2;
+}
+`);
+    assert.ok(body?.kind == 'NumberLiteral');
+    assert.strictEqual(body.value, 4);
+});
+
+test('fold power operations', () => {
+    const body = getVariableBody(`
+fn main() {
+    var x = 2 ** 3 ** 2;
+}
+`);
+    assert.ok(body?.kind == 'NumberLiteral');
+    assert.strictEqual(body.value, 512);
+});
+
+test('fold power of the fraction', () => {
+    const body = getVariableBody(`
+fn main() {
+    var x = (7 / 2) ** 2;
+}
+`);
+    assert.ok(body?.kind == 'NumberLiteral');
+    assert.strictEqual(body.value, 12.25);
+});
+
+test('fold logical and comparison operations', () => {
+    const body = getVariableBody(`
+fn main() {
+    var x = !(1 < 2) || "a" + "b" == "ab";
+}
+`);
+    assert.ok(body?.kind == 'BoolLiteral');
+    assert.strictEqual(body.value, true);
+});
+
+test('operations with variables are not folded', () => {
+    const body = getVariableBody(`
+fn main() {
+    var x = y + 2 * 3;
+}
+`);
+    assert.ok(body?.kind == 'BinaryOp');
+    assert.strictEqual(body.right.kind, 'NumberLiteral');
+});
+
+test('folding power overflow fails', () => {
+    assert.throws(() => getMainBody(`
+fn main() {
+    var x = 2 ** 60;
+}
+`), /integer overflow/);
+});
+
+test('folding division by zero fails', () => {
+    assert.throws(() => getMainBody(`
+fn main() {
+    var x = 1 / 0;
+}
+`), /division by zero/);
+});
@@ -0,0 +1,180 @@
+import assert from 'assert';
+import { parse } from '../src/lib/syntax/parse.js';
+import { formatSource } from '../src/lib/syntax/format.js';
+import { defaultVersion, ProjectInfo } from '../src/lib/project-file.js';
+
+function format(sourceCode: string): string {
+    const projectInfo: ProjectInfo = {
+        filename: 'main.ug',
+        langVersion: defaultVersion,
+    };
+    return formatSource(parse(sourceCode, projectInfo.filename, projectInfo));
+}
+
+test('format in the canonical layout', () => {
+    const formatted = format(`
+struct P { x: number, y: number }
+var g=1;
+fn add(a:number,b:number):number{return a+b;}
+fn main(){var p=new P{x:1,y:2};if p.x==1{console.writeNum(add(p.x,p.y));}else if p.y==2{g+=1;}else{loop{break;}}}
+`);
+    const expected = `struct P {
+    x: number,
+    y: number,
+}
+
+var g = 1;
+
+fn add(a: number, b: number): number {
+    return a + b;
+}
+
+fn main() {
+    var p = new P { x: 1, y: 2 };
+    if p.x == 1 {
+        console.writeNum(add(p.x, p.y));
+    } else if p.y == 2 {
+        g += 1;
+    } else {
+        loop {
+            break;
+        }
+    }
+}
+`;
+    assert.strictEqual(formatted, expected);
+});
+
+test('format keeps the order of the operations', () => {
+    const formatted = format(`
+fn main() {
+    var a = (1 + 2) * 3;
+    var b = 1 - (2 - 3);
+    var c = 1 + 2 * 3;
+    var d = -(a + b);
+    var e = !(true && false) || true;
+    var f = 2 ** 3 ** 2;
+    var g = (2 ** 3) ** 2;
+}
+`);
+    const expected = `fn main() {
+    var a = (1 + 2) * 3;
+    var b = 1 - (2 - 3);
+    var c = 1 + 2 * 3;
+    var d = -(a + b);
+    var e = !(true && false) || true;
+    var f = 2 ** 3 ** 2;
+    var g = (2 ** 3) ** 2;
+}
+`;
+    assert.strictEqual(formatted, expected);
+});
+
+test('formatted code is stable', () => {
+    const first = format(`
+import "lib.ug";
+struct S { name: string }
+enum Color { Red, Green }
+const size = 3;
+fn apply(f: (number) => number, x: number = 1): number { f(x) }
+fn main() {
+    var s = new S { name: "a\\tb\\"c\\"" };
+    var c = '\\'';
+    var items = [1.5, 2.0];
+    for (var i = 0; i < size; i += 1) { items[0] = items[0] * 2.0; }
+    for name, value in s { console.write(name); }
+    repeat 3 { }
+    var i = 0;
+    while i < 3 { i += 1; }
+    var x = if i == 3 { 1 } else if i == 2 { 2 } else { 3 };
+    number.assertEq(apply(fn1, x), 2);
+    var t: (number,(bool, char)) = (1,(true, 'a'));
+    assert(t.1.0);
+    var m = match x { 1 => 10, -1 => 20, _ => 30 };
+    var cv = (x > 0 ? true : false) ? 1 + (i == 3 ? 1 : 2) : x < 0 ? -1 : 0;
+    var o: (number,bool)? = none;
+    fn twice(x: number): number { x * 2 }
+    var add = fn(y: number): number { x + y };
+    outer: while i < 3 { loop { continue outer; } }
+}
+fn fn1(x: number): number { return x + 1; }
+`);
+    assert.strictEqual(format(first), first);
+    assert.ok(first.includes('var s = new S { name: "a\\tb\\"c\\"" };\n'));
+    assert.ok(first.includes('    for (var i = 0; i < size; i += 1) {\n'));
+    assert.ok(first.includes('fn apply(f: (number) => number, x: number = 1): number {\n'));
+    assert.ok(first.includes('    var x = if i == 3 {\n        1\n    } else if i == 2 {\n'));
+    assert.ok(first.includes("    var t: (number, (bool, char)) = (1, (true, 'a'));\n"));
+    assert.ok(first.includes('enum Color {\n    Red,\n    Green,\n}\n'));
+    assert.ok(first.includes('    var m = match x {\n        1 => 10,\n        -1 => 20,\n        _ => 30,\n    };\n'));
+    assert.ok(first.includes('    var cv = (x > 0 ? true : false) ? 1 + (i == 3 ? 1 : 2) : x < 0 ? -1 : 0;\n'));
+    assert.ok(first.includes('    var o: (number, bool)? = none;\n'));
+    assert.ok(first.includes('    fn twice(x: number): number {\n        x * 2\n    }\n'));
+    assert.ok(first.includes('    var add = fn(y: number): number {\n        x + y\n    };\n'));
+    assert.ok(first.includes('    outer: while i < 3 {\n        loop {\n            continue outer;\n        }\n    }\n'));
+});
+
+test('trailing commas do not change the syntax tree', () => {
+    const withoutCommas = format(`
+struct P { x: number, y: number }
+fn add(x: number, y: number): (number, number) { (x + y, x - y) }
+fn apply(f: (number, number) => (number, number), x: number): number { f(x, 1).0 }
+fn main() {
+    var p = new P { x: 1, y: 2 };
+    var items = [add(1, 2), add(3, 4)];
+}
+`);
+    const withCommas = format(`
+struct P { x: number, y: number, }
+fn add(x: number, y: number,): (number, number,) { (x + y, x - y,) }
+fn apply(f: (number, number,) => (number, number), x: number,): number { f(x, 1,).0 }
+fn main() {
+    var p = new P { x: 1, y: 2, };
+    var items = [add(1, 2,), add(3, 4,),];
+}
+`);
+    assert.strictEqual(withCommas, withoutCommas);
+});
+
+test('format keeps the comments', () => {
+    const formatted = format(`
+// the point
+struct P {
+    x: number, // the x
+    y: number,
+}
+
+/* the entry point */
+fn main() {
+    // the first statement
+    var p = new P { x: 1, y: 2 };  // trailing
+    if p.x == 1 {
+        p.y = 3;
+        // the end of the if
+    }
+    // the end of main
+}
+// the end of file
+`);
+    const expected = `// the point
+struct P {
+    x: number, // the x
+    y: number,
+}
+
+/* the entry point */
+fn main() {
+    // the first statement
+    var p = new P { x: 1, y: 2 }; // trailing
+    if p.x == 1 {
+        p.y = 3;
+        // the end of the if
+    }
+    // the end of main
+}
+
+// the end of file
+`;
+    assert.strictEqual(formatted, expected);
+    assert.strictEqual(format(formatted), formatted);
+});
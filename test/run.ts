@@ -19,8 +19,8 @@ class RunTestError extends Error {
     }
 }
 
-function expectOk(sourceCode: string) {
-    const options: UguisuOptions = {};
+function expectOk(sourceCode: string, options?: UguisuOptions) {
+    options = options ?? {};
     const projectInfo: ProjectInfo = {
         filename: 'main.ug',
         langVersion: defaultVersion,
@@ -42,9 +42,9 @@ function expectOk(sourceCode: string) {
     run(sourceFile, runningEnv, options, projectInfo);
 }
 
-function expectErr(sourceCode: string) {
+function expectErr(sourceCode: string, options?: UguisuOptions) {
     try {
-        expectOk(sourceCode);
+        expectOk(sourceCode, options);
     } catch (err) {
         if (err instanceof RunTestError) {
             return;
@@ -55,6 +55,32 @@ function expectErr(sourceCode: string) {
 }
 
 describe('variable', () => {
+    test('global variable', () => expectOk(`
+    var g = 10;
+    var h: number = g * 2;
+    fn inner(): number {
+        g += 1;
+        return g + h;
+    }
+    fn outer(): number {
+        return inner();
+    }
+    fn main() {
+        number.assertEq(outer(), 31);
+        number.assertEq(g, 11);
+    }
+    `));
+
+    test('global variable initialized by function', () => expectOk(`
+    var g = init();
+    fn init(): number {
+        return 5;
+    }
+    fn main() {
+        number.assertEq(g, 5);
+    }
+    `));
+
     test('variable arith 1', () => expectOk(`
     fn main() {
         var x = 1;
@@ -98,6 +124,160 @@ describe('variable', () => {
         console.writeNum(x);
     }
     `));
+
+    test('const', () => expectOk(`
+    const size = 3;
+    fn main() {
+        const x: number = size * 2;
+        number.assertEq(x, 6);
+    }
+    `));
+
+    test('assign to const', () => expectErr(`
+    fn main() {
+        const x = 1;
+        x = 2;
+    }
+    `));
+
+    test('compound assign to global const', () => expectErr(`
+    const count = 0;
+    fn main() {
+        count += 1;
+    }
+    `));
+
+    test('assign to the field of const', () => expectErr(`
+    struct P {
+        x: number,
+    }
+    fn main() {
+        const p = new P { x: 1 };
+        p.x = 5;
+    }
+    `));
+
+    test('assign to the item of const', () => expectErr(`
+    fn main() {
+        const a = [1, 2];
+        a[0] = 1;
+    }
+    `));
+
+    test('const without initializer', () => {
+        assert.throws(() => expectOk(`
+        fn main() {
+            const x: number;
+        }
+        `), /const declaration requires an initializer/);
+    });
+
+    test('variable assigned in both branches', () => expectOk(`
+    fn main() {
+        var x;
+        if number.random(0, 2) == 0 {
+            x = 1;
+        } else {
+            x = 2;
+        }
+        assert(x == 1 || x == 2);
+    }
+    `));
+
+    test('variable assigned in one branch', () => expectErr(`
+    fn main() {
+        var x: number;
+        if number.random(0, 2) == 0 {
+            x = 1;
+        }
+        console.writeNum(x);
+    }
+    `));
+
+    test('variable assigned in the branch that does not return', () => expectOk(`
+    fn main() {
+        var x: number;
+        if number.random(0, 2) == 0 {
+            return;
+        } else {
+            x = 1;
+        }
+        number.assertEq(x, 1);
+    }
+    `));
+
+    test('variable assigned in a loop', () => expectErr(`
+    fn main() {
+        var x: number;
+        var i = 0;
+        while i < 3 {
+            x = i;
+            i += 1;
+        }
+        console.writeNum(x);
+    }
+    `));
+
+    test('variable assigned before break in the loop statement', () => expectOk(`
+    fn f(): number {
+        return 1;
+    }
+    fn main() {
+        var x: number;
+        loop {
+            x = f();
+            break;
+        }
+        number.assertEq(x, 1);
+    }
+    `));
+
+    test('variable assigned after break in the loop statement', () => expectErr(`
+    fn main() {
+        var x: number;
+        loop {
+            if number.random(0, 2) == 0 {
+                break;
+            }
+            x = 1;
+            break;
+        }
+        console.writeNum(x);
+    }
+    `));
+
+    test('compound assignment to the variable not assigned yet', () => expectErr(`
+    fn main() {
+        var x: number;
+        x += 1;
+    }
+    `));
+
+    test('reassign a value of another type', () => expectErr(`
+    fn main() {
+        var x = 1;
+        x = "a";
+    }
+    `));
+
+    test('reassign a value of another type to the delay defined variable', () => expectErr(`
+    fn main() {
+        var x;
+        x = 1;
+        x = "a";
+    }
+    `));
+
+    test('assign values of different types in the branches', () => expectErr(`
+    fn main() {
+        var x;
+        if number.random(0, 2) == 0 {
+            x = 1;
+        } else {
+            x = "a";
+        }
+    }
+    `));
 });
 
 // function declaration
@@ -129,6 +309,47 @@ fn main() {
 }
 `));
 
+test('assign to parameter', () => expectErr(`
+fn f(x: number) {
+    x = 1;
+}
+fn main() {
+    f(0);
+}
+`));
+
+test('assign to the field of parameter', () => expectErr(`
+struct Point {
+    x: number,
+}
+fn f(p: Point) {
+    p.x = 1;
+}
+fn main() {
+    f(new Point { x: 0 });
+}
+`));
+
+test('assign to the item of parameter', () => expectErr(`
+fn f(items: array) {
+    items[0] = 1;
+}
+fn main() {
+    f([0]);
+}
+`));
+
+test('parameter copied to a local variable', () => expectOk(`
+fn f(x: number): number {
+    var y = x;
+    y += 1;
+    return y;
+}
+fn main() {
+    number.assertEq(f(1), 2);
+}
+`));
+
 // function call
 
 test('call function 1', () => expectOk(`
@@ -168,6 +389,174 @@ fn main() {
 }
 `));
 
+// nested function
+
+test('nested function', () => expectOk(`
+fn main() {
+    fn square(x: number): number {
+        return x * x;
+    }
+    number.assertEq(square(3) + square(4), 25);
+}
+`));
+
+test('nested function recursion', () => expectOk(`
+fn main() {
+    fn fact(n: number): number {
+        if n == 0 {
+            return 1;
+        }
+        return n * fact(n - 1);
+    }
+    number.assertEq(fact(5), 120);
+}
+`));
+
+test('nested recursive function called after the enclosing function returned', () => expectOk(`
+fn makeFact(): (number) => number {
+    fn fact(n: number): number {
+        if n <= 1 {
+            return 1;
+        }
+        return n * fact(n - 1);
+    }
+    return fact;
+}
+fn main() {
+    var fact = makeFact();
+    number.assertEq(fact(5), 120);
+}
+`));
+
+test('nested function calls global function', () => expectOk(`
+const offset = 10;
+fn add(x: number, y: number): number {
+    return x + y;
+}
+fn main() {
+    fn addOffset(x: number): number {
+        return add(x, offset);
+    }
+    number.assertEq(addOffset(1), 11);
+}
+`));
+
+test('nested function is not visible from other functions', () => expectErr(`
+fn f() {
+    fn inner() { }
+    inner();
+}
+fn main() {
+    f();
+    inner();
+}
+`));
+
+test('nested function is not visible outside the block', () => expectErr(`
+fn main() {
+    if true {
+        fn inner() { }
+    }
+    inner();
+}
+`));
+
+test('nested function cannot use local variables of enclosing function', () => expectErr(`
+fn main() {
+    var x = 1;
+    fn f(): number {
+        return x;
+    }
+    f();
+}
+`));
+
+// lambda
+
+test('lambda', () => expectOk(`
+fn apply(f: (number) => number, x: number): number {
+    return f(x);
+}
+fn main() {
+    var square = fn(x: number): number { x * x };
+    number.assertEq(square(3), 9);
+    number.assertEq(apply(fn(x: number): number { x + 1 }, 2), 3);
+}
+`));
+
+test('lambda captures local variables by value', () => expectOk(`
+fn main() {
+    var offset = 10;
+    var add = fn(x: number): number { x + offset };
+    offset = 20;
+    number.assertEq(add(1), 11);
+}
+`));
+
+test('lambda captures parameters', () => expectOk(`
+fn makeAdder(n: number): (number) => number {
+    return fn(x: number): number { x + n };
+}
+fn main() {
+    var add2 = makeAdder(2);
+    var add3 = makeAdder(3);
+    number.assertEq(add2(1), 3);
+    number.assertEq(add3(1), 4);
+}
+`));
+
+test('lambda captures copy of struct', () => expectOk(`
+struct Point {
+    x: number,
+}
+fn main() {
+    var p = new Point { x: 1 };
+    var getX = fn(): number { p.x };
+    p.x = 2;
+    number.assertEq(getX(), 1);
+}
+`));
+
+test('nested lambda', () => expectOk(`
+fn main() {
+    var a = 1;
+    var f = fn(b: number): number {
+        var g = fn(c: number): number { a + b + c };
+        g(3)
+    };
+    number.assertEq(f(2), 6);
+}
+`));
+
+test('lambda uses global variables', () => expectOk(`
+var count = 0;
+fn main() {
+    var increment = fn() { count += 1; };
+    increment();
+    increment();
+    number.assertEq(count, 2);
+}
+`));
+
+test('assign to captured variable', () => expectErr(`
+fn main() {
+    var x = 1;
+    var f = fn() { x = 2; };
+    f();
+}
+`));
+
+test('assign to the field of captured struct', () => expectErr(`
+struct Point {
+    x: number,
+}
+fn main() {
+    var p = new Point { x: 1 };
+    var move = fn() { p.x += 1; };
+    move();
+}
+`));
+
 // function params
 
 test('calc with func param', () => expectOk(`
@@ -181,6 +570,34 @@ fn main() {
 }
 `));
 
+test('default param values', () => expectOk(`
+fn greet(name: string, greeting: string = "hi", text: string = greeting + " " + name): string {
+    text
+}
+fn main() {
+    string.assertEq(greet("a"), "hi a");
+    string.assertEq(greet("a", "hello"), "hello a");
+    string.assertEq(greet("a", "hello", "bye"), "bye");
+}
+`));
+
+test('default param value must match the param type', () => expectErr(`
+fn f(x: number = "a") { }
+fn main() { }
+`));
+
+test('param without default value after default param', () => expectErr(`
+fn f(x: number = 1, y: number) { }
+fn main() { }
+`));
+
+test('omitted argument without default value', () => expectErr(`
+fn f(x: number, y: number = 1) { }
+fn main() {
+    f();
+}
+`));
+
 // return function
 
 test('return function', () => expectOk(`
@@ -198,71 +615,510 @@ fn main() {
 }
 `));
 
-// if + if-else + if-elseif-else + bool literal
-
-test('if empty', () => expectOk(`
+test('return without value from void function', () => expectOk(`
+fn f(x: number) {
+    if x == 0 {
+        return;
+    }
+    console.writeNum(x);
+}
 fn main() {
-    if true { }
-    else if true { }
-    else if true { }
-    else { }
+    f(0);
 }
 `));
 
-test('if', () => expectOk(`
+test('return value from void function', () => expectErr(`
+fn f() {
+    return 5;
+}
 fn main() {
-    var x = 0;
-    if true {
-        x = 1;
-    }
-    number.assertEq(x, 1);
-    if false {
-        x = 2;
-    }
-    number.assertEq(x, 1);
+    f();
 }
 `));
 
-test('if else', () => expectOk(`
+test('return value of mismatched type', () => expectErr(`
+fn f(): number {
+    return true;
+}
 fn main() {
-    var x = 1;
-    if true {
-        x = 2;
-    } else {
-        x = 3;
-    }
-    number.assertEq(x, 2);
-    if false {
-        x = 4;
-    } else {
-        x = 5;
-    }
-    number.assertEq(x, 5);
+    f();
 }
 `));
 
-test('if elseif else', () => expectOk(`
+test('return without value from non-void function', () => {
+    assert.throws(() => expectOk(`
+    fn f(): number {
+        return;
+    }
+    fn main() {
+        f();
+    }
+    `), /function `f` must return a value of type `number`\./);
+});
+
+test('function without return', () => expectErr(`
+fn f(): number {
+}
+fn main() {
+    f();
+}
+`));
+
+test('function returning on some paths', () => {
+    assert.throws(() => expectOk(`
+    fn f(x: number): number {
+        if x == 0 {
+            return 1;
+        }
+    }
+    fn main() {
+        f(1);
+    }
+    `), /function `f` does not return a value on all paths\./);
+});
+
+test('function returning from infinite loop', () => expectOk(`
+fn f(x: number): number {
+    var i = 0;
+    loop {
+        if i == x {
+            return i;
+        }
+        i += 1;
+    }
+}
+fn main() {
+    number.assertEq(f(3), 3);
+}
+`));
+
+test('function returning after loop with break', () => expectErr(`
+fn f(): number {
+    loop {
+        break;
+    }
+}
+fn main() {
+    f();
+}
+`));
+
+test('arguments are evaluated from left to right', () => {
+    const output: string[] = [];
+    expectOk(`
+    fn record(x: number): number {
+        console.writeNum(x);
+        return x;
+    }
+    fn join(x: number, y: number, z: number): number {
+        console.writeNum(0);
+        return x * 100 + y * 10 + z;
+    }
+    fn main() {
+        number.assertEq(join(record(1), record(2), record(3)), 123);
+    }
+    `, {
+        stdout(str) {
+            output.push(str);
+        },
+    });
+    assert.deepStrictEqual(output, ['1', '2', '3', '0']);
+});
+
+// block value
+
+test('block value as return value', () => expectOk(`
+fn calc(x: number): number {
+    var y = x + 1;
+    y * 2
+}
+fn main() {
+    number.assertEq(calc(1), 4);
+}
+`));
+
+test('block value of if branches', () => expectOk(`
+fn choose(x: number): number {
+    if x == 0 {
+        var y = 1;
+        y + 2
+    } else if x == 1 {
+        10
+    } else {
+        20
+    }
+}
+fn main() {
+    number.assertEq(choose(0), 3);
+    number.assertEq(choose(1), 10);
+    number.assertEq(choose(2), 20);
+}
+`));
+
+test('block value with mismatched type', () => expectErr(`
+fn calc(): number {
+    true
+}
+fn main() {
+    calc();
+}
+`));
+
+test('block value in the middle of a block', () => expectErr(`
+fn calc(x: number): number {
+    if x == 0 { 1 } else { 2 }
+    return 3;
+}
+fn main() {
+    calc(0);
+}
+`));
+
+test('block value of if branches with mismatched types', () => expectErr(`
+fn calc(x: number): number {
+    if x == 0 { 1 } else { true }
+}
+fn main() {
+    calc(0);
+}
+`));
+
+// block scope
+
+test('loop-local and function-local variables', () => expectOk(`
+fn main() {
+    var total = 0;
+    var i = 0;
+    loop {
+        if i == 3 {
+            break;
+        }
+        var x = i * 10;
+        if x > 0 {
+            var y = x + 1;
+            total += y;
+        }
+        i += 1;
+    }
+    number.assertEq(total, 32);
+    number.assertEq(i, 3);
+}
+`));
+
+test('loop-local variable is initialized in each iteration', () => expectOk(`
+fn main() {
+    var count = 0;
+    repeat 3 {
+        var x = 0;
+        x += 1;
+        count += x;
+    }
+    number.assertEq(count, 3);
+}
+`));
+
+test('variable shadowed in the inner block', () => expectOk(`
 fn main() {
     var x = 1;
     if true {
-        x = 2;
-    } else if false {
-        x = 3;
+        var x = 2;
+        number.assertEq(x, 2);
+    }
+    number.assertEq(x, 1);
+}
+`));
+
+test('block-local variable is not visible outside the block', () => expectErr(`
+fn main() {
+    if true {
+        var y = 1;
+    }
+    var z = y;
+}
+`));
+
+test('variable shadowed in the loop block', () => expectOk(`
+fn main() {
+    var x = 1;
+    var i = 0;
+    while i < 2 {
+        var x = i + 10;
+        number.assertEq(x, i + 10);
+        i += 1;
+    }
+    number.assertEq(x, 1);
+}
+`));
+
+test('loop-local variable is not visible outside the loop', () => expectErr(`
+fn main() {
+    loop {
+        var y = 1;
+        break;
+    }
+    var z = y;
+}
+`));
+
+// unreachable code
+
+test('code after return', () => expectErr(`
+fn main() {
+    return;
+    var x = 1;
+}
+`));
+
+test('code after break', () => expectErr(`
+fn main() {
+    loop {
+        break;
+        var x = 1;
+    }
+}
+`));
+
+test('code after if with both branches returning', () => expectErr(`
+fn f(x: number): number {
+    if x == 0 {
+        return 1;
     } else {
-        x = 4;
+        return 2;
     }
-    number.assertEq(x, 2);
-    if false {
-        x = 2;
-    } else if true {
-        x = 3;
+    return 3;
+}
+fn main() {
+    f(1);
+}
+`));
+
+test('code after if with one branch returning', () => expectOk(`
+fn f(x: number): number {
+    if x == 0 {
+        return 1;
+    }
+    return 2;
+}
+fn main() {
+    number.assertEq(f(1), 2);
+}
+`));
+
+// repeat
+
+test('repeat', () => expectOk(`
+fn main() {
+    var count = 0;
+    repeat 4 {
+        count += 1;
+    }
+    number.assertEq(count, 4);
+    repeat 0 - 1 {
+        count += 1;
+    }
+    number.assertEq(count, 4);
+}
+`));
+
+test('repeat with non-number count', () => expectErr(`
+fn main() {
+    repeat true { }
+}
+`));
+
+// while
+
+test('while', () => expectOk(`
+fn main() {
+    var i = 0;
+    var sum = 0;
+    while i < 5 {
+        sum += i;
+        i += 1;
+    }
+    number.assertEq(sum, 10);
+    while true {
+        break;
+    }
+    while false {
+        number.assertEq(0, 1);
+    }
+}
+`));
+
+test('while with non-bool condition', () => expectErr(`
+fn main() {
+    while 1 { }
+}
+`));
+
+// for
+
+test('for', () => expectOk(`
+fn main() {
+    var sum = 0;
+    for (var i = 0; i < 5; i += 1) {
+        sum += i;
+    }
+    number.assertEq(sum, 10);
+    var j = 0;
+    for (; ; j += 1) {
+        if j == 3 {
+            break;
+        }
+    }
+    number.assertEq(j, 3);
+}
+`));
+
+test('for variable is scoped to the loop', () => expectErr(`
+fn main() {
+    for (var i = 0; i < 5; i += 1) { }
+    var x = i;
+}
+`));
+
+test('for with non-bool condition', () => expectErr(`
+fn main() {
+    for (var i = 0; i; i += 1) { }
+}
+`));
+
+// if expression
+
+test('if expression', () => expectOk(`
+fn main() {
+    var a = true;
+    var m = if a { 1 } else { 2 };
+    number.assertEq(m, 1);
+    var n = if !a {
+        1
+    } else if a {
+        var x = 10;
+        x * 2
     } else {
-        x = 4;
+        3
+    };
+    number.assertEq(n, 20);
+    number.assertEq(if a { 5 } else { 6 } + 1, 6);
+}
+`));
+
+test('if expression without else', () => {
+    assert.throws(() => expectOk(`
+    fn main() {
+        var m = if true { 1 };
     }
+    `), /else/);
+});
+
+test('if expression with mismatched types', () => expectErr(`
+fn main() {
+    var m = if true { 1 } else { "a" };
+}
+`));
+
+// conditional operator
+
+test('conditional operator', () => expectOk(`
+fn sign(x: number): number {
+    x > 0 ? 1 : x < 0 ? -1 : 0
+}
+fn fail(): number {
+    assert(false);
+    return 0;
+}
+fn main() {
+    number.assertEq(sign(5), 1);
+    number.assertEq(sign(-5), -1);
+    number.assertEq(sign(0), 0);
+    var a = true;
+    var x = a || false ? 1 + 2 : 4;
     number.assertEq(x, 3);
+
+    // only the selected branch is evaluated
+    var y = a ? 1 : fail();
+    number.assertEq(y, 1);
+}
+`));
+
+test('conditional operator requires a bool condition', () => expectErr(`
+fn main() {
+    var x = 1 ? 2 : 3;
+}
+`));
+
+test('conditional operator with mismatched types', () => expectErr(`
+fn main() {
+    var x = true ? 1 : "a";
+}
+`));
+
+// if + if-else + if-elseif-else + bool literal
+
+test('if empty', () => expectOk(`
+fn main() {
+    if true { }
+    else if true { }
+    else if true { }
+    else { }
+}
+`));
+
+test('if', () => expectOk(`
+fn main() {
+    var x = 0;
+    if true {
+        x = 1;
+    }
+    number.assertEq(x, 1);
     if false {
+        x = 2;
+    }
+    number.assertEq(x, 1);
+}
+`));
+
+test('if else', () => expectOk(`
+fn main() {
+    var x = 1;
+    if true {
+        x = 2;
+    } else {
         x = 3;
-    } else if false {
+    }
+    number.assertEq(x, 2);
+    if false {
+        x = 4;
+    } else {
+        x = 5;
+    }
+    number.assertEq(x, 5);
+}
+`));
+
+test('if elseif else', () => expectOk(`
+fn main() {
+    var x = 1;
+    if true {
+        x = 2;
+    } else if false {
+        x = 3;
+    } else {
+        x = 4;
+    }
+    number.assertEq(x, 2);
+    if false {
+        x = 2;
+    } else if true {
+        x = 3;
+    } else {
+        x = 4;
+    }
+    number.assertEq(x, 3);
+    if false {
+        x = 3;
+    } else if false {
         x = 4;
     } else {
         x = 5;
@@ -299,153 +1155,1056 @@ fn main() {
 }
 `));
 
-test('logical op 2', () => expectOk(`
+test('logical op 2', () => expectOk(`
+fn main() {
+    var x = 1;
+    if false && true || true && true {
+        x = 2;
+    }
+    number.assertEq(x, 2);
+}
+`));
+
+test('logical op 3', () => expectOk(`
+fn main() {
+    var x = 1;
+    if !false {
+        x = 2;
+    }
+    number.assertEq(x, 2);
+}
+`));
+
+test('logical op short-circuit', () => {
+    const output: string[] = [];
+    expectOk(`
+    fn check(x: bool): bool {
+        console.write("evaluated");
+        return x;
+    }
+    fn main() {
+        assert(!(false && check(true)));
+        assert(true || check(true));
+        assert(true && check(true));
+        assert(!(false || check(false)));
+    }
+    `, {
+        stdout(str) {
+            output.push(str);
+        },
+    });
+    assert.deepStrictEqual(output, ['evaluated', 'evaluated']);
+});
+
+// arithmetic unary operation
+
+test('unary minus', () => expectOk(`
+fn main() {
+    var x = 5;
+    number.assertEq(-x, 0 - 5);
+    number.assertEq(1 - -2, 3);
+    number.assertEq(-(1 + 2) * 2, 0 - 6);
+    number.assertEq(- -x, 5);
+}
+`));
+
+test('unary minus for float', () => expectOk(`
+fn main() {
+    var x = -1.5;
+    float.assertNear(x, 0.0 - 1.5, 0.001);
+}
+`));
+
+test('unary minus for bool', () => expectErr(`
+fn main() {
+    var x = -true;
+}
+`));
+
+test('number literal with base prefix', () => expectOk(`
+fn main() {
+    number.assertEq(0xFF, 255);
+    number.assertEq(0o17, 15);
+    number.assertEq(0b1010, 10);
+    number.assertEq(-0x10 + 0b11 * 2, 0 - 10);
+}
+`));
+
+test('number literal with digit separators', () => expectOk(`
+fn main() {
+    number.assertEq(1_000_000, 1000000);
+    number.assertEq(0b1111_0000, 240);
+    float.assertNear(1_000.5, 1000.5, 0.001);
+}
+`));
+
+// arithmetic comparison
+
+test('arith comp 1', () => expectOk(`
+fn main() {
+    var x = 1;
+    if x == 1 {
+        x = 2;
+    }
+    number.assertEq(x, 2);
+    if x == 1 {
+        x = 3;
+    }
+    number.assertEq(x, 2);
+}
+`));
+
+test('arith comp 2', () => expectOk(`
+fn main() {
+    var x = 1;
+    if 1 + 2 == 3 {
+        x = 2;
+    }
+    number.assertEq(x, 2);
+    if 2 - 1 == 0 {
+        x = 3;
+    }
+    number.assertEq(x, 2);
+}
+`));
+
+// loop
+
+test('loop statement', () => expectOk(`
+fn main() {
+    var i = 0;
+    var x = 1;
+    loop {
+        if i == 10 { break; }
+        x = x * 2;
+        i = i + 1;
+    }
+    number.assertEq(x, 1024);
+}
+`));
+
+// break
+
+test('break no target', () => expectErr(`
+fn main() {
+    break;
+}
+`));
+
+test('break no target nested', () => expectErr(`
+fn main() {
+    var x = true;
+    if x {
+        break;
+    }
+}
+`));
+
+test('break with label', () => expectOk(`
+fn main() {
+    var count = 0;
+    outer: loop {
+        loop {
+            count += 1;
+            if count == 3 {
+                break outer;
+            }
+        }
+    }
+    number.assertEq(count, 3);
+}
+`));
+
+test('break with label of while and for', () => expectOk(`
+fn main() {
+    var found = 0;
+    var i = 1;
+    outer: while i < 10 {
+        for (var j = 1; j < 10; j += 1) {
+            if i * j == 12 {
+                found = i * 10 + j;
+                break outer;
+            }
+        }
+        i += 1;
+    }
+    number.assertEq(found, 26);
+}
+`));
+
+test('break with unknown label', () => expectErr(`
+fn main() {
+    outer: loop {
+        break inner;
+    }
+}
+`));
+
+test('break with label of enclosing function', () => expectErr(`
+fn main() {
+    outer: loop {
+        fn f() {
+            loop {
+                break outer;
+            }
+        }
+        break;
+    }
+}
+`));
+
+test('duplicated label', () => expectErr(`
+fn main() {
+    outer: loop {
+        outer: loop {
+            break outer;
+        }
+        break;
+    }
+}
+`));
+
+// continue
+
+test('continue', () => expectOk(`
+fn main() {
+    var sum = 0;
+    for (var i = 0; i < 10; i += 1) {
+        if i % 2 == 0 {
+            continue;
+        }
+        sum += i;
+    }
+    number.assertEq(sum, 25);
+}
+`));
+
+test('continue with label', () => expectOk(`
+fn main() {
+    var count = 0;
+    var i = 0;
+    outer: repeat 3 {
+        i += 1;
+        var j = 0;
+        while true {
+            j += 1;
+            if j > i {
+                continue outer;
+            }
+            count += 1;
+        }
+    }
+    number.assertEq(count, 6);
+}
+`));
+
+test('continue no target', () => expectErr(`
+fn main() {
+    continue;
+}
+`));
+
+// assignment
+
+test('assignment', () => expectOk(`
+fn main() {
+    var x = 0;
+    number.assertEq(x, 0);
+    x = 1;
+    number.assertEq(x, 1);
+    x = 2;
+    number.assertEq(x, 2);
+}
+`));
+
+test('assignment_modes', () => expectOk(`
+fn main() {
+    var x = 0;
+    number.assertEq(x, 0);
+    x += 10;
+    number.assertEq(x, 10);
+    x -= 2;
+    number.assertEq(x, 8);
+    x *= 2;
+    number.assertEq(x, 16);
+    x /= 4;
+    number.assertEq(x, 4);
+}
+`));
+
+test('float', () => expectOk(`
+fn main() {
+    var x = 3.14;
+    var y: float = 2.0;
+    float.assertNear(x * y, 6.28, 0.001);
+    float.assertNear(x + y, 5.14, 0.001);
+    float.assertNear(x / y, 1.57, 0.001);
+    var z = x;
+    z -= 0.14;
+    float.assertNear(z, 3.0, 0.001);
+    if x <= y {
+        number.assertEq(0, 1);
+    }
+    number.assertEq(float.toNumber(x), 3);
+    float.assertNear(float.fromNumber(2), y, 0.0);
+    string.assertEq(float.toString(y), "2.0");
+}
+`));
+
+test('division by zero', () => {
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = 0;
+        var y = 1 / x;
+    }
+    `), /division by zero/);
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = 0;
+        var y = 1 % x;
+    }
+    `), /modulo by zero/);
+});
+
+test('division by zero in compound assignment', () => {
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = 1;
+        x /= 0;
+    }
+    `), /division by zero/);
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = 1;
+        x %= 0;
+    }
+    `), /modulo by zero/);
+});
+
+test('power operator', () => expectOk(`
+fn main() {
+    number.assertEq(2 ** 10, 1024);
+    number.assertEq(5 ** 0, 1);
+    number.assertEq(2 ** 3 ** 2, 512);
+    number.assertEq((2 ** 3) ** 2, 64);
+    number.assertEq(2 * 3 ** 2, 18);
+    var x = 3;
+    x **= 2;
+    number.assertEq(x, 9);
+}
+`));
+
+test('power with negative exponent', () => {
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = -1;
+        var y = 2 ** x;
+    }
+    `), /negative exponent/);
+});
+
+test('power overflow', () => {
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = 2;
+        x **= 60;
+    }
+    `), /integer overflow/);
+});
+
+test('power operator is not defined for float', () => expectErr(`
+fn main() {
+    var x = 2.0 ** 2.0;
+}
+`));
+
+test('float and number are distinct types', () => expectErr(`
+fn main() {
+    var x = 1.5 + 1;
+}
+`));
+
+test('assign number to float variable', () => expectErr(`
+fn main() {
+    var x: float = 1;
+}
+`));
+
+test('string concatenation', () => expectOk(`
+fn main() {
+    var x = "abc" + "def";
+    string.assertEq(x, "abcdef");
+    string.assertEq(x + "" + "g", "abcdefg");
+}
+`));
+
+test('string compound assignment', () => expectOk(`
+struct Message {
+    text: string,
+}
+fn main() {
+    var x = "abc";
+    x += "def";
+    x += "";
+    string.assertEq(x, "abcdef");
+    var message = new Message { text: "hello" };
+    message.text += " world";
+    string.assertEq(message.text, "hello world");
+}
+`));
+
+test('string compound assignment with number', () => expectErr(`
+fn main() {
+    var x = "abc";
+    x += 1;
+}
+`));
+
+test('string compound assignment except addition', () => expectErr(`
+fn main() {
+    var x = "abc";
+    x -= "c";
+}
+`));
+
+test('string comparison', () => expectOk(`
+fn main() {
+    if !("abc" == "abc") { number.assertEq(0, 1); }
+    if "abc" != "abc" { number.assertEq(0, 1); }
+    if !("a" < "b") { number.assertEq(0, 1); }
+    if !("ab" < "b") { number.assertEq(0, 1); }
+    if !("a" <= "a") { number.assertEq(0, 1); }
+    if !("b" > "abc") { number.assertEq(0, 1); }
+    if "a" >= "ab" { number.assertEq(0, 1); }
+}
+`));
+
+test('string and number cannot be added', () => expectErr(`
+fn main() {
+    var x = "abc" + 1;
+}
+`));
+
+test('string subtraction', () => expectErr(`
+fn main() {
+    var x = "abc" - "c";
+}
+`));
+
+test('string multiplication', () => expectErr(`
+fn main() {
+    var x = "abc" * 2;
+}
+`));
+
+test('string compound assignment', () => expectErr(`
+fn main() {
+    var x = "abc";
+    x -= "c";
+}
+`));
+
+// struct
+
+test('struct', () => expectOk(`
+struct A {
+    value: number,
+}
+fn main() {
+    var x = new A {
+        value: 1,
+    };
+    number.assertEq(x.value, 1);
+    x.value = 2;
+    number.assertEq(x.value, 2);
+}
+`));
+
+test('assign to the nested struct field', () => expectOk(`
+struct Point {
+    x: number,
+    y: number,
+}
+struct Line {
+    start: Point,
+    end: Point,
+}
+fn main() {
+    var line = new Line {
+        start: new Point { x: 0, y: 0 },
+        end: new Point { x: 1, y: 1 },
+    };
+    line.end.x = 5;
+    line.start = new Point { x: 2, y: 3 };
+    number.assertEq(line.end.x, 5);
+    number.assertEq(line.end.y, 1);
+    number.assertEq(line.start.y, 3);
+}
+`));
+
+test('assign a value of another type to the struct field', () => expectErr(`
+struct A {
+    value: number,
+}
+fn main() {
+    var x = new A { value: 1 };
+    x.value = "a";
+}
+`));
+
+test('struct field shorthand', () => expectOk(`
+struct Point {
+    x: number,
+    y: number,
+}
+fn main() {
+    var x = 1;
+    var y = 2;
+    var p1 = new Point { x, y };
+    var p2 = new Point { x: x, y: y };
+    number.assertEq(p1.x, p2.x);
+    number.assertEq(p1.y, p2.y);
+}
+`));
+
+test('struct field shorthand with unknown identifier', () => expectErr(`
+struct Point {
+    x: number,
+    y: number,
+}
+fn main() {
+    var x = 1;
+    var p = new Point { x, y };
+}
+`));
+
+test('struct field iteration', () => expectOk(`
+struct Point {
+    x: number,
+    y: number,
+}
+fn main() {
+    var p = new Point { x: 1, y: 2 };
+    var names = "";
+    var values = 0;
+    for name, value in p {
+        names = string.concat(names, name);
+        values = values * 10 + value;
+    }
+    string.assertEq(names, "xy");
+    number.assertEq(values, 12);
+}
+`));
+
+test('struct field iteration in the declared order', () => expectOk(`
+struct Point {
+    x: number,
+    y: number,
+}
+fn main() {
+    var p = new Point { y: 2, x: 1 };
+    var names = "";
+    for name, value in p {
+        names = string.concat(names, name);
+    }
+    string.assertEq(names, "xy");
+}
+`));
+
+test('struct field iteration evaluates the target once', () => expectOk(`
+struct Point {
+    x: number,
+    y: number,
+}
+var count = 0;
+fn make(): Point {
+    count += 1;
+    return new Point { x: 1, y: 2 };
+}
+fn main() {
+    var sum = 0;
+    for name, value in make() {
+        sum += value;
+    }
+    number.assertEq(sum, 3);
+    number.assertEq(count, 1);
+}
+`));
+
+test('nested struct field iteration', () => expectOk(`
+struct Point {
+    x: number,
+    y: number,
+}
+struct Line {
+    begin: Point,
+    end: Point,
+}
+fn main() {
+    var line = new Line { begin: new Point { x: 1, y: 2 }, end: new Point { x: 3, y: 4 } };
+    var names = "";
+    outer: for name, point in line {
+        for field, value in point {
+            if value == 3 {
+                break outer;
+            }
+            names = string.concat(names, string.concat(name, field));
+        }
+    }
+    string.assertEq(names, "beginxbeginy");
+}
+`));
+
+test('struct field iteration checks each field type', () => expectErr(`
+struct User {
+    id: number,
+    name: string,
+}
+fn main() {
+    var user = new User { id: 1, name: "alice" };
+    for name, value in user {
+        number.assertEq(value, 1);
+    }
+}
+`));
+
+test('struct field iteration over non-struct', () => expectErr(`
+fn main() {
+    var x = 1;
+    for name, value in x { }
+}
+`));
+
+test('recursive struct', () => expectErr(`
+struct A {
+    b: B,
+}
+struct B {
+    a: A,
+}
+fn main() { }
+`));
+
+test('access to unknown field', () => {
+    assert.throws(() => expectOk(`
+    struct Point {
+        x: number,
+    }
+    fn main() {
+        var p = new Point { x: 1 };
+        var y = p.y;
+    }
+    `), /unknown field `y` for type `Point`\./);
+});
+
+test('field access on non-struct value', () => {
+    assert.throws(() => expectOk(`
+    fn f() { }
+    fn main() {
+        var x = f.y;
+    }
+    `), /type `\(\) => void` has no fields\./);
+});
+
+test('struct with mismatched field type', () => {
+    assert.throws(() => expectOk(`
+    struct Point {
+        x: number,
+        y: number,
+    }
+    fn main() {
+        var p = new Point { x: 1, y: "2" };
+    }
+    `), /type mismatched for field `y`\. expected `number`, found `string` \(7:38\)/);
+});
+
+test('struct with missing and unknown fields', () => {
+    assert.throws(() => expectOk(`
+    struct Point {
+        x: number,
+        y: number,
+        z: number,
+    }
+    fn main() {
+        var p = new Point { x: 1, w: 2 };
+    }
+    `), /invalid fields for struct `Point`\. missing: `y`, `z`\. unknown: `w`\./);
+});
+
+test('struct is copied on assignment', () => expectOk(`
+struct Point {
+    x: number,
+    y: number,
+}
+fn move(p: Point) {
+    // the parameter cannot be assigned, so the variable refers to it
+    var q = share(p);
+    q.x = 10;
+}
+fn main() {
+    var a = new Point { x: 1, y: 2 };
+    var b = a;
+    b.x = 3;
+    number.assertEq(a.x, 1);
+    number.assertEq(b.x, 3);
+    move(a);
+    number.assertEq(a.x, 1);
+}
+`));
+
+test('struct is shared by share()', () => expectOk(`
+struct Point {
+    x: number,
+    y: number,
+}
+fn move(p: Point) {
+    // the parameter cannot be assigned, so the variable refers to it
+    var q = share(p);
+    q.x = 10;
+}
+fn main() {
+    var a = new Point { x: 1, y: 2 };
+    var b = share(a);
+    b.x = 3;
+    number.assertEq(a.x, 3);
+    move(share(a));
+    number.assertEq(b.x, 10);
+}
+`));
+
+// array
+
+test('array', () => expectOk(`
+fn main() {
+    var x = [1, 2];
+    number.assertEq(x[0], 1);
+    number.assertEq(x[1], 2);
+    x[0] = 3;
+    number.assertEq(x[0], 3);
+    x[1] = 4;
+    number.assertEq(x[1], 4);
+
+    // operations
+    array.insert(x, 2, 5);
+    number.assertEq(array.count(x), 3);
+    number.assertEq(x[2], 5);
+    array.removeAt(x, 2);
+    number.assertEq(array.count(x), 2);
+}
+`));
+
+test('array builtins modify the array in place', () => expectOk(`
+fn push(x: array, value: number) {
+    array.add(x, value);
+}
+fn main() {
+    var x = [1];
+    array.add(x, 2);
+    number.assertEq(array.count(x), 2);
+    number.assertEq(x[1], 2);
+
+    // the array passed to the user function is copied
+    push(x, 3);
+    number.assertEq(array.count(x), 2);
+
+    // share the array to modify it in the user function
+    push(share(x), 3);
+    number.assertEq(array.count(x), 3);
+    number.assertEq(x[2], 3);
+}
+`));
+
+test('array elements must have the same type', () => expectErr(`
+fn main() {
+    var x = [1, true];
+}
+`));
+
+test('array item has the type of the elements', () => expectOk(`
+fn sum(items: array): number {
+    var total = 0;
+    for (var i = 0; i < array.count(items); i += 1) {
+        total += items[i];
+    }
+    return total;
+}
+fn main() {
+    var x = [1, 2];
+    var y: number = x[0] + x[1];
+    x[0] = 10;
+    number.assertEq(sum(x), 12);
+}
+`));
+
+test('assign a value of another type to the array item', () => expectErr(`
+fn main() {
+    var a = [1];
+    a[0] = "s";
+}
+`));
+
+test('read the array item as another type', () => expectErr(`
+fn main() {
+    var a = [1];
+    var s: string = a[0];
+}
+`));
+
+test('array index must be a number', () => expectErr(`
+fn main() {
+    var x = [1, 2];
+    var y = x[true];
+}
+`));
+
+test('index access to non-array', () => expectErr(`
+fn main() {
+    var x = 1;
+    var y = x[0];
+}
+`));
+
+test('array index out of bounds', () => {
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = [1, 2];
+        var y = x[2];
+    }
+    `), /index out of bounds: the count is 2 but the index is 2/);
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = [1, 2];
+        var y = x[-1];
+    }
+    `), /index out of bounds/);
+});
+
+// tuple
+
+test('tuple', () => expectOk(`
+fn divmod(x: number, y: number): (number, number) {
+    return ((x - x % y) / y, x % y);
+}
+fn main() {
+    var x = divmod(7, 2);
+    number.assertEq(x.0, 3);
+    number.assertEq(x.1, 1);
+    x.0 = 5;
+    number.assertEq(x.0, 5);
+
+    // nested tuple
+    var y: ((number, bool), string) = ((1, true), "a");
+    number.assertEq(y.0.0, 1);
+    assert(y.0.1);
+    string.assertEq(y.1, "a");
+}
+`));
+
+test('tuple elements must match the type', () => expectErr(`
+fn main() {
+    var x: (number, bool) = (1, 2);
+}
+`));
+
+test('tuple index out of range', () => expectErr(`
+fn main() {
+    var x = (1, 2);
+    var y = x.2;
+}
+`));
+
+test('tuple type requires two or more elements', () => {
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x: (number) = 1;
+    }
+    `), /a tuple type requires two or more elements/);
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x = (1,);
+    }
+    `), /a tuple requires two or more items/);
+});
+
+// enum
+
+test('enum', () => expectOk(`
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+fn next(x: Color): Color {
+    if x == Color.Red {
+        Color.Green
+    } else if x == Color.Green {
+        Color.Blue
+    } else {
+        Color.Red
+    }
+}
+fn main() {
+    var x = Color.Red;
+    assert(x == Color.Red);
+    assert(x != Color.Blue);
+    x = next(x);
+    assert(x == Color.Green);
+    x = next(next(x));
+    assert(x == Color.Red);
+}
+`));
+
+test('enum variant must exist', () => expectErr(`
+enum Color { Red, Green }
 fn main() {
-    var x = 1;
-    if false && true || true && true {
-        x = 2;
-    }
-    number.assertEq(x, 2);
+    var x = Color.Blue;
 }
 `));
 
-test('logical op 3', () => expectOk(`
+test('enum values of different enums cannot be compared', () => expectErr(`
+enum Color { Red, Green }
+enum Shape { Circle, Square }
 fn main() {
-    var x = 1;
-    if !false {
-        x = 2;
-    }
-    number.assertEq(x, 2);
+    var x = Color.Red == Shape.Circle;
 }
 `));
 
-// arithmetic comparison
-
-test('arith comp 1', () => expectOk(`
+test('enum variant cannot be assigned', () => expectErr(`
+enum Color { Red, Green }
 fn main() {
-    var x = 1;
-    if x == 1 {
-        x = 2;
-    }
-    number.assertEq(x, 2);
-    if x == 1 {
-        x = 3;
-    }
-    number.assertEq(x, 2);
+    Color.Red = Color.Green;
 }
 `));
 
-test('arith comp 2', () => expectOk(`
+test('enum cannot be used as a value', () => expectErr(`
+enum Color { Red, Green }
 fn main() {
-    var x = 1;
-    if 1 + 2 == 3 {
-        x = 2;
-    }
-    number.assertEq(x, 2);
-    if 2 - 1 == 0 {
-        x = 3;
-    }
-    number.assertEq(x, 2);
+    var c: Color = Color;
 }
 `));
 
-// loop
+test('enum variants must be unique', () => expectErr(`
+enum Color { Red, Red }
+fn main() { }
+`));
 
-test('loop statement', () => expectOk(`
-fn main() {
-    var i = 0;
-    var x = 1;
-    loop {
-        if i == 10 { break; }
-        x = x * 2;
-        i = i + 1;
+// match
+
+test('match expression', () => expectOk(`
+enum Color { Red, Green, Blue }
+fn toNumber(x: Color): number {
+    match x {
+        Red => 1,
+        Green => 2,
+        Blue => 3,
+    }
+}
+fn name(x: number): string {
+    match x {
+        -1 => "minus one",
+        0 => "zero",
+        _ => "other",
     }
-    number.assertEq(x, 1024);
+}
+fn main() {
+    number.assertEq(toNumber(Color.Red), 1);
+    number.assertEq(toNumber(Color.Blue), 3);
+    var y = match Color.Green { Red => 10, _ => 20 };
+    number.assertEq(y, 20);
+    string.assertEq(name(0), "zero");
+    string.assertEq(name(-1), "minus one");
+    string.assertEq(name(5), "other");
 }
 `));
 
-// break
-
-test('break no target', () => expectErr(`
+test('match on enum must handle all variants', () => expectErr(`
+enum Color { Red, Green, Blue }
 fn main() {
-    break;
+    var x = match Color.Red { Red => 1, Green => 2 };
 }
 `));
 
-test('break no target nested', () => expectErr(`
+test('match on number requires the wildcard arm', () => expectErr(`
 fn main() {
-    var x = true;
-    if x {
-        break;
-    }
+    var x = match 1 { 1 => 1, 2 => 2 };
 }
 `));
 
-// assignment
-
-test('assignment', () => expectOk(`
+test('match arms must yield the same type', () => expectErr(`
 fn main() {
-    var x = 0;
-    number.assertEq(x, 0);
-    x = 1;
-    number.assertEq(x, 1);
-    x = 2;
-    number.assertEq(x, 2);
+    var x = match 1 { 1 => 1, _ => true };
 }
 `));
 
-test('assignment_modes', () => expectOk(`
+test('match pattern must be a variant of the enum', () => expectErr(`
+enum Color { Red, Green }
 fn main() {
-    var x = 0;
-    number.assertEq(x, 0);
-    x += 10;
-    number.assertEq(x, 10);
-    x -= 2;
-    number.assertEq(x, 8);
-    x *= 2;
-    number.assertEq(x, 16);
-    x /= 4;
-    number.assertEq(x, 4);
+    var x = match Color.Red { Red => 1, Blue => 2, _ => 3 };
 }
 `));
 
-// struct
+// optional
 
-test('struct', () => expectOk(`
-struct A {
-    value: number,
+test('optional', () => expectOk(`
+fn find(items: array, target: number): number? {
+    for (var i = 0; i < array.count(items); i += 1) {
+        if items[i] == target {
+            return some(i);
+        }
+    }
+    return none;
 }
 fn main() {
-    var x = new A {
-        value: 1,
-    };
-    number.assertEq(x.value, 1);
-    x.value = 2;
-    number.assertEq(x.value, 2);
+    var items = [3, 5, 8];
+    var found = find(items, 5);
+    assert(isSome(found));
+    number.assertEq(unwrap(found), 1);
+    var missing: number? = find(items, 4);
+    assert(!isSome(missing));
+    assert(missing == none);
+    assert(found == some(1));
+    assert(found != some(2));
+    missing = some(10);
+    number.assertEq(unwrap(missing) + 1, 11);
 }
 `));
 
-// array
+test('optional requires some to wrap the value', () => expectErr(`
+fn main() {
+    var x: number? = 1;
+}
+`));
 
-test('array', () => expectOk(`
+test('optional cannot be used as the inner value', () => expectErr(`
 fn main() {
-    var x = [1, 2];
-    number.assertEq(x[0], 1);
-    number.assertEq(x[1], 2);
-    x[0] = 3;
-    number.assertEq(x[0], 3);
-    x[1] = 4;
-    number.assertEq(x[1], 4);
+    var x: number? = some(1);
+    var y = x + 1;
+}
+`));
 
-    // operations
-    array.insert(x, 2, 5);
-    number.assertEq(array.count(x), 3);
-    number.assertEq(x[2], 5);
-    array.removeAt(x, 2);
-    number.assertEq(array.count(x), 2);
+test('unwrap requires optional', () => expectErr(`
+fn main() {
+    var x = unwrap(1);
 }
 `));
 
+test('local function named after the builtin has no rules of the builtin', () => expectOk(`
+fn main() {
+    var some = fn(x: number): number { x + 1 };
+    var y: number = some(1);
+    number.assertEq(y, 2);
+}
+`));
+
+test('unwrap on none', () => {
+    assert.throws(() => expectOk(`
+    fn main() {
+        var x: number? = none;
+        unwrap(x);
+    }
+    `), /called `unwrap` on `none`/);
+});
+
 // function
 
 describe('function', () => {
@@ -455,14 +2214,43 @@ describe('function', () => {
     }
     `));
 
-    // test('return', () => runTest(`
-    // fn f(): () => void {
-    //     return f;
-    // }
-    // fn main() {
-    //     f();
-    // }
-    // `));
+    test('return', () => expectOk(`
+    fn f(): () => void {
+        return main;
+    }
+    fn main() {
+        var g = f();
+    }
+    `));
+
+    test('function type label', () => expectOk(`
+    struct Calc {
+        op: (number, number) => number,
+    }
+    fn add(x: number, y: number): number {
+        return x + y;
+    }
+    fn apply(f: (number, number) => number, x: number, y: number): number {
+        return f(x, y);
+    }
+    fn main() {
+        number.assertEq(apply(add, 1, 2), 3);
+        number.assertEq(apply(number.max, 1, 2), 2);
+        var f: (number, number) => number = add;
+        number.assertEq(f(3, 4), 7);
+        var calc = new Calc { op: add };
+        number.assertEq(calc.op(5, 6), 11);
+    }
+    `));
+
+    test('function type label mismatched', () => expectErr(`
+    fn apply(f: (number) => number, x: number): number {
+        return f(x);
+    }
+    fn main() {
+        apply(main, 1);
+    }
+    `));
 
     test('compare', () => expectOk(`
     fn main() {
@@ -470,6 +2258,51 @@ describe('function', () => {
     }
     `));
 
+    test('indirect call', () => expectOk(`
+    fn add(x: number, y: number): number {
+        return x + y;
+    }
+    fn sub(x: number, y: number): number {
+        return x - y;
+    }
+    fn main() {
+        var f = add;
+        number.assertEq(f(1, 2), 3);
+        f = sub;
+        number.assertEq(f(1, 2), 0 - 1);
+    }
+    `));
+
+    test('indirect call with incorrect argument count', () => expectErr(`
+    fn add(x: number, y: number): number {
+        return x + y;
+    }
+    fn main() {
+        var f = add;
+        f(1);
+    }
+    `));
+
+    test('indirect call with incorrect argument type', () => expectErr(`
+    fn add(x: number, y: number): number {
+        return x + y;
+    }
+    fn main() {
+        var f = add;
+        f(1, true);
+    }
+    `));
+
+    test('assign function of another signature', () => expectErr(`
+    fn add(x: number, y: number): number {
+        return x + y;
+    }
+    fn main() {
+        var f = add;
+        f = main;
+    }
+    `));
+
     test('expr statement', () => expectOk(`
     fn main() {
         main;
@@ -563,6 +2396,47 @@ fn main() {
 }
 `));
 
+test('escape sequence', () => expectOk(`
+fn main() {
+    number.assertEq(array.count(string.toChars(\"line1\\nline2\")), 11);
+    number.assertEq(array.count(string.toChars(\"\\\"\\\\\\'\")), 3);
+    string.assertEq(\"\\u{3042}\", \"あ\");
+    var c: char = '\\'';
+}
+`));
+
+test('unknown escape sequence', () => expectErr(`
+fn main() {
+    var s: string = \"abc\\q\";
+}
+`));
+
+// syntax errors
+
+test('all syntax errors are reported', () => {
+    const projectInfo: ProjectInfo = {
+        filename: 'main.ug',
+        langVersion: defaultVersion,
+    };
+    assert.throws(() => parse(`
+fn main() {
+    var x = ;
+    var y = 1;
+    y = ;
+}
+fn f( {
+}
+fn g() { }
+`, projectInfo.filename, projectInfo), (err: Error) => {
+        const lines = err.message.split('\n');
+        assert.strictEqual(lines.length, 3);
+        assert.match(lines[0], /\(3:13\)$/);
+        assert.match(lines[1], /\(5:9\)$/);
+        assert.match(lines[2], /\(7:7\)$/);
+        return true;
+    });
+});
+
 // other examples
 
 test('example', () => expectOk(`
@@ -578,3 +2452,310 @@ fn main() {
     number.assertEq(calc(value), 1024);
 }
 `));
+
+// builtins
+
+describe('builtins', () => {
+    test('getEnv', () => expectOk(`
+    fn main() {
+        string.assertEq(getEnv("FOO"), "foo value");
+        string.assertEq(getEnv("BAR"), "");
+    }
+    `, { env: { FOO: 'foo value' } }));
+
+    test('random and clock with the fixed config', () => {
+        const sourceCode = `
+        fn main() {
+            console.writeNum(number.random(0, 1000));
+            console.writeNum(number.random(0, 1000));
+            console.writeNum(number.random(0, 1000));
+            console.writeNum(getUnixtime());
+        }
+        `;
+        function runWithConfig(): string[] {
+            const output: string[] = [];
+            expectOk(sourceCode, {
+                stdout(str) {
+                    output.push(str);
+                },
+                randomSeed: 1234,
+                clock: () => 1680000000000,
+            });
+            return output;
+        }
+        const first = runWithConfig();
+        const second = runWithConfig();
+        assert.deepStrictEqual(second, first);
+        assert.strictEqual(first[3], '1680000000');
+    });
+
+    test('print', () => {
+        const output: string[] = [];
+        expectOk(`
+        fn main() {
+            print(1 + 2);
+            print(1.0);
+            print(1 < 2);
+            print('a');
+            print("hello");
+            var x = "world";
+            print(x);
+        }
+        `, {
+            stdout(str) {
+                output.push(str);
+            },
+        });
+        assert.deepStrictEqual(output, ['3', '1.0', 'true', 'a', 'hello', 'world']);
+    });
+
+    test('print with the value that is not printable', () => expectErr(`
+    fn main() {
+        print([1, 2]);
+    }
+    `));
+
+    test('assert', () => {
+        expectOk(`
+        fn main() {
+            assert(1 + 1 == 2);
+            assert(true);
+        }
+        `);
+        assert.throws(() => expectOk(`
+        fn main() {
+            assert(1 > 2);
+        }
+        `), /assertion failed\. \(3:13\)/);
+    });
+
+    test('assertEq for any type', () => expectOk(`
+    struct Point {
+        x: number,
+        y: number,
+    }
+    fn main() {
+        assertEq(1 + 1, 2);
+        assertEq(true, 1 < 2);
+        assertEq("abc", "abc");
+        assertEq('a', 'a');
+        assertEq(new Point { x: 1, y: 2 }, new Point { x: 1, y: 2 });
+        assertEq([1, 2], [1, 2]);
+    }
+    `));
+
+    test('assertEq with different values', () => {
+        assert.throws(() => expectOk(`
+        fn main() {
+            assertEq("abc", "xyz");
+        }
+        `), /assertion error\. expected `"xyz"`, actual `"abc"`\./);
+        assert.throws(() => expectOk(`
+        fn main() {
+            assertEq([1, 2], [1, 3]);
+        }
+        `), /expected `\[1, 3\]`, actual `\[1, 2\]`/);
+    });
+
+    test('assertEq with different types', () => expectErr(`
+    fn main() {
+        assertEq(1, "1");
+    }
+    `));
+
+    test('assert requires bool', () => expectErr(`
+    fn main() {
+        assert(1);
+    }
+    `));
+
+    test('console.writeBool', () => {
+        const output: string[] = [];
+        expectOk(`
+        fn main() {
+            console.writeBool(true);
+            console.writeBool(1 > 2);
+            console.write("text");
+        }
+        `, {
+            stdout(str) {
+                output.push(str);
+            },
+        });
+        assert.deepStrictEqual(output, ['true', 'false', 'text']);
+    });
+
+    test('maximum recursion depth', () => {
+        const sourceCode = `
+        fn f(n: number): number {
+            if n == 0 {
+                return 0;
+            }
+            return f(n - 1) + 1;
+        }
+        fn main() {
+            number.assertEq(f(50), 50);
+        }
+        `;
+        expectOk(sourceCode, { maxCallDepth: 100 });
+        assert.throws(() => expectOk(sourceCode, { maxCallDepth: 10 }), /maximum recursion depth exceeded/);
+    });
+
+    test('math functions', () => expectOk(`
+    fn main() {
+        number.assertEq(number.abs(-3), 3);
+        number.assertEq(number.abs(3), 3);
+        number.assertEq(number.min(1, 2), 1);
+        number.assertEq(number.max(1, 2), 2);
+        number.assertEq(number.pow(2, 10), 1024);
+        number.assertEq(number.pow(5, 0), 1);
+    }
+    `));
+
+    test('pow with negative exponent', () => {
+        assert.throws(() => expectOk(`
+        fn main() {
+            number.pow(2, -1);
+        }
+        `), /negative exponent/);
+    });
+
+    test('pow that overflows', () => {
+        assert.throws(() => expectOk(`
+        fn main() {
+            number.pow(2, 60);
+        }
+        `), /integer overflow/);
+    });
+
+    test('pow with the fraction part', () => expectOk(`
+    fn main() {
+        number.assertEq(number.pow(5 / 2, 2), 25 / 4);
+        number.assertEq((7 / 2) ** 2, 49 / 4);
+    }
+    `));
+
+    test('pow with non-integer exponent', () => {
+        assert.throws(() => expectOk(`
+        fn main() {
+            number.pow(4, 1 / 2);
+        }
+        `), /non-integer exponent/);
+    });
+
+    test('panic stops the execution', () => {
+        const output: string[] = [];
+        assert.throws(() => expectOk(`
+        fn main() {
+            console.write("before");
+            panic("something went wrong");
+            console.write("after");
+        }
+        `, { stdout(str) { output.push(str); } }), /panic: something went wrong \(4:13\)/);
+        assert.deepStrictEqual(output, ['before']);
+    });
+
+    test('panic counts as the end of the function', () => expectOk(`
+    fn sign(x: number): number {
+        if x > 0 {
+            return 1;
+        } else if x < 0 {
+            return -1;
+        } else {
+            panic("zero has no sign");
+        }
+    }
+    fn main() {
+        number.assertEq(sign(-5), -1);
+    }
+    `));
+
+    test('parse number', () => expectOk(`
+    fn main() {
+        number.assertEq(unwrap(number.parse("123")), 123);
+        number.assertEq(unwrap(number.parse("-45")), -45);
+        number.assertEq(unwrap(number.parse("+7")), 7);
+        number.assertEq(unwrap(number.parse("007")), 7);
+    }
+    `));
+
+    test('parse malformed number', () => {
+        for (const source of ['abc', '', ' 1', '1.5', '0x10', '1e3', '99999999999999999']) {
+            expectOk(`
+            fn main() {
+                assert(!isSome(number.parse("${source}")));
+            }
+            `);
+        }
+    });
+
+    test('convert values to string', () => expectOk(`
+    fn main() {
+        var x = 42;
+        string.assertEq("x=" + number.toString(x), "x=42");
+        string.assertEq(number.toString(-1234567), "-1234567");
+        string.assertEq(bool.toString(true), "true");
+        string.assertEq(bool.toString(1 > 2), "false");
+    }
+    `));
+
+    test('string length and substring', () => expectOk(`
+    fn main() {
+        number.assertEq(string.length(""), 0);
+        number.assertEq(string.length("abc"), 3);
+        number.assertEq(string.length("あいう"), 3);
+        number.assertEq(string.length("😀a"), 2);
+        string.assertEq(string.substring("abcde", 1, 3), "bc");
+        string.assertEq(string.substring("あいうえお", 2, 5), "うえお");
+        string.assertEq(string.substring("😀a😀", 1, 3), "a😀");
+        string.assertEq(string.substring("abc", 1, 1), "");
+    }
+    `));
+
+    test('substring out of range', () => {
+        assert.throws(() => expectOk(`
+        fn main() {
+            string.substring("abc", 1, 4);
+        }
+        `), /index out of bounds: the length is 3 but the range is 1..4/);
+        assert.throws(() => expectOk(`
+        fn main() {
+            string.substring("abc", -1, 2);
+        }
+        `), /index out of bounds/);
+        assert.throws(() => expectOk(`
+        fn main() {
+            string.substring("abc", 2, 1);
+        }
+        `), /the start index is greater than the end index: 2 > 1/);
+    });
+
+    test('substring with non-integer index', () => {
+        assert.throws(() => expectOk(`
+        fn main() {
+            string.substring("abcde", 1 / 2, 3);
+        }
+        `), /the index is not an integer: the range is 0.5..3/);
+        assert.throws(() => expectOk(`
+        fn main() {
+            string.substring("abcde", 1, 7 / 2);
+        }
+        `), /the index is not an integer/);
+    });
+
+    test('assertNear within epsilon', () => expectOk(`
+    fn main() {
+        number.assertNear(100, 102, 2);
+        number.assertNear(102, 100, 2);
+        number.assertNear(5, 5, 0);
+    }
+    `));
+
+    test('assertNear outside epsilon', () => {
+        assert.throws(() => expectOk(`
+        fn main() {
+            number.assertNear(100, 103, 2);
+        }
+        `), /expected `103` within `2`, actual `100`/);
+    });
+});
@@ -25,6 +25,46 @@ test('eof', () => {
     assertToken(s, Token.EOF);
 });
 
+test('arrow', () => {
+    const input = '= => ==';
+    const s = setupTest(input);
+    assertToken(s, Token.Assign);
+    s.next();
+    assertToken(s, Token.Arrow);
+    s.next();
+    assertToken(s, Token.Eq);
+    s.next();
+    assertToken(s, Token.EOF);
+});
+
+test('asterisk', () => {
+    const input = '* *= ** **=';
+    const s = setupTest(input);
+    assertToken(s, Token.Asterisk);
+    s.next();
+    assertToken(s, Token.MultAssign);
+    s.next();
+    assertToken(s, Token.Asterisk2);
+    s.next();
+    assertToken(s, Token.PowAssign);
+    s.next();
+    assertToken(s, Token.EOF);
+});
+
+test('keyword', () => {
+    const input = 'var const fn enum';
+    const s = setupTest(input);
+    assertToken(s, Token.Var);
+    s.next();
+    assertToken(s, Token.Const);
+    s.next();
+    assertToken(s, Token.Fn);
+    s.next();
+    assertToken(s, Token.Enum);
+    s.next();
+    assertToken(s, Token.EOF);
+});
+
 test('identifier', () => {
     const input = 'aaa123 xyz456';
     const s = setupTest(input);
@@ -48,6 +88,47 @@ describe('literal token', () => {
         assertToken(s, Token.EOF);
     });
 
+    test('number literal with base prefix', () => {
+        const input = '0xFF 0o17 0b1010';
+        const s = setupTest(input);
+        assertLiteralToken(s, 'number', '0xFF');
+        s.next();
+        assertLiteralToken(s, 'number', '0o17');
+        s.next();
+        assertLiteralToken(s, 'number', '0b1010');
+        s.next();
+        assertToken(s, Token.EOF);
+        assert.throws(() => setupTest('0b12'), /invalid digit `2` in the binary literal/);
+        assert.throws(() => setupTest('0x'), /the hexadecimal literal has no digits/);
+    });
+
+    test('number literal with digit separators', () => {
+        const input = '1_000_000 0xFF_FF 3.141_592';
+        const s = setupTest(input);
+        assertLiteralToken(s, 'number', '1000000');
+        s.next();
+        assertLiteralToken(s, 'number', '0xFFFF');
+        s.next();
+        assertLiteralToken(s, 'float', '3.141592');
+        s.next();
+        assertToken(s, Token.EOF);
+        assert.throws(() => setupTest('1_'), /the digit separator `_` must be placed between digits/);
+        assert.throws(() => setupTest('1__0'), /the digit separator `_` must be placed between digits/);
+        assert.throws(() => setupTest('0x_1'), /the digit separator `_` must be placed between digits/);
+    });
+
+    test('float literal', () => {
+        const input = '3.14 1.';
+        const s = setupTest(input);
+        assertLiteralToken(s, 'float', '3.14');
+        s.next();
+        assertLiteralToken(s, 'number', '1');
+        s.next();
+        assertToken(s, Token.Dot);
+        s.next();
+        assertToken(s, Token.EOF);
+    });
+
     test('string literal', () => {
         const input = '"abc123" "xyz456"';
         const s = setupTest(input);
@@ -58,6 +139,20 @@ describe('literal token', () => {
         assertToken(s, Token.EOF);
     });
 
+    test('string literal with escape sequences', () => {
+        const input = '"a\\nb\\t\\"c\\\\" "\\u{3042}"';
+        const s = setupTest(input);
+        assertLiteralToken(s, 'string', 'a\nb\t"c\\');
+        s.next();
+        assertLiteralToken(s, 'string', 'あ');
+        s.next();
+        assertToken(s, Token.EOF);
+    });
+
+    test('unknown escape sequence', () => {
+        assert.throws(() => setupTest('"a\\qb"'), /unknown escape sequence `\\q`/);
+    });
+
     test('bool literal', () => {
         const input = 'true false';
         const s = setupTest(input);
@@ -102,4 +197,20 @@ describe('token sequence', () => {
         s.next();
         assertToken(s, Token.EOF);
     });
+
+    test('tuple item access', () => {
+        const input = 'x.0.1';
+        const s = setupTest(input);
+        assertToken(s, Token.Ident);
+        s.next();
+        assertToken(s, Token.Dot);
+        s.next();
+        assertLiteralToken(s, 'number', '0');
+        s.next();
+        assertToken(s, Token.Dot);
+        s.next();
+        assertLiteralToken(s, 'number', '1');
+        s.next();
+        assertToken(s, Token.EOF);
+    });
 });